@@ -0,0 +1,196 @@
+//! Benchmarks of the letter count representation through the search pipeline
+//!
+//! `CountSet` packs two counts per byte by default; run these benchmarks once
+//! as is and once with `--features unpacked-counts` to compare with one byte
+//! per count:
+//!
+//! ```sh
+//! cargo bench --bench count_set -- --save-baseline packed
+//! cargo bench --bench count_set --features unpacked-counts -- --baseline packed
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::collections::HashMap;
+use word_puzzle_searcher::compare::random_racks;
+use word_puzzle_searcher::count::CountSet;
+use word_puzzle_searcher::dict::Dictionary;
+use word_puzzle_searcher::format::{read_dict, write_dict};
+use word_puzzle_searcher::index::{Indexes, LetterIndex, SortedIndex};
+use word_puzzle_searcher::rack::{Rack, RackOptions};
+use word_puzzle_searcher::sample;
+use word_puzzle_searcher::search::{SearchQuery, SortOrder};
+
+// the sample words, then about 250,000 random words of 2 to 12 letters with
+// the letter frequencies of a Scrabble bag, the same on every run
+fn fixture() -> Dictionary {
+    let mut dict = sample::dictionary();
+    for size in 2..=12 {
+        dict.extend(random_racks(size as u64, 25_000, size));
+    }
+
+    dict
+}
+
+fn bench_search(c: &mut Criterion) {
+    let dict = fixture();
+    let racks = random_racks(0, 16, 7)
+        .into_iter()
+        .map(|letters| Rack::parse(&format!("{}?", letters), &RackOptions::default()).unwrap())
+        .collect::<Vec<_>>();
+
+    // `CountSet::contains` through `Rack::contains_word`, on every entry
+    c.bench_function("search", |b| {
+        b.iter(|| {
+            for rack in racks.iter() {
+                let query = SearchQuery::new(rack.clone());
+                black_box(query.search(&dict, &Indexes::default()));
+            }
+        })
+    });
+
+    // without blanks, most entries have a letter missing from the rack,
+    // rejected by `Rack::contains_word` from the letter presence masks
+    let exact_racks = random_racks(0, 16, 7)
+        .into_iter()
+        .map(|letters| Rack::parse(&letters, &RackOptions::default()).unwrap())
+        .collect::<Vec<_>>();
+    c.bench_function("search without blanks", |b| {
+        b.iter(|| {
+            for rack in exact_racks.iter() {
+                let query = SearchQuery::new(rack.clone());
+                black_box(query.search(&dict, &Indexes::default()));
+            }
+        })
+    });
+
+    let mut query = SearchQuery::new(racks[0].clone());
+    query.require = CountSet::from_word("e").unwrap();
+    c.bench_function("search with required letters", |b| {
+        b.iter(|| black_box(query.search(&dict, &Indexes::default())))
+    });
+
+    // `--require q`: a rare letter, which the letter index narrows down to a
+    // few entries, against a scan of every entry
+    query.require = CountSet::from_word("q").unwrap();
+    c.bench_function("search requiring q by a scan", |b| {
+        b.iter(|| black_box(query.search(&dict, &Indexes::default())))
+    });
+    let indexes = Indexes {
+        letters: Some(LetterIndex::build(&dict)),
+        ..Indexes::default()
+    };
+    c.bench_function("search requiring q by the letter index", |b| {
+        b.iter(|| black_box(query.search(&dict, &indexes)))
+    });
+
+    // `--starts-with`: the sorted index binary searches the entries with the
+    // prefix, against a scan of every entry
+    let mut query = SearchQuery::new(racks[0].clone());
+    query.starts_with = "re".to_owned();
+    c.bench_function("search starting with re by a scan", |b| {
+        b.iter(|| black_box(query.search(&dict, &Indexes::default())))
+    });
+    let indexes = Indexes {
+        sorted: Some(SortedIndex::build(&dict)),
+        ..Indexes::default()
+    };
+    c.bench_function("search starting with re by the sorted index", |b| {
+        b.iter(|| black_box(query.search(&dict, &indexes)))
+    });
+}
+
+fn bench_top(c: &mut Criterion) {
+    let dict = fixture();
+    // blanks make for tens of thousands of matches
+    let query = SearchQuery::new(Rack::parse("etaoin????", &RackOptions::default()).unwrap());
+
+    c.bench_function("top 20 by sorting every match", |b| {
+        b.iter(|| {
+            let mut results = query.search(&dict, &Indexes::default());
+            results.sort(SortOrder::Consumed);
+            results.page(0, Some(20));
+            black_box(results)
+        })
+    });
+    c.bench_function("top 20 by heaps", |b| {
+        b.iter(|| black_box(query.search_top(&dict, &Indexes::default(), SortOrder::Consumed, 20)))
+    });
+}
+
+fn bench_batch(c: &mut Criterion) {
+    let dict = fixture();
+    let racks = random_racks(2, 64, 7)
+        .iter()
+        .map(|letters| CountSet::from_word(letters).unwrap())
+        .collect::<Vec<_>>();
+    let mut query = SearchQuery::new(Rack::from_counts(&CountSet::default()));
+    query.min_length = 3;
+
+    c.bench_function("64 racks by a loop of searches", |b| {
+        b.iter(|| {
+            for counts in racks.iter() {
+                query.rack = Rack::from_counts(counts);
+                black_box(query.search(&dict, &Indexes::default()));
+            }
+        })
+    });
+    c.bench_function("64 racks by search_batch", |b| {
+        b.iter(|| black_box(query.search_batch(&dict, &racks)))
+    });
+}
+
+fn bench_hashing(c: &mut Criterion) {
+    let dict = fixture();
+
+    // anagram grouping, as in dedup by letter counts
+    c.bench_function("group by count set", |b| {
+        b.iter(|| {
+            let mut groups = HashMap::<&CountSet, usize>::new();
+            for set in dict.word_count().values() {
+                *groups.entry(set).or_default() += 1;
+            }
+            black_box(groups.len())
+        })
+    });
+
+    c.bench_function("letter index", |b| {
+        b.iter(|| black_box(LetterIndex::build(&dict)))
+    });
+}
+
+fn bench_build(c: &mut Criterion) {
+    let words = random_racks(1, 250_000, 8);
+
+    // `CountSet::from_word` for every word, serially then in parallel
+    c.bench_function("add words", |b| {
+        b.iter(|| black_box(words.iter().collect::<Dictionary>()))
+    });
+    c.bench_function("par_extend words", |b| {
+        b.iter(|| {
+            let mut dict = Dictionary::new();
+            dict.par_extend(words.clone()).unwrap();
+            black_box(dict)
+        })
+    });
+}
+
+fn bench_load(c: &mut Criterion) {
+    let mut file = Vec::new();
+    write_dict(&fixture(), &mut file).unwrap();
+
+    // dictionary files are always packed, so this includes any conversion
+    c.bench_function("read dictionary", |b| {
+        b.iter(|| black_box(read_dict(&mut file.as_slice()).unwrap()))
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_search,
+    bench_top,
+    bench_batch,
+    bench_hashing,
+    bench_build,
+    bench_load
+);
+criterion_main!(benches);
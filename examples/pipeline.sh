@@ -0,0 +1,23 @@
+#!/bin/sh
+# Builds the sample dictionary with the command line, then chains a few
+# subcommands; only data goes to the standard output, so it pipes cleanly.
+#
+#     sh examples/pipeline.sh
+set -eu
+
+cd "$(dirname "$0")/.."
+dict="${TMPDIR:-/tmp}/word-puzzle-searcher-sample.dict"
+run() { cargo run --quiet -- "$@"; }
+
+run generate -o "$dict" --sorted-index tests/data/words.txt
+run info -d "$dict"
+
+# the seven-letter anagrams of a rack, then its number of words per length
+run search -d "$dict" retains -m 7
+run search -d "$dict" retains --histogram
+
+# words with a double letter, made from a rack with a blank
+run search -d "$dict" 'bollet?' --pattern '*XX*'
+
+# the two-letter words, as flashcards
+run shortlist -d "$dict" -f csv | grep '^2,'
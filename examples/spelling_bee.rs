@@ -0,0 +1,47 @@
+//! Solves a Spelling Bee puzzle with the sample dictionary.
+//!
+//! Words have at least 4 letters, all among the 7 letters of the puzzle
+//! (which can be reused), and contain the center letter, given first.
+//!
+//! ```text
+//! cargo run --example spelling_bee -- tinesra
+//! ```
+
+use rayon::prelude::*;
+use word_puzzle_searcher::sample;
+
+fn main() {
+    let letters = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| String::from("tinesra"))
+        .to_ascii_lowercase();
+    let center = match letters.chars().next() {
+        Some(center) => center,
+        None => {
+            eprintln!("usage: spelling_bee LETTERS (center letter first)");
+            std::process::exit(1);
+        }
+    };
+
+    // a letter count of zero means the letter isn't in the puzzle
+    let allowed = |i: usize| letters.contains((b'a' + i as u8) as char);
+    let dict = sample::dictionary();
+    let mut words = dict
+        .par_iter()
+        .filter(|entry| entry.len() >= 4 && entry.word.contains(center))
+        .filter(|entry| {
+            entry
+                .count_set
+                .iter()
+                .enumerate()
+                .all(|(i, count)| count == 0 || allowed(i))
+        })
+        .map(|entry| entry.word)
+        .collect::<Vec<_>>();
+    words.sort_unstable();
+
+    for word in words {
+        let pangram = letters.chars().all(|letter| word.contains(letter));
+        println!("{}{}", word, if pangram { " (pangram)" } else { "" });
+    }
+}
@@ -0,0 +1,250 @@
+//! Dictionaries stored as entries of tar and zip archives, for shipping
+//! several dictionaries in one file
+//!
+//! An entry is named by appending it to the archive path after a colon, as in
+//! `bundle.zip:en.dict`.
+
+use std::error::Error;
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+/// Archive formats, recognized by their extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Tar,
+    Zip,
+}
+
+impl ArchiveFormat {
+    fn from_path(path: &OsStr) -> Option<Self> {
+        let path = path.as_encoded_bytes().to_ascii_lowercase();
+        if path.ends_with(b".tar") {
+            Some(ArchiveFormat::Tar)
+        } else if path.ends_with(b".zip") {
+            Some(ArchiveFormat::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+/// An entry of an archive, such as `bundle.zip:en.dict`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchivePath {
+    pub archive: PathBuf,
+    pub entry: String,
+    pub format: ArchiveFormat,
+}
+
+impl ArchivePath {
+    /// Splits a path into an archive and an entry, at the first colon
+    /// following a known archive extension.
+    ///
+    /// Returns `None` if the path isn't in an archive, or is an existing file
+    /// (whose name happens to contain such a colon).
+    pub fn parse(path: &Path) -> Option<Self> {
+        if path.is_file() {
+            return None;
+        }
+
+        // the archive path may be any `OsStr`, but not the entry name
+        let bytes = path.as_os_str().as_encoded_bytes();
+        (0..bytes.len())
+            .filter(|&i| bytes[i] == b':')
+            .find_map(|i| {
+                // SAFETY: the bytes are split at an ASCII character
+                let archive = unsafe { OsStr::from_encoded_bytes_unchecked(&bytes[..i]) };
+                let entry = std::str::from_utf8(&bytes[(i + 1)..]).ok()?;
+                let format = ArchiveFormat::from_path(archive)?;
+                Some(Self {
+                    archive: PathBuf::from(archive),
+                    entry: entry.to_owned(),
+                    format,
+                })
+            })
+    }
+}
+
+impl fmt::Display for ArchivePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.archive.display(), self.entry)
+    }
+}
+
+/// Error type returned when reading an archive entry
+#[derive(Debug)]
+pub enum ArchiveError {
+    /// The archive has no entry of this name
+    MissingEntry(ArchivePath),
+    /// Malformed zip archive
+    ZipError(zip::result::ZipError),
+    /// Error returned by an I/O operation
+    IoError(io::Error),
+}
+
+impl fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ArchiveError::*;
+
+        match self {
+            MissingEntry(path) => {
+                write!(f, "no entry {:?} in archive {:?}", path.entry, path.archive)
+            }
+            ZipError(err) => fmt::Display::fmt(err, f),
+            IoError(err) => fmt::Display::fmt(err, f),
+        }
+    }
+}
+
+impl Error for ArchiveError {}
+
+impl From<io::Error> for ArchiveError {
+    fn from(err: io::Error) -> Self {
+        ArchiveError::IoError(err)
+    }
+}
+
+impl From<zip::result::ZipError> for ArchiveError {
+    fn from(err: zip::result::ZipError) -> Self {
+        ArchiveError::ZipError(err)
+    }
+}
+
+/// Opens an archive entry and passes its content stream to `f`, which can be
+/// any reader function, such as `read_dict`.
+///
+/// The stream borrows the archive, so it can't outlive the call.
+pub fn read_entry<T, F>(path: &ArchivePath, f: F) -> Result<T, ArchiveError>
+where
+    F: FnOnce(&mut dyn Read) -> T,
+{
+    let file = BufReader::new(File::open(&path.archive)?);
+
+    match path.format {
+        ArchiveFormat::Tar => {
+            let mut archive = tar::Archive::new(file);
+            for entry in archive.entries()? {
+                let mut entry = entry?;
+                if entry.path()?.as_ref() == Path::new(&path.entry) {
+                    return Ok(f(&mut entry));
+                }
+            }
+            Err(ArchiveError::MissingEntry(path.clone()))
+        }
+        ArchiveFormat::Zip => {
+            let mut archive = zip::ZipArchive::new(file)?;
+            let mut entry = match archive.by_name(&path.entry) {
+                Err(zip::result::ZipError::FileNotFound) => {
+                    return Err(ArchiveError::MissingEntry(path.clone()))
+                }
+                entry => entry?,
+            };
+            Ok(f(&mut entry))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dict::Dictionary;
+    use crate::format::{read_dict, write_dict};
+    use std::io::Write;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "word-puzzle-searcher-archive-{}-{}",
+            std::process::id(),
+            name
+        ))
+    }
+
+    fn dict_bytes() -> Vec<u8> {
+        let dict = ["stain", "satin", "ant"].iter().collect::<Dictionary>();
+        let mut bytes = Vec::new();
+        write_dict(&dict, &mut bytes).unwrap();
+        bytes
+    }
+
+    fn entry_path(archive: &Path, entry: &str) -> ArchivePath {
+        ArchivePath::parse(Path::new(&format!("{}:{}", archive.display(), entry))).unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        let path = ArchivePath::parse(Path::new("dicts/bundle.ZIP:en/words.dict")).unwrap();
+        assert_eq!(path.archive, Path::new("dicts/bundle.ZIP"));
+        assert_eq!(path.entry, "en/words.dict");
+        assert_eq!(path.format, ArchiveFormat::Zip);
+        assert_eq!(path.to_string(), "dicts/bundle.ZIP:en/words.dict");
+
+        let path = ArchivePath::parse(Path::new("bundle.tar:en.dict")).unwrap();
+        assert_eq!(path.format, ArchiveFormat::Tar);
+
+        assert_eq!(ArchivePath::parse(Path::new("en.dict")), None);
+        assert_eq!(ArchivePath::parse(Path::new("notes:en.dict")), None);
+
+        // archive paths needn't be UTF-8
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            let path = Path::new(OsStr::from_bytes(b"\xffbundle.zip:en.dict"));
+            let path = ArchivePath::parse(path).unwrap();
+            assert_eq!(path.archive.as_os_str().as_bytes(), b"\xffbundle.zip");
+            assert_eq!(path.entry, "en.dict");
+        }
+    }
+
+    #[test]
+    fn tar() {
+        let archive = temp_path("bundle.tar");
+        let bytes = dict_bytes();
+        let mut builder = tar::Builder::new(File::create(&archive).unwrap());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(bytes.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "en.dict", bytes.as_slice())
+            .unwrap();
+        builder.finish().unwrap();
+        drop(builder);
+
+        let dict = read_entry(&entry_path(&archive, "en.dict"), |reader| {
+            read_dict(reader).unwrap()
+        })
+        .unwrap();
+        assert_eq!(dict.len(), 3);
+
+        let missing = read_entry(&entry_path(&archive, "fr.dict"), |_| ());
+        assert!(matches!(missing, Err(ArchiveError::MissingEntry(_))));
+        std::fs::remove_file(&archive).unwrap();
+    }
+
+    #[test]
+    fn zip() {
+        let archive = temp_path("bundle.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&archive).unwrap());
+        writer
+            .start_file("en.dict", zip::write::FileOptions::default())
+            .unwrap();
+        writer.write_all(&dict_bytes()).unwrap();
+        writer.finish().unwrap();
+
+        let dict = read_entry(&entry_path(&archive, "en.dict"), |reader| {
+            read_dict(reader).unwrap()
+        })
+        .unwrap();
+        assert_eq!(dict.len(), 3);
+
+        let missing = read_entry(&entry_path(&archive, "fr.dict"), |_| ());
+        assert_eq!(
+            missing.unwrap_err().to_string(),
+            format!("no entry \"fr.dict\" in archive {:?}", archive)
+        );
+        std::fs::remove_file(&archive).unwrap();
+    }
+}
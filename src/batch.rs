@@ -0,0 +1,223 @@
+//! Searches of many racks at once, one per line of a file
+//!
+//! The racks are searched in parallel, but their results come out in the
+//! order of the file, each as soon as the racks before it are done: a
+//! reorder buffer holds the results that finish early, the racks running at
+//! most a few hundred ahead of the earliest one not done. A line that can't be
+//! searched, such as a rack with invalid characters, is reported with its
+//! line number without stopping the others.
+
+use crate::history::write_json_string;
+use crate::par::*;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::sync::mpsc;
+use std::thread;
+
+/// A rack of a batch file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BatchLine<'a> {
+    /// Line number, from 1
+    pub line: usize,
+    /// The line, trimmed
+    pub rack: &'a str,
+}
+
+/// A line of a batch file that couldn't be searched. `Display` writes it as
+/// a line of JSON: `{"line":3,"rack":"ab1","error":"..."}`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BatchError {
+    /// Line number, from 1
+    pub line: usize,
+    /// The line, trimmed, with invalid UTF-8 replaced
+    pub rack: String,
+    pub message: String,
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{\"line\":{},\"rack\":", self.line)?;
+        write_json_string(f, &self.rack)?;
+        f.write_str(",\"error\":")?;
+        write_json_string(f, &self.message)?;
+        f.write_str("}")
+    }
+}
+
+/// The racks of a batch file, in order, skipping blank lines; the lines
+/// that aren't UTF-8 are errors.
+pub fn batch_lines(contents: &[u8]) -> Vec<Result<BatchLine<'_>, BatchError>> {
+    contents
+        .split(|&b| b == b'\n')
+        .enumerate()
+        .map(|(i, line)| match std::str::from_utf8(line) {
+            Ok(rack) => Ok(BatchLine {
+                line: i + 1,
+                rack: rack.trim(),
+            }),
+            Err(_) => Err(BatchError {
+                line: i + 1,
+                rack: String::from_utf8_lossy(line).trim().to_owned(),
+                message: String::from("the line isn't valid UTF-8"),
+            }),
+        })
+        .filter(|line| !matches!(line, Ok(line) if line.rack.is_empty()))
+        .collect()
+}
+
+// most items run ahead of the earliest one not done, bounding the results
+// held in the reorder buffer
+const REORDER_WINDOW: usize = 256;
+
+/// Runs `work` on every item in parallel, passing the results to `emit` in
+/// the order of the items, each as soon as every earlier one is done.
+/// Stops at the first error of `emit`, such as a closed output.
+pub fn run_ordered<T, R, W, E>(items: Vec<T>, work: W, mut emit: E) -> io::Result<()>
+where
+    T: Send,
+    R: Send,
+    W: Fn(T) -> R + Sync,
+    E: FnMut(R) -> io::Result<()>,
+{
+    let (sender, receiver) = mpsc::channel();
+    let work = &work;
+    thread::scope(|scope| {
+        scope.spawn(move || {
+            // a window at a time, so that a slow item holds back at most a
+            // window of results
+            let mut items = items.into_iter().enumerate().peekable();
+            while items.peek().is_some() {
+                let window = items.by_ref().take(REORDER_WINDOW).collect::<Vec<_>>();
+                // stops early once the receiver is gone, after `emit` failed
+                let sent = window
+                    .into_par_iter()
+                    .try_for_each(|(i, item)| sender.send((i, work(item))));
+                if sent.is_err() {
+                    break;
+                }
+            }
+        });
+
+        // the results that came before their turn
+        let mut early = BTreeMap::new();
+        let mut next = 0;
+        for (i, result) in receiver {
+            early.insert(i, result);
+            while let Some(result) = early.remove(&next) {
+                emit(result)?;
+                next += 1;
+            }
+        }
+
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_lines() {
+        let lines = super::batch_lines(b"abc\n\n  \n d?e \r\nab\xff\nxyz");
+        assert_eq!(
+            lines,
+            [
+                Ok(BatchLine {
+                    line: 1,
+                    rack: "abc"
+                }),
+                Ok(BatchLine {
+                    line: 4,
+                    rack: "d?e"
+                }),
+                Err(BatchError {
+                    line: 5,
+                    rack: String::from("ab\u{fffd}"),
+                    message: String::from("the line isn't valid UTF-8"),
+                }),
+                Ok(BatchLine {
+                    line: 6,
+                    rack: "xyz"
+                }),
+            ]
+        );
+        assert!(super::batch_lines(b"").is_empty());
+    }
+
+    #[test]
+    fn batch_error() {
+        let error = BatchError {
+            line: 3,
+            rack: String::from("a\"b"),
+            message: String::from("String contains characters other than letters"),
+        };
+        assert_eq!(
+            error.to_string(),
+            "{\"line\":3,\"rack\":\"a\\\"b\",\
+             \"error\":\"String contains characters other than letters\"}"
+        );
+    }
+
+    #[test]
+    fn run_ordered() {
+        let items = (0..1000).collect::<Vec<u64>>();
+        let mut emitted = Vec::new();
+        super::run_ordered(
+            items,
+            |i| {
+                // uneven durations, for the items to finish out of order
+                thread::sleep(std::time::Duration::from_micros((1000 - i) % 7));
+                i * 2
+            },
+            |result| {
+                emitted.push(result);
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert_eq!(emitted, (0..1000).map(|i| i * 2).collect::<Vec<_>>());
+
+        // stops at the first error
+        let mut emitted = 0;
+        let result = super::run_ordered(
+            (0..100).collect(),
+            |i: u32| i,
+            |_| {
+                emitted += 1;
+                match emitted {
+                    3 => Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed")),
+                    _ => Ok(()),
+                }
+            },
+        );
+        assert!(result.is_err());
+        assert_eq!(emitted, 3);
+    }
+
+    #[test]
+    fn run_ordered_window() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // a slow first item doesn't let the others run ahead past the window
+        let started = AtomicUsize::new(0);
+        let mut ahead = None;
+        super::run_ordered(
+            (0..(REORDER_WINDOW * 4)).collect(),
+            |i: usize| {
+                started.fetch_add(1, Ordering::SeqCst);
+                if i == 0 {
+                    thread::sleep(std::time::Duration::from_millis(50));
+                }
+                started.load(Ordering::SeqCst)
+            },
+            |result| {
+                ahead.get_or_insert(result);
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert!(ahead.unwrap() <= REORDER_WINDOW);
+    }
+}
@@ -0,0 +1,426 @@
+//! The interactive result browser of `search --tui`, as a state machine fed
+//! with key presses, so that it works (and can be tested) without a terminal
+//!
+//! The browser only narrows down the results of a search already held in
+//! memory, so refining them never goes back to the dictionary.
+
+use crate::fold::fold_case;
+use crate::search::{SearchResults, SortOrder};
+#[cfg(feature = "tui")]
+use std::io::{self, Write};
+
+/// The key presses the browser reacts to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Key {
+    /// Adds a character to the filter text
+    Char(char),
+    /// Removes the last character of the filter text
+    Backspace,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    /// Switches between alphabetical and consumed order
+    ToggleSort,
+    /// Quits with the selected word
+    Enter,
+    /// Quits without a word
+    Escape,
+}
+
+/// What to do after a key press
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Action {
+    Continue,
+    /// Quit, printing the word if any
+    Quit(Option<String>),
+}
+
+pub struct Browser<'a> {
+    // every result with the number of rack letters it uses, in `order`
+    entries: Vec<(&'a str, usize)>,
+    order: SortOrder,
+    filter: String,
+    // indices in `entries` of the words containing `filter`
+    visible: Vec<usize>,
+    selected: usize,
+    scroll: usize,
+    height: usize,
+}
+
+impl<'a> Browser<'a> {
+    /// Creates a browser over the results, showing `height` words at a time.
+    pub fn new(results: &SearchResults<'a>, order: SortOrder, height: usize) -> Self {
+        let mut browser = Self {
+            entries: results
+                .words
+                .iter()
+                .copied()
+                .zip(results.letters_used.iter().copied())
+                .collect(),
+            order,
+            filter: String::new(),
+            visible: Vec::new(),
+            selected: 0,
+            scroll: 0,
+            height: height.max(1),
+        };
+        browser.sort(order);
+        browser
+    }
+
+    pub fn handle(&mut self, key: Key) -> Action {
+        match key {
+            Key::Char(c) if c.is_ascii_alphabetic() => {
+                self.filter.push(fold_case(c as u8) as char);
+                // a longer filter only narrows down the visible words
+                self.refilter(true);
+            }
+            Key::Char(_) => {}
+            Key::Backspace => {
+                if self.filter.pop().is_some() {
+                    self.refilter(false);
+                }
+            }
+            Key::Up => self.select(self.selected.saturating_sub(1)),
+            Key::Down => self.select(self.selected.saturating_add(1)),
+            Key::PageUp => self.select(self.selected.saturating_sub(self.height)),
+            Key::PageDown => self.select(self.selected.saturating_add(self.height)),
+            Key::Home => self.select(0),
+            Key::End => self.select(usize::MAX),
+            Key::ToggleSort => self.sort(match self.order {
+                SortOrder::Alpha => SortOrder::Consumed,
+                SortOrder::Consumed => SortOrder::Alpha,
+            }),
+            Key::Enter => return Action::Quit(self.selected().map(String::from)),
+            Key::Escape => return Action::Quit(None),
+        }
+
+        Action::Continue
+    }
+
+    /// Changes the number of words shown at a time, after the terminal is
+    /// resized.
+    pub fn resize(&mut self, height: usize) {
+        self.height = height.max(1);
+        self.select(self.selected);
+    }
+
+    #[inline]
+    pub fn filter(&self) -> &str {
+        &self.filter
+    }
+
+    #[inline]
+    pub fn order(&self) -> SortOrder {
+        self.order
+    }
+
+    /// Number of words containing the filter text
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.visible.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.visible.is_empty()
+    }
+
+    pub fn selected(&self) -> Option<&'a str> {
+        self.visible.get(self.selected).map(|&i| self.entries[i].0)
+    }
+
+    /// The words in view, and whether each is the selected one
+    pub fn rows(&self) -> impl Iterator<Item = (&'a str, bool)> + '_ {
+        self.visible
+            .iter()
+            .enumerate()
+            .skip(self.scroll)
+            .take(self.height)
+            .map(move |(row, &i)| (self.entries[i].0, row == self.selected))
+    }
+
+    fn sort(&mut self, order: SortOrder) {
+        let selected = self.selected();
+        match order {
+            SortOrder::Alpha => self.entries.sort_unstable(),
+            SortOrder::Consumed => self
+                .entries
+                .sort_unstable_by_key(|&(word, used)| (std::cmp::Reverse(used), word)),
+        }
+        self.order = order;
+
+        self.refilter(false);
+        // keep the same word selected, wherever it moved
+        let selected =
+            selected.and_then(|word| self.visible.iter().position(|&i| self.entries[i].0 == word));
+        self.select(selected.unwrap_or(0));
+    }
+
+    // filters the visible words, or every word unless `narrow`
+    fn refilter(&mut self, narrow: bool) {
+        let selected = self.visible.get(self.selected).copied();
+        let candidates = if narrow {
+            std::mem::take(&mut self.visible)
+        } else {
+            (0..self.entries.len()).collect()
+        };
+        let filter = self.filter.as_bytes();
+        self.visible = candidates
+            .into_iter()
+            .filter(|&i| {
+                filter.is_empty()
+                    || self.entries[i]
+                        .0
+                        .as_bytes()
+                        .windows(filter.len())
+                        .any(|window| {
+                            window
+                                .iter()
+                                .map(|&b| fold_case(b))
+                                .eq(filter.iter().copied())
+                        })
+            })
+            .collect();
+
+        let selected =
+            selected.and_then(|selected| self.visible.iter().position(|&i| i == selected));
+        self.select(selected.unwrap_or(0));
+    }
+
+    // clamps the selection, and scrolls just enough to keep it in view
+    fn select(&mut self, index: usize) {
+        self.selected = index.min(self.visible.len().saturating_sub(1));
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + self.height {
+            self.scroll = self.selected + 1 - self.height;
+        }
+        self.scroll = self
+            .scroll
+            .min(self.visible.len().saturating_sub(self.height));
+    }
+}
+
+/// Runs the browser in the terminal until a word is picked or the browser is
+/// quit, returning the word picked if any.
+///
+/// The browser is drawn on the standard error, on the alternate screen, and
+/// the terminal is restored afterwards, even after a panic.
+#[cfg(feature = "tui")]
+pub fn run(browser: &mut Browser) -> io::Result<Option<String>> {
+    use crossterm::{cursor, execute, terminal};
+    use std::sync::Arc;
+
+    fn restore() {
+        let _ = execute!(io::stderr(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+
+    terminal::enable_raw_mode()?;
+    // the caller's hook, shared with ours to be put back once done
+    let hook = Arc::new(std::panic::take_hook());
+    let previous = Arc::clone(&hook);
+    std::panic::set_hook(Box::new(move |info| {
+        restore();
+        previous(info);
+    }));
+
+    let mut out = io::stderr();
+    let picked = execute!(out, terminal::EnterAlternateScreen, cursor::Hide)
+        .and_then(|_| event_loop(browser, &mut out));
+    restore();
+    std::panic::set_hook(Box::new(move |info| hook(info)));
+    picked
+}
+
+#[cfg(feature = "tui")]
+fn event_loop(browser: &mut Browser, out: &mut impl Write) -> io::Result<Option<String>> {
+    use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+    use crossterm::terminal;
+
+    // the last line shows the status
+    let list_height = |rows: u16| usize::from(rows).saturating_sub(1);
+    browser.resize(list_height(terminal::size()?.1));
+
+    loop {
+        draw(browser, out)?;
+        match event::read()? {
+            Event::Key(key) if key.kind != KeyEventKind::Release => {
+                let key = match key.code {
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        Key::Escape
+                    }
+                    KeyCode::Char(c) => Key::Char(c),
+                    KeyCode::Backspace => Key::Backspace,
+                    KeyCode::Up => Key::Up,
+                    KeyCode::Down => Key::Down,
+                    KeyCode::PageUp => Key::PageUp,
+                    KeyCode::PageDown => Key::PageDown,
+                    KeyCode::Home => Key::Home,
+                    KeyCode::End => Key::End,
+                    KeyCode::Tab => Key::ToggleSort,
+                    KeyCode::Enter => Key::Enter,
+                    KeyCode::Esc => Key::Escape,
+                    _ => continue,
+                };
+                if let Action::Quit(word) = browser.handle(key) {
+                    return Ok(word);
+                }
+            }
+            Event::Resize(_, rows) => browser.resize(list_height(rows)),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(feature = "tui")]
+fn draw(browser: &Browser, out: &mut impl Write) -> io::Result<()> {
+    use crossterm::style::{Attribute, Print, SetAttribute};
+    use crossterm::{cursor, queue, terminal};
+
+    queue!(out, terminal::Clear(terminal::ClearType::All))?;
+    for (line, (word, selected)) in (0..).zip(browser.rows()) {
+        queue!(out, cursor::MoveTo(0, line))?;
+        if selected {
+            queue!(
+                out,
+                SetAttribute(Attribute::Reverse),
+                Print(word),
+                SetAttribute(Attribute::Reset)
+            )?;
+        } else {
+            queue!(out, Print(word))?;
+        }
+    }
+
+    let status = format!(
+        "{} words, {} order, filter: {}  (type to filter, Tab: sort, Enter: pick, Esc: quit)",
+        browser.len(),
+        browser.order(),
+        browser.filter()
+    );
+    let (columns, rows) = terminal::size()?;
+    queue!(
+        out,
+        cursor::MoveTo(0, rows.saturating_sub(1)),
+        SetAttribute(Attribute::Bold),
+        Print(status.chars().take(columns.into()).collect::<String>()),
+        SetAttribute(Attribute::Reset)
+    )?;
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dict::Dictionary;
+    use crate::index::Indexes;
+    use crate::rack::{Rack, RackOptions};
+    use crate::search::SearchQuery;
+
+    fn dictionary() -> Dictionary {
+        [
+            "tan", "ant", "ants", "stain", "satin", "saint", "sat", "tin",
+        ]
+        .iter()
+        .collect()
+    }
+
+    fn rows(browser: &Browser) -> Vec<String> {
+        browser
+            .rows()
+            .map(|(word, selected)| {
+                if selected {
+                    format!(">{}", word)
+                } else {
+                    word.to_owned()
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn navigation() {
+        let dict = dictionary();
+        let query = SearchQuery::new(Rack::parse("satin", &RackOptions::default()).unwrap());
+        let results = query.search(&dict, &Indexes::default());
+        let mut browser = Browser::new(&results, SortOrder::Alpha, 3);
+
+        assert_eq!(rows(&browser), [">ant", "ants", "saint"]);
+        browser.handle(Key::Down);
+        browser.handle(Key::Down);
+        browser.handle(Key::Down);
+        assert_eq!(rows(&browser), ["ants", "saint", ">sat"]);
+        browser.handle(Key::End);
+        assert_eq!(rows(&browser), ["stain", "tan", ">tin"]);
+        browser.handle(Key::PageUp);
+        assert_eq!(rows(&browser), [">satin", "stain", "tan"]);
+        browser.handle(Key::Home);
+        browser.handle(Key::Up);
+        assert_eq!(browser.selected(), Some("ant"));
+
+        browser.resize(10);
+        assert_eq!(browser.rows().count(), 8);
+        assert_eq!(browser.handle(Key::Enter), Action::Quit(Some("ant".into())));
+        assert_eq!(browser.handle(Key::Escape), Action::Quit(None));
+    }
+
+    #[test]
+    fn filter() {
+        let dict = dictionary();
+        let query = SearchQuery::new(Rack::parse("satin", &RackOptions::default()).unwrap());
+        let results = query.search(&dict, &Indexes::default());
+        let mut browser = Browser::new(&results, SortOrder::Alpha, 10);
+
+        browser.handle(Key::Down);
+        assert_eq!(browser.selected(), Some("ants"));
+        browser.handle(Key::Char('A'));
+        browser.handle(Key::Char('n'));
+        assert_eq!(browser.filter(), "an");
+        assert_eq!(rows(&browser), ["ant", ">ants", "tan"]);
+
+        // non-letters are ignored
+        browser.handle(Key::Char('1'));
+        browser.handle(Key::Char('t'));
+        assert_eq!(rows(&browser), ["ant", ">ants"]);
+        browser.handle(Key::Char('x'));
+        assert!(browser.is_empty());
+        assert_eq!(browser.handle(Key::Enter), Action::Quit(None));
+
+        browser.handle(Key::Backspace);
+        browser.handle(Key::Backspace);
+        browser.handle(Key::Backspace);
+        assert_eq!(browser.filter(), "a");
+        assert_eq!(browser.len(), 7);
+    }
+
+    #[test]
+    fn toggle_sort() {
+        let dict = dictionary();
+        let query = SearchQuery::new(Rack::parse("satin", &RackOptions::default()).unwrap());
+        let results = query.search(&dict, &Indexes::default());
+        let mut browser = Browser::new(&results, SortOrder::Alpha, 10);
+
+        browser.handle(Key::Char('t'));
+        browser.handle(Key::Char('i'));
+        browser.handle(Key::End);
+        assert_eq!(rows(&browser), ["satin", ">tin"]);
+
+        browser.handle(Key::ToggleSort);
+        assert_eq!(browser.order(), SortOrder::Consumed);
+        browser.handle(Key::Home);
+        assert_eq!(rows(&browser), [">satin", "tin"]);
+        browser.handle(Key::Down);
+        browser.handle(Key::Backspace);
+        browser.handle(Key::Backspace);
+        assert_eq!(browser.selected(), Some("tin"));
+        assert_eq!(
+            browser.rows().map(|(word, _)| word).collect::<Vec<_>>(),
+            ["saint", "satin", "stain", "ants", "ant", "sat", "tan", "tin"]
+        );
+    }
+}
@@ -0,0 +1,810 @@
+//! Dictionary building from word lists
+
+use crate::count::{normalize_letters, CountError, CountSet, NormalizeOptions};
+use crate::dict::{parse_tier, Dictionary, TooManySources, MAX_TIER};
+use crate::fold::fold_word;
+use crate::inflect;
+use crate::trace::event;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, Read};
+use std::str::FromStr;
+
+/// Number of lines between progress reports
+const PROGRESS_INTERVAL: usize = 10_000;
+
+/// Options controlling how lines of a word list become dictionary words.
+#[derive(Clone, Debug, Default)]
+pub struct BuildOptions {
+    /// Trim whitespace around each line
+    pub trim: bool,
+    /// Convert words to lowercase, merging words that differ only by case
+    pub fold_case: bool,
+    /// Skip invalid words instead of failing
+    pub skip_invalid: bool,
+    /// Collect words that differ only by case into the report
+    pub case_report: bool,
+    /// Keep empty and whitespace-only lines instead of skipping them, which
+    /// either fail as invalid words or, if empty, add an empty word matching
+    /// every search
+    pub keep_blank: bool,
+    /// Add the regular plurals and verb forms of every word, tagged as
+    /// derived (see the `inflect` module)
+    pub expand_inflections: bool,
+    /// Read digits as letters, per `count::LEET_DIGITS`
+    pub leet: bool,
+    /// With `leet`, list the words with their digits (`h3llo`) rather than
+    /// the letters they stand for (`hello`); they're counted as the letters
+    /// either way
+    pub keep_leet_spelling: bool,
+    /// Read the difficulty tier of each word from a second column, after
+    /// whitespace or a comma; words without one are in `MAX_TIER`
+    pub tiers: Option<TierSource>,
+    /// How words are laid out in the lines of the word list
+    pub format: ListFormat,
+}
+
+/// The layout of a word list
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ListFormat {
+    /// A word per line
+    #[default]
+    Words,
+    /// A class of anagrams per line, as exported with
+    /// `export::ExportFormat::AnagramKeys`: a key, which is ignored, then
+    /// the words, separated by whitespace
+    AnagramKeys,
+}
+
+impl FromStr for ListFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "words" => Ok(ListFormat::Words),
+            "anagram-keys" => Ok(ListFormat::AnagramKeys),
+            _ => Err(format!(
+                "unknown word list format {:?} (expected words or anagram-keys)",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ListFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            ListFormat::Words => "words",
+            ListFormat::AnagramKeys => "anagram-keys",
+        })
+    }
+}
+
+/// What the second column of a word list with difficulty tiers holds
+#[derive(Clone, Debug)]
+pub enum TierSource {
+    /// The tier itself, from 0 (easy) to `MAX_TIER` or named as in
+    /// `TIER_NAMES`
+    Column,
+    /// A word frequency, turned into a tier with thresholds
+    Frequency(TierThresholds),
+}
+
+impl TierSource {
+    /// The tier of a word given its second column, if valid
+    pub fn tier(&self, value: &str) -> Option<u8> {
+        match self {
+            TierSource::Column => parse_tier(value).ok(),
+            TierSource::Frequency(thresholds) => value
+                .parse()
+                .ok()
+                .filter(|frequency: &f64| frequency.is_finite())
+                .map(|frequency| thresholds.tier(frequency)),
+        }
+    }
+}
+
+/// Decreasing word frequencies separating the tiers: the words at least as
+/// frequent as the first threshold are in tier 0, then those at least as
+/// frequent as the second in tier 1, and so on, up to `MAX_TIER` thresholds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TierThresholds(Vec<f64>);
+
+impl TierThresholds {
+    pub fn tier(&self, frequency: f64) -> u8 {
+        self.0
+            .iter()
+            .position(|&threshold| frequency >= threshold)
+            .unwrap_or(self.0.len()) as u8
+    }
+}
+
+/// Parses comma-separated thresholds, such as `1e-5,1e-7`.
+impl FromStr for TierThresholds {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let thresholds = s
+            .split(',')
+            .map(|threshold| {
+                threshold
+                    .trim()
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|threshold| threshold.is_finite())
+                    .ok_or_else(|| format!("invalid tier threshold {:?}", threshold))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if thresholds.len() > MAX_TIER as usize {
+            return Err(format!(
+                "too many tier thresholds, expected at most {}",
+                MAX_TIER
+            ));
+        }
+        if thresholds.windows(2).any(|pair| pair[0] <= pair[1]) {
+            return Err(String::from("tier thresholds must be decreasing"));
+        }
+
+        Ok(Self(thresholds))
+    }
+}
+
+/// Progress of a build, reported periodically through the progress callback.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Progress {
+    /// Lines processed so far
+    pub lines: usize,
+    /// Words added so far
+    pub added: usize,
+}
+
+/// Summary of what happened to every line of the input.
+#[derive(Debug, Default)]
+pub struct BuildReport {
+    pub lines: usize,
+    pub added: usize,
+    /// Words already in the dictionary
+    pub duplicates: usize,
+    /// Skipped empty or whitespace-only lines
+    pub blank: usize,
+    /// Skipped words, with their line number (starting from 1) and reason
+    pub skipped: Vec<(usize, String, CountError)>,
+    /// Groups of spellings that differ only by case, if requested
+    pub case_duplicates: Vec<Vec<String>>,
+    /// Inflections added to the dictionary, if requested
+    pub derived: usize,
+    /// Words added in each difficulty tier, if read
+    pub tiers: [usize; MAX_TIER as usize + 1],
+    /// Room reserved with `DictionaryBuilder::reserve` ahead of the words,
+    /// as a number of words and of bytes of word text
+    pub reserved: (usize, usize),
+}
+
+/// Quality report of a word list, made without building a dictionary.
+#[derive(Debug, Default)]
+pub struct LintReport {
+    pub lines: usize,
+    /// Distinct valid words
+    pub unique: usize,
+    /// Valid words already seen earlier in the list
+    pub duplicates: usize,
+    /// Empty or whitespace-only lines
+    pub blank: usize,
+    /// Invalid words, with their line number (starting from 1) and reason
+    pub invalid: Vec<(usize, String, CountError)>,
+}
+
+impl LintReport {
+    /// Number of invalid words with the given reason
+    pub fn invalid_count(&self, reason: CountError) -> usize {
+        self.invalid
+            .iter()
+            .filter(|&&(_, _, err)| err == reason)
+            .count()
+    }
+}
+
+/// Checks every line of a word list the way `DictionaryBuilder` would with
+/// the default options.
+pub fn lint<R: BufRead>(reader: R) -> io::Result<LintReport> {
+    let mut report = LintReport::default();
+    let mut seen = HashSet::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        report.lines += 1;
+
+        if line.trim().is_empty() {
+            report.blank += 1;
+        } else if let Err(err) = CountSet::from_word(&line) {
+            report.invalid.push((report.lines, line, err));
+        } else if seen.insert(line) {
+            report.unique += 1;
+        } else {
+            report.duplicates += 1;
+        }
+    }
+
+    Ok(report)
+}
+
+/// Size of a word list, counted by `prescan` without parsing it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Prescan {
+    /// Lines, the last one counting even without a final newline
+    pub lines: usize,
+    /// Bytes of the lines, without the newlines: at least the bytes of the
+    /// words they hold
+    pub bytes: usize,
+}
+
+/// Counts the lines and bytes of a word list in a quick first pass, to
+/// reserve room for its words with `DictionaryBuilder::reserve` before
+/// reading it for good. Only for inputs that can be read twice, such as files.
+pub fn prescan<R: Read>(mut reader: R) -> io::Result<Prescan> {
+    let mut buf = vec![0; 64 * 1024];
+    let (mut prescan, mut last) = (Prescan::default(), b'\n');
+    loop {
+        let read = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        let newlines = buf[..read].iter().filter(|&&b| b == b'\n').count();
+        prescan.lines += newlines;
+        prescan.bytes += read - newlines;
+        last = buf[read - 1];
+    }
+    if last != b'\n' {
+        prescan.lines += 1;
+    }
+
+    Ok(prescan)
+}
+
+/// Error type returned by `DictionaryBuilder`
+#[derive(Debug)]
+pub enum BuildError {
+    /// Invalid word at the given line number (starting from 1)
+    InvalidWord(usize, CountError),
+    /// Invalid difficulty tier column at the given line number
+    InvalidTier(usize, String),
+    /// Error returned by an I/O operation
+    IoError(io::Error),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use BuildError::*;
+
+        match self {
+            InvalidWord(line, err) => write!(f, "line {}: {}", line, err),
+            InvalidTier(line, value) => write!(f, "line {}: invalid tier {:?}", line, value),
+            IoError(err) => fmt::Display::fmt(err, f),
+        }
+    }
+}
+
+impl Error for BuildError {}
+
+impl From<io::Error> for BuildError {
+    fn from(err: io::Error) -> Self {
+        BuildError::IoError(err)
+    }
+}
+
+/// Builds a dictionary line by line, normalizing words and keeping track of
+/// what happened to each line.
+pub struct DictionaryBuilder<'a> {
+    options: BuildOptions,
+    dict: Dictionary,
+    report: BuildReport,
+    spellings: HashMap<String, Vec<String>>,
+    source: Option<u16>,
+    progress: Option<Box<dyn FnMut(Progress) + 'a>>,
+}
+
+impl<'a> DictionaryBuilder<'a> {
+    pub fn new(options: BuildOptions) -> Self {
+        Self {
+            options,
+            dict: Dictionary::new(),
+            report: BuildReport::default(),
+            spellings: HashMap::new(),
+            source: None,
+            progress: None,
+        }
+    }
+
+    /// Sets a callback called every few thousand lines with the progress so far.
+    pub fn on_progress<F: FnMut(Progress) + 'a>(mut self, callback: F) -> Self {
+        self.progress = Some(Box::new(callback));
+        self
+    }
+
+    /// Tags the words of the following lines with the name of their word list.
+    pub fn set_source(&mut self, name: &str) -> Result<(), TooManySources> {
+        self.source = Some(self.dict.add_source(name)?);
+        Ok(())
+    }
+
+    /// Reserves room for `words` more words of `bytes` bytes in all, such as
+    /// those of a word list measured with `prescan`, so the dictionary doesn't
+    /// grow by steps while reading it. More room than needed is only memory
+    /// used for nothing, and less only leaves the rest to grow as usual.
+    pub fn reserve(&mut self, words: usize, bytes: usize) {
+        self.dict.reserve(words, bytes);
+        self.report.reserved.0 += words;
+        self.report.reserved.1 += bytes;
+    }
+
+    pub fn add_line(&mut self, line: &str) -> Result<(), BuildError> {
+        self.report.lines += 1;
+        let line_number = self.report.lines;
+
+        if !self.options.keep_blank && line.trim().is_empty() {
+            self.report.blank += 1;
+            return Ok(());
+        }
+
+        if self.options.format == ListFormat::AnagramKeys {
+            for word in line.split_ascii_whitespace().skip(1) {
+                self.add_word(line_number, word, None)?;
+            }
+        } else {
+            let (word, tier) = self.split_tier(line_number, line)?;
+            self.add_word(line_number, word, tier)?;
+        }
+
+        if line_number.is_multiple_of(PROGRESS_INTERVAL) {
+            self.report_progress();
+        }
+
+        Ok(())
+    }
+
+    // the word of a line and its tier, if the word list has tiers
+    fn split_tier<'l>(
+        &self,
+        line_number: usize,
+        line: &'l str,
+    ) -> Result<(&'l str, Option<u8>), BuildError> {
+        Ok(match self.options.tiers.as_ref() {
+            Some(source) => {
+                let line = line.trim();
+                match line.split_once(|c: char| c.is_ascii_whitespace() || c == ',') {
+                    Some((word, value)) => {
+                        let value = value.trim();
+                        let tier = source.tier(value).ok_or_else(|| {
+                            BuildError::InvalidTier(line_number, value.to_owned())
+                        })?;
+                        (word, Some(tier))
+                    }
+                    None => (line, Some(MAX_TIER)),
+                }
+            }
+            None => (line, None),
+        })
+    }
+
+    fn add_word(
+        &mut self,
+        line_number: usize,
+        line: &str,
+        tier: Option<u8>,
+    ) -> Result<(), BuildError> {
+        let word = normalize_letters(
+            line,
+            &NormalizeOptions {
+                trim: self.options.trim,
+                ..NormalizeOptions::default()
+            },
+        );
+        let word = word.as_ref();
+        if self.options.case_report {
+            let spellings = self.spellings.entry(fold_word(word)).or_default();
+            if !spellings.iter().any(|spelling| spelling == word) {
+                spellings.push(word.to_owned());
+            }
+        }
+
+        let word = if self.options.fold_case {
+            fold_word(word)
+        } else {
+            word.to_owned()
+        };
+
+        let letters = normalize_letters(
+            &word,
+            &NormalizeOptions {
+                leet: self.options.leet,
+                ..NormalizeOptions::default()
+            },
+        )
+        .into_owned();
+        let word = if self.options.keep_leet_spelling {
+            word
+        } else {
+            letters.clone()
+        };
+
+        let len = self.dict.len();
+        let key = (self.dict.word_string().len(), word.len());
+        let added = self.dict.add_spelling(&word, &letters, self.source);
+        match added {
+            Ok(()) if self.dict.len() > len => {
+                self.report.added += 1;
+                if let Some(tier) = tier {
+                    self.dict.set_tier(key, tier);
+                    self.report.tiers[tier as usize] += 1;
+                }
+            }
+            Ok(()) => self.report.duplicates += 1,
+            Err(err) if self.options.skip_invalid => {
+                self.report.skipped.push((line_number, word, err))
+            }
+            Err(err) => return Err(BuildError::InvalidWord(line_number, err)),
+        }
+
+        Ok(())
+    }
+
+    pub fn read_from<R: BufRead>(&mut self, reader: R) -> Result<(), BuildError> {
+        for line in reader.lines() {
+            self.add_line(&line?)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> (Dictionary, BuildReport) {
+        self.report_progress();
+
+        let mut case_duplicates = std::mem::take(&mut self.spellings)
+            .into_values()
+            .filter(|spellings| spellings.len() > 1)
+            .collect::<Vec<_>>();
+        case_duplicates.sort_unstable();
+        self.report.case_duplicates = case_duplicates;
+
+        if self.options.expand_inflections {
+            self.expand_inflections();
+        }
+
+        event!(
+            lines = self.report.lines,
+            added = self.report.added,
+            duplicates = self.report.duplicates,
+            skipped = self.report.skipped.len(),
+            "dictionary built"
+        );
+        (self.dict, self.report)
+    }
+
+    fn expand_inflections(&mut self) {
+        // in word list order, for reproducible dictionaries
+        let mut lemmas = self.dict.word_count().keys().copied().collect::<Vec<_>>();
+        lemmas.sort_unstable();
+
+        for key in lemmas {
+            let lemma = self.dict.get(&key).unwrap();
+            let (forms, source) = (inflect::inflections(lemma.word), lemma.source);
+
+            for form in forms {
+                let len = self.dict.len();
+                // forms with too many of a letter are silently dropped
+                let _ = self.dict.add_derived(&form, source);
+                self.report.derived += self.dict.len() - len;
+            }
+        }
+    }
+
+    fn report_progress(&mut self) {
+        if let Some(progress) = self.progress.as_mut() {
+            progress(Progress {
+                lines: self.report.lines,
+                added: self.report.added,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    // `Dictionary::par_iter` is a plain iterator without the feature
+    #[cfg(feature = "parallel")]
+    use crate::par::*;
+
+    const MESSY: &str = "apple\r\n  Banana \nbanana\ncherry\nch3rry\napple\nAPPLE\ndürian\n";
+
+    #[test]
+    fn report() {
+        let options = BuildOptions {
+            trim: true,
+            fold_case: true,
+            skip_invalid: true,
+            case_report: true,
+            keep_blank: false,
+            expand_inflections: false,
+            leet: false,
+            keep_leet_spelling: false,
+            tiers: None,
+            format: ListFormat::Words,
+        };
+        let mut builder = DictionaryBuilder::new(options);
+        builder.read_from(MESSY.as_bytes()).unwrap();
+        let (dict, report) = builder.finish();
+
+        assert_eq!(dict.len(), 3);
+        assert_eq!(report.lines, 8);
+        assert_eq!(report.added, 3);
+        assert_eq!(report.duplicates, 3);
+        assert_eq!(report.skipped.len(), 2);
+        assert_eq!(report.skipped[0].0, 5);
+        assert_eq!(report.skipped[0].1, "ch3rry");
+        assert_eq!(report.skipped[1].0, 8);
+        assert_eq!(
+            report.case_duplicates,
+            [vec!["Banana", "banana"], vec!["apple", "APPLE"]]
+        );
+    }
+
+    #[test]
+    fn blank_lines() {
+        let mut builder = DictionaryBuilder::new(BuildOptions::default());
+        builder
+            .read_from("\nfoo\n\n  \t\nbar\n\n".as_bytes())
+            .unwrap();
+        let (dict, report) = builder.finish();
+
+        assert_eq!(dict.len(), 2);
+        assert_eq!(dict.word_string(), "foobar");
+        assert_eq!(report.lines, 6);
+        assert_eq!(report.blank, 4);
+
+        let mut builder = DictionaryBuilder::new(BuildOptions {
+            keep_blank: true,
+            ..BuildOptions::default()
+        });
+        builder.read_from("\nfoo\n".as_bytes()).unwrap();
+        let (dict, report) = builder.finish();
+        assert_eq!(dict.len(), 2);
+        assert_eq!(report.blank, 0);
+    }
+
+    #[test]
+    fn sources() {
+        let mut builder = DictionaryBuilder::new(BuildOptions::default());
+        builder.set_source("small").unwrap();
+        builder.read_from("cat\ndog\n".as_bytes()).unwrap();
+        builder.set_source("big").unwrap();
+        builder.read_from("cat\nemu\n".as_bytes()).unwrap();
+        let (dict, report) = builder.finish();
+
+        assert_eq!(dict.sources(), ["small", "big"]);
+        assert_eq!(report.duplicates, 1);
+        let mut sources = dict
+            .word_sources()
+            .iter()
+            .map(|(&(offset, len), &source)| (&dict.word_string()[offset..(offset + len)], source))
+            .collect::<Vec<_>>();
+        sources.sort_unstable();
+        assert_eq!(sources, [("cat", 0), ("dog", 0), ("emu", 1)]);
+    }
+
+    #[test]
+    fn anagram_keys() {
+        let mut builder = DictionaryBuilder::new(BuildOptions {
+            format: ListFormat::AnagramKeys,
+            ..BuildOptions::default()
+        });
+        builder
+            .read_from("aest east seat\n\nant  ant\ttan\nooz\n".as_bytes())
+            .unwrap();
+        let (dict, report) = builder.finish();
+
+        let mut words = dict.par_iter().map(|entry| entry.word).collect::<Vec<_>>();
+        words.sort_unstable();
+        assert_eq!(words, ["ant", "east", "seat", "tan"]);
+        assert_eq!((report.lines, report.blank, report.added), (4, 1, 4));
+        assert_eq!("anagram-keys".parse(), Ok(ListFormat::AnagramKeys));
+    }
+
+    #[test]
+    fn tiers() {
+        let thresholds = "1e-5, 1e-7".parse::<TierThresholds>().unwrap();
+        assert_eq!(thresholds.tier(1e-3), 0);
+        assert_eq!(thresholds.tier(1e-5), 0);
+        assert_eq!(thresholds.tier(1e-6), 1);
+        assert_eq!(thresholds.tier(1e-9), 2);
+        assert!("1e-7,1e-5".parse::<TierThresholds>().is_err());
+        assert!("1,0.1,0.01,0.001".parse::<TierThresholds>().is_err());
+        assert!("1e-5,x".parse::<TierThresholds>().is_err());
+
+        let mut builder = DictionaryBuilder::new(BuildOptions {
+            tiers: Some(TierSource::Frequency(thresholds)),
+            ..BuildOptions::default()
+        });
+        builder
+            .read_from("the 0.05\ncat,2e-6\nqat\t1e-9\nzax\n".as_bytes())
+            .unwrap();
+        let (dict, report) = builder.finish();
+        assert_eq!(report.tiers, [1, 1, 1, 1]);
+        let tiers = (0..4)
+            .map(|i| dict.get(&(i * 3, 3)).unwrap().tier())
+            .collect::<Vec<_>>();
+        assert_eq!(tiers, [Some(0), Some(1), Some(2), Some(MAX_TIER)]);
+
+        let mut builder = DictionaryBuilder::new(BuildOptions {
+            tiers: Some(TierSource::Column),
+            ..BuildOptions::default()
+        });
+        builder.add_line("cat 1").unwrap();
+        match builder.add_line("dog 4").unwrap_err() {
+            BuildError::InvalidTier(2, value) => assert_eq!(value, "4"),
+            err => panic!("Wrong 'invalid_tier' error! {:?}", err),
+        }
+        builder.add_line("qat\tRare").unwrap();
+        let (_, report) = builder.finish();
+        assert_eq!(report.tiers, [0, 1, 1, 0]);
+    }
+
+    #[test]
+    fn leet() {
+        let read = |options| {
+            let mut builder = DictionaryBuilder::new(options);
+            builder.read_from("h3llo\nhello\nc4t\n".as_bytes()).unwrap();
+            builder.finish()
+        };
+
+        let (dict, report) = read(BuildOptions {
+            leet: true,
+            ..BuildOptions::default()
+        });
+        assert_eq!(dict.word_string(), "hellocat");
+        assert_eq!(report.duplicates, 1);
+
+        let (dict, report) = read(BuildOptions {
+            leet: true,
+            keep_leet_spelling: true,
+            ..BuildOptions::default()
+        });
+        assert_eq!(dict.word_string(), "h3llohelloc4t");
+        assert_eq!(report.added, 3);
+        assert_eq!(dict.get(&(10, 3)).unwrap().count_set.to_string(), "act");
+
+        let (_, report) = read(BuildOptions {
+            skip_invalid: true,
+            ..BuildOptions::default()
+        });
+        assert_eq!(report.skipped.len(), 2);
+    }
+
+    #[test]
+    fn expand_inflections() {
+        let mut builder = DictionaryBuilder::new(BuildOptions {
+            expand_inflections: true,
+            ..BuildOptions::default()
+        });
+        builder.set_source("small").unwrap();
+        builder.read_from("run\nrunning\n".as_bytes()).unwrap();
+        let (dict, report) = builder.finish();
+
+        // runs, runned, runnings, runninged, runninging
+        assert_eq!(report.derived, 5);
+        assert_eq!(dict.len(), 7);
+        let derived = dict
+            .par_iter()
+            .filter(|entry| entry.derived)
+            .map(|entry| (entry.word, entry.source))
+            .collect::<Vec<_>>();
+        assert_eq!(derived.len(), 5);
+        assert!(derived.contains(&("runs", Some(0))));
+        assert!(!dict.get(&(3, 7)).unwrap().derived);
+    }
+
+    #[test]
+    fn lint() {
+        let report = super::lint(MESSY.as_bytes()).unwrap();
+
+        assert_eq!(report.lines, 8);
+        assert_eq!(report.unique, 4);
+        assert_eq!(report.duplicates, 1);
+        assert_eq!(report.blank, 0);
+        assert_eq!(report.invalid_count(CountError::NotAlphabetic), 2);
+        assert_eq!(report.invalid_count(CountError::NotAscii), 1);
+        assert_eq!(report.invalid[0].0, 2);
+    }
+
+    #[test]
+    fn prescan() {
+        let prescan = |list: &str| super::prescan(list.as_bytes()).unwrap();
+        assert_eq!(prescan(""), Prescan::default());
+        assert_eq!(prescan("cat\ndog\n"), Prescan { lines: 2, bytes: 6 });
+        // a last line without a newline still counts
+        assert_eq!(prescan("cat\r\ndog"), Prescan { lines: 2, bytes: 7 });
+        assert_eq!(
+            prescan(&"word\n".repeat(100_000)),
+            Prescan {
+                lines: 100_000,
+                bytes: 400_000
+            }
+        );
+    }
+
+    #[test]
+    fn reserve() {
+        let list = "cat\ndog\ncat\nDog\n";
+        let size = super::prescan(list.as_bytes()).unwrap();
+        let mut builder = DictionaryBuilder::new(BuildOptions {
+            fold_case: true,
+            ..BuildOptions::default()
+        });
+        builder.reserve(size.lines, size.bytes);
+        builder.read_from(list.as_bytes()).unwrap();
+        let (dict, report) = builder.finish();
+
+        assert_eq!(report.reserved, (4, 12));
+        assert_eq!(dict.len(), 2);
+        assert_eq!(dict.word_string(), "catdog");
+    }
+
+    #[test]
+    fn strict() {
+        let mut builder = DictionaryBuilder::new(BuildOptions::default());
+        match builder.read_from(MESSY.as_bytes()).unwrap_err() {
+            // spaces are not letters
+            BuildError::InvalidWord(2, CountError::NotAlphabetic) => {}
+            err => panic!("Wrong 'invalid_word' error! {:?}", err),
+        }
+    }
+
+    // a word list keeping its case, with the Turkish dotted capital I: it's
+    // rejected rather than folded to some `i`, and `I` always folds to `i`
+    #[test]
+    fn turkish_i() {
+        for fold_case in [false, true] {
+            let mut builder = DictionaryBuilder::new(BuildOptions {
+                fold_case,
+                ..BuildOptions::default()
+            });
+            assert!(matches!(
+                builder.add_line("\u{130}stanbul"),
+                Err(BuildError::InvalidWord(_, CountError::NotAscii))
+            ));
+            assert!(matches!(
+                builder.add_line("d\u{131}sk"),
+                Err(BuildError::InvalidWord(_, CountError::NotAscii))
+            ));
+            builder.add_line("ISTANBUL").unwrap();
+            let (dict, _) = builder.finish();
+
+            let words = dict.par_iter().map(|entry| entry.word).collect::<Vec<_>>();
+            assert_eq!(words, [if fold_case { "istanbul" } else { "ISTANBUL" }]);
+        }
+    }
+
+    #[test]
+    fn progress() {
+        let mut progress = Vec::new();
+        let mut builder =
+            DictionaryBuilder::new(BuildOptions::default()).on_progress(|p| progress.push(p));
+        for i in 0..25_000 {
+            // "a" to "z", then "aa" to "zz", ...
+            let word = (b'a' + (i % 26) as u8) as char;
+            builder
+                .add_line(&word.to_string().repeat(i / 26 % 15 + 1))
+                .unwrap();
+        }
+        builder.finish();
+
+        assert_eq!(progress.len(), 3);
+        assert_eq!(progress[0].lines, 10_000);
+        assert_eq!(progress[2].lines, 25_000);
+        assert_eq!(progress[2].added, 26 * 15);
+    }
+}
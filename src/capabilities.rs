@@ -0,0 +1,103 @@
+//! What this build of the crate can read, write and do
+//!
+//! Everything here is derived from constants and `cfg!`, so it can't get out
+//! of sync with the code.
+
+use crate::format::{
+    FORMAT_VERSION, MIN_FORMAT_VERSION, SECTION_DEFAULTS, SECTION_DERIVED, SECTION_SOURCES,
+    SECTION_TIERS,
+};
+use std::fmt::Write;
+
+/// Dictionary format versions this build can read
+pub const FORMAT_VERSIONS: std::ops::RangeInclusive<u32> = MIN_FORMAT_VERSION..=FORMAT_VERSION;
+
+/// Optional sections of a dictionary file, by tag
+pub const SECTIONS: &[(&[u8; 4], &str)] = &[
+    (SECTION_SOURCES, "word list sources"),
+    (SECTION_DEFAULTS, "default search options"),
+    (SECTION_DERIVED, "derived words"),
+    (SECTION_TIERS, "word difficulty tiers"),
+];
+
+/// Optional cargo features, and whether they are enabled in this build
+pub const FEATURES: &[(&str, bool)] = &[
+    ("unpacked-counts", cfg!(feature = "unpacked-counts")),
+    ("archives", cfg!(feature = "archives")),
+    ("tui", cfg!(feature = "tui")),
+    ("unstable", cfg!(feature = "unstable")),
+    ("parallel", cfg!(feature = "parallel")),
+    ("profile-memory", cfg!(feature = "profile-memory")),
+    ("tracing", cfg!(feature = "tracing")),
+];
+
+/// Optional files that may accompany a dictionary file, by extension
+pub const SIDECARS: &[(&str, &str)] = &[("sidx", "sorted index")];
+
+/// The dictionary used when none is given
+pub const DEFAULT_DICTIONARY: &str = "default.dict";
+
+/// Detailed version information, as printed by `--version`
+pub fn long_version() -> String {
+    let mut out = String::from(env!("CARGO_PKG_VERSION"));
+
+    let versions = FORMAT_VERSIONS
+        .map(|version| version.to_string())
+        .collect::<Vec<_>>();
+    write!(
+        out,
+        "\ndictionary format versions: {} (writes {})",
+        versions.join(", "),
+        FORMAT_VERSION
+    )
+    .unwrap();
+
+    let features = FEATURES
+        .iter()
+        .map(|&(name, enabled)| format!("{}{}", if enabled { '+' } else { '-' }, name))
+        .collect::<Vec<_>>();
+    write!(
+        out,
+        "\nfeatures: {}",
+        if features.is_empty() {
+            String::from("(none)")
+        } else {
+            features.join(" ")
+        }
+    )
+    .unwrap();
+
+    write!(out, "\ndefault dictionary: {}", DEFAULT_DICTIONARY).unwrap();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // every feature declared in the manifest must be in the registry
+    #[test]
+    fn features_match_manifest() {
+        let manifest = include_str!("../Cargo.toml");
+        let declared = manifest
+            .lines()
+            .skip_while(|line| line.trim() != "[features]")
+            .skip(1)
+            .take_while(|line| !line.starts_with('['))
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| line.split('=').next())
+            .map(str::trim)
+            .filter(|name| !name.is_empty() && *name != "default")
+            .collect::<Vec<_>>();
+
+        let registered = FEATURES.iter().map(|&(name, _)| name).collect::<Vec<_>>();
+        assert_eq!(declared, registered);
+    }
+
+    #[test]
+    fn long_version() {
+        let version = super::long_version();
+        assert!(version.starts_with(env!("CARGO_PKG_VERSION")));
+        assert!(version.contains("dictionary format versions: 1, 2 (writes 2)"));
+    }
+}
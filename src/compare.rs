@@ -0,0 +1,253 @@
+//! Comparison of two dictionaries: of their results over a set of racks, or
+//! of their words
+
+use crate::count::CountError;
+use crate::dict::Dictionary;
+use crate::index::Indexes;
+use crate::par::*;
+use crate::rack::{Rack, RackOptions};
+use crate::search::SearchQuery;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::{BTreeSet, HashSet};
+
+/// Number of tiles of each letter in a standard English Scrabble set, without
+/// the blanks; used to generate realistic racks.
+pub const TILE_DISTRIBUTION: [u8; 26] = [
+    9, 2, 2, 4, 12, 2, 3, 2, 9, 1, 1, 4, 2, 6, 8, 2, 1, 6, 4, 6, 4, 2, 2, 1, 2, 1,
+];
+
+/// Maximum number of example words kept for each dictionary
+const MAX_EXAMPLES: usize = 10;
+
+/// Results of both dictionaries for a single rack
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RackComparison {
+    pub rack: String,
+    pub first: usize,
+    pub second: usize,
+}
+
+impl RackComparison {
+    /// How many more words the second dictionary found
+    #[inline]
+    pub fn difference(&self) -> i64 {
+        self.second as i64 - self.first as i64
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Comparison {
+    pub racks: Vec<RackComparison>,
+    /// A few words (sorted) only found by the first dictionary
+    pub only_first: BTreeSet<String>,
+    /// A few words (sorted) only found by the second dictionary
+    pub only_second: BTreeSet<String>,
+}
+
+/// Statistics of the per-rack differences in number of words
+#[derive(Clone, Debug, PartialEq)]
+pub struct Summary {
+    pub mean_first: f64,
+    pub mean_second: f64,
+    pub mean: f64,
+    pub median: i64,
+    pub p10: i64,
+    pub p90: i64,
+    pub min: i64,
+    pub max: i64,
+}
+
+/// Generates `count` random racks of `size` letters, drawn with the
+/// frequencies of `TILE_DISTRIBUTION`; the same seed gives the same racks.
+pub fn random_racks(seed: u64, count: usize, size: usize) -> Vec<String> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let letters = WeightedIndex::new(TILE_DISTRIBUTION.iter()).unwrap();
+
+    (0..count)
+        .map(|_| {
+            (0..size)
+                .map(|_| (b'a' + letters.sample(&mut rng) as u8) as char)
+                .collect()
+        })
+        .collect()
+}
+
+/// Runs `query` with each of the racks against both dictionaries.
+pub fn compare(
+    first: &Dictionary,
+    second: &Dictionary,
+    query: &SearchQuery,
+    racks: &[String],
+) -> Result<Comparison, CountError> {
+    let mut comparison = Comparison::default();
+    let indexes = Indexes::default();
+
+    for rack in racks.iter() {
+        let mut query = query.clone();
+        query.rack = Rack::parse(rack, &RackOptions::default())?;
+
+        let first_words = query.search(first, &indexes).words;
+        let second_words = query.search(second, &indexes).words;
+        collect_examples(&first_words, &second_words, &mut comparison.only_first);
+        collect_examples(&second_words, &first_words, &mut comparison.only_second);
+
+        comparison.racks.push(RackComparison {
+            rack: rack.clone(),
+            first: first_words.len(),
+            second: second_words.len(),
+        });
+    }
+
+    Ok(comparison)
+}
+
+/// Words added and removed between two versions of a dictionary
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DictionaryDiff<'a> {
+    /// Words only in the new dictionary, sorted
+    pub added: Vec<&'a str>,
+    /// Words only in the old dictionary, sorted
+    pub removed: Vec<&'a str>,
+}
+
+/// Compares the words of two versions of a dictionary.
+pub fn diff<'a>(old: &'a Dictionary, new: &'a Dictionary) -> DictionaryDiff<'a> {
+    let words = |dict: &'a Dictionary| {
+        dict.par_iter()
+            .map(|entry| entry.word)
+            .collect::<HashSet<_>>()
+    };
+    let (old_words, new_words) = join(|| words(old), || words(new));
+
+    let only = |words: &HashSet<&'a str>, other: &HashSet<&'a str>| {
+        let mut only = words
+            .par_iter()
+            .filter(|word| !other.contains(*word))
+            .copied()
+            .collect::<Vec<_>>();
+        only.par_sort_unstable();
+        only
+    };
+
+    DictionaryDiff {
+        added: only(&new_words, &old_words),
+        removed: only(&old_words, &new_words),
+    }
+}
+
+// keeps the alphabetically smallest words of `words` missing from `other`
+fn collect_examples(words: &[&str], other: &[&str], examples: &mut BTreeSet<String>) {
+    let other = other.iter().collect::<HashSet<_>>();
+    for word in words.iter().filter(|word| !other.contains(word)) {
+        examples.insert(word.to_string());
+        if examples.len() > MAX_EXAMPLES {
+            examples.pop_last();
+        }
+    }
+}
+
+impl Comparison {
+    /// Summarizes the per-rack differences, or `None` if there are no racks.
+    pub fn summary(&self) -> Option<Summary> {
+        let mut differences = self
+            .racks
+            .iter()
+            .map(RackComparison::difference)
+            .collect::<Vec<_>>();
+        differences.sort_unstable();
+
+        let n = differences.len() as f64;
+        let mean = |value: fn(&RackComparison) -> i64| {
+            self.racks.iter().map(value).sum::<i64>() as f64 / n
+        };
+
+        Some(Summary {
+            mean_first: mean(|rack| rack.first as i64),
+            mean_second: mean(|rack| rack.second as i64),
+            mean: mean(RackComparison::difference),
+            median: percentile(&differences, 50)?,
+            p10: percentile(&differences, 10)?,
+            p90: percentile(&differences, 90)?,
+            min: *differences.first()?,
+            max: *differences.last()?,
+        })
+    }
+}
+
+// nearest-rank percentile of sorted values
+fn percentile(sorted: &[i64], p: usize) -> Option<i64> {
+    let rank = (p * sorted.len()).div_ceil(100).max(1);
+    sorted.get(rank - 1).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff() {
+        let old = ["stain", "satin", "ant", "tan"]
+            .iter()
+            .collect::<Dictionary>();
+        let new = ["tan", "ants", "satin", "saint", "zoo"]
+            .iter()
+            .collect::<Dictionary>();
+
+        let diff = super::diff(&old, &new);
+        assert_eq!(diff.added, ["ants", "saint", "zoo"]);
+        assert_eq!(diff.removed, ["ant", "stain"]);
+        assert_eq!(super::diff(&old, &old), DictionaryDiff::default());
+    }
+
+    #[test]
+    fn percentiles() {
+        let values = (1..=10).collect::<Vec<_>>();
+        assert_eq!(percentile(&values, 10), Some(1));
+        assert_eq!(percentile(&values, 50), Some(5));
+        assert_eq!(percentile(&values, 90), Some(9));
+        assert_eq!(percentile(&values, 100), Some(10));
+        assert_eq!(percentile(&[], 50), None);
+    }
+
+    #[test]
+    fn random_racks_are_deterministic() {
+        let racks = random_racks(42, 5, 7);
+        assert_eq!(racks, random_racks(42, 5, 7));
+        assert_ne!(racks, random_racks(43, 5, 7));
+        assert!(racks.iter().all(|rack| rack.len() == 7));
+    }
+
+    #[test]
+    fn compare() {
+        let small = ["cat", "act", "at"].iter().collect::<Dictionary>();
+        let big = ["cat", "act", "tac", "dog", "god"]
+            .iter()
+            .collect::<Dictionary>();
+        let query = SearchQuery::new(Rack::parse("", &RackOptions::default()).unwrap());
+        let racks = [
+            String::from("tac"),
+            String::from("dog"),
+            String::from("xyz"),
+        ];
+
+        let comparison = super::compare(&small, &big, &query, &racks).unwrap();
+        let differences = comparison
+            .racks
+            .iter()
+            .map(RackComparison::difference)
+            .collect::<Vec<_>>();
+        assert_eq!(differences, [0, 2, 0]);
+        assert_eq!(comparison.only_first.iter().collect::<Vec<_>>(), ["at"]);
+        assert_eq!(
+            comparison.only_second.iter().collect::<Vec<_>>(),
+            ["dog", "god", "tac"]
+        );
+
+        let summary = comparison.summary().unwrap();
+        assert_eq!(summary.median, 0);
+        assert_eq!(summary.max, 2);
+        assert!((summary.mean - 2.0 / 3.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,352 @@
+//! Filters of a query that contradict each other
+//!
+//! A query whose filters can't all hold matches no word, which looks just
+//! like a rack without words. `conflicts` tells those queries apart before
+//! searching, so that the mistake can be reported rather than answered with
+//! an empty list.
+
+use crate::count::CountSet;
+use crate::search::SearchQuery;
+use std::fmt;
+
+/// A reason why a query can't match any word
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Conflict {
+    /// The minimum length is above the maximum length
+    LengthBounds { min: usize, max: usize },
+    /// The prefix is longer than the maximum length
+    PrefixTooLong { prefix: usize, max: usize },
+    /// The words matching the pattern are all outside the length bounds
+    PatternLength {
+        pattern: (usize, Option<usize>),
+        min: usize,
+        max: Option<usize>,
+    },
+    /// More letters are required than the maximum length allows
+    TooManyRequired { required: usize, max: usize },
+    /// More different letters are required than the maximum allows
+    TooManyDistinct { required: usize, max: usize },
+    /// The required letters can't be made from the rack, even with its
+    /// blanks; holds the letters missing
+    RequiredNotInRack { missing: CountSet },
+    /// The prefix can't be made from the rack, even with its blanks; holds
+    /// the letters missing
+    PrefixNotInRack { missing: CountSet },
+    /// The words must be longer than the rack, blanks included
+    MinLengthAboveRack { min: usize, tiles: usize },
+    /// The words must use more tiles than the rack has
+    MinUseAboveRack { min: usize, tiles: usize },
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Conflict::LengthBounds { min, max } => write!(
+                f,
+                "the minimum length {} is above the maximum length {}",
+                min, max
+            ),
+            Conflict::PrefixTooLong { prefix, max } => write!(
+                f,
+                "the prefix has {} letters, more than the maximum length {}",
+                prefix, max
+            ),
+            Conflict::PatternLength { pattern, min, max } => {
+                match pattern {
+                    (shortest, Some(longest)) if shortest == longest => {
+                        write!(f, "the pattern matches words of {} letters", shortest)?
+                    }
+                    (shortest, _) => write!(
+                        f,
+                        "the pattern matches words of {} letters or more",
+                        shortest
+                    )?,
+                }
+                match max {
+                    Some(max) => write!(f, ", outside the lengths {} to {}", min, max),
+                    None => write!(f, ", below the minimum length {}", min),
+                }
+            }
+            Conflict::TooManyRequired { required, max } => write!(
+                f,
+                "{} letters are required, more than the maximum length {}",
+                required, max
+            ),
+            Conflict::TooManyDistinct { required, max } => write!(
+                f,
+                "{} different letters are required, more than the maximum of {}",
+                required, max
+            ),
+            Conflict::RequiredNotInRack { missing } => write!(
+                f,
+                "the required letters need {} more than the rack has",
+                missing
+            ),
+            Conflict::PrefixNotInRack { missing } => {
+                write!(f, "the prefix needs {} more than the rack has", missing)
+            }
+            Conflict::MinLengthAboveRack { min, tiles } => write!(
+                f,
+                "the minimum length {} is above the {} tiles of the rack",
+                min, tiles
+            ),
+            Conflict::MinUseAboveRack { min, tiles } => write!(
+                f,
+                "the words must use {} tiles, more than the {} of the rack",
+                min, tiles
+            ),
+        }
+    }
+}
+
+/// The contradictions between the filters of `query`, in the order of the
+/// `Conflict` variants; empty if some word could match.
+///
+/// The filters comparing words with the rack don't apply to inverted
+/// queries, which look for the words the rack can't make.
+pub fn conflicts(query: &SearchQuery) -> Vec<Conflict> {
+    let mut conflicts = filter_conflicts(query);
+    if query.invert {
+        return conflicts;
+    }
+
+    let min = query.min_length;
+    let rack = &query.rack;
+    let letters = rack.letter_counts();
+    // the letters of `needed` the rack doesn't have, if blanks can't make up
+    // for them
+    let missing = |needed: &CountSet| {
+        let missing = needed.difference(&letters);
+        (missing.total() > rack.blanks() as usize).then_some(missing)
+    };
+    if let Some(missing) = missing(&query.require) {
+        conflicts.push(Conflict::RequiredNotInRack { missing });
+    }
+    // too many of a letter for a `CountSet` is too many for any word
+    if let Some(missing) = CountSet::from_word(&query.starts_with)
+        .ok()
+        .and_then(|prefix| missing(&prefix))
+    {
+        conflicts.push(Conflict::PrefixNotInRack { missing });
+    }
+    let tiles = rack
+        .letters()
+        .iter()
+        .map(|&count| count as usize)
+        .sum::<usize>()
+        + rack.blanks() as usize;
+    if min > tiles {
+        conflicts.push(Conflict::MinLengthAboveRack { min, tiles });
+    }
+    if let Some(min) = query.min_use.filter(|&min| min > tiles) {
+        conflicts.push(Conflict::MinUseAboveRack { min, tiles });
+    }
+
+    conflicts
+}
+
+/// The contradictions between the filters of `query` that hold whatever the
+/// rack, such as for the many racks of a batch search.
+pub fn filter_conflicts(query: &SearchQuery) -> Vec<Conflict> {
+    let mut conflicts = Vec::new();
+    let min = query.min_length;
+    let prefix = query.starts_with.len();
+    let required = query.require.total();
+
+    if let Some(max) = query.max_length {
+        if min > max {
+            conflicts.push(Conflict::LengthBounds { min, max });
+        }
+        if prefix > max {
+            conflicts.push(Conflict::PrefixTooLong { prefix, max });
+        }
+    }
+    if let Some(pattern) = &query.pattern {
+        let (shortest, longest) = pattern.length_bounds();
+        if longest.is_some_and(|longest| longest < min)
+            || query.max_length.is_some_and(|max| shortest > max)
+        {
+            conflicts.push(Conflict::PatternLength {
+                pattern: (shortest, longest),
+                min,
+                max: query.max_length,
+            });
+        }
+    }
+    if let Some(max) = query.max_length.filter(|&max| required > max) {
+        conflicts.push(Conflict::TooManyRequired { required, max });
+    }
+    let distinct = query.require.distinct();
+    if let Some(max) = query.max_unique.filter(|&max| distinct > max) {
+        conflicts.push(Conflict::TooManyDistinct {
+            required: distinct,
+            max,
+        });
+    }
+
+    conflicts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern::Pattern;
+    use crate::rack::{Rack, RackOptions};
+
+    // a query for the rack, changed by `set`
+    fn query(rack: &str, set: impl FnOnce(&mut SearchQuery)) -> SearchQuery {
+        let mut query = SearchQuery::new(Rack::parse(rack, &RackOptions::default()).unwrap());
+        query.min_length = 3;
+        set(&mut query);
+        query
+    }
+
+    fn letters(word: &str) -> CountSet {
+        CountSet::from_word(word).unwrap()
+    }
+
+    #[test]
+    fn conflicts() {
+        let cases: Vec<(SearchQuery, Vec<Conflict>)> = vec![
+            (query("retains", |_| {}), vec![]),
+            (
+                query("retains", |q| q.max_length = Some(2)),
+                vec![Conflict::LengthBounds { min: 3, max: 2 }],
+            ),
+            (
+                query("retains", |q| {
+                    q.max_length = Some(4);
+                    q.starts_with = String::from("retai");
+                }),
+                vec![Conflict::PrefixTooLong { prefix: 5, max: 4 }],
+            ),
+            (
+                query("retains", |q| {
+                    q.pattern = Some(Pattern::parse("_X").unwrap())
+                }),
+                vec![Conflict::PatternLength {
+                    pattern: (2, Some(2)),
+                    min: 3,
+                    max: None,
+                }],
+            ),
+            (
+                query("retains", |q| {
+                    q.max_length = Some(5);
+                    q.pattern = Some(Pattern::parse("s_____*").unwrap());
+                }),
+                vec![Conflict::PatternLength {
+                    pattern: (6, None),
+                    min: 3,
+                    max: Some(5),
+                }],
+            ),
+            (
+                query("retains", |q| {
+                    q.pattern = Some(Pattern::parse("*__").unwrap())
+                }),
+                vec![],
+            ),
+            (
+                query("retains", |q| {
+                    q.max_length = Some(4);
+                    q.require = letters("resin");
+                }),
+                vec![Conflict::TooManyRequired {
+                    required: 5,
+                    max: 4,
+                }],
+            ),
+            (
+                query("retains", |q| {
+                    q.max_unique = Some(2);
+                    q.require = letters("rest");
+                }),
+                vec![Conflict::TooManyDistinct {
+                    required: 4,
+                    max: 2,
+                }],
+            ),
+            (
+                query("retains", |q| q.require = letters("zz")),
+                vec![Conflict::RequiredNotInRack {
+                    missing: letters("zz"),
+                }],
+            ),
+            (query("retains?", |q| q.require = letters("ez")), vec![]),
+            (
+                query("retains", |q| q.starts_with = String::from("tt")),
+                vec![Conflict::PrefixNotInRack {
+                    missing: letters("t"),
+                }],
+            ),
+            (
+                query("ab?", |q| q.min_length = 4),
+                vec![Conflict::MinLengthAboveRack { min: 4, tiles: 3 }],
+            ),
+            (
+                query("ab?", |q| q.min_use = Some(4)),
+                vec![Conflict::MinUseAboveRack { min: 4, tiles: 3 }],
+            ),
+            // an inverted query looks for the words the rack can't make
+            (
+                query("ab", |q| {
+                    q.invert = true;
+                    q.require = letters("zz");
+                    q.min_length = 4;
+                }),
+                vec![],
+            ),
+            (
+                query("ab", |q| {
+                    q.invert = true;
+                    q.max_length = Some(2);
+                }),
+                vec![Conflict::LengthBounds { min: 3, max: 2 }],
+            ),
+        ];
+
+        for (query, expected) in cases {
+            assert_eq!(super::conflicts(&query), expected, "{}", query);
+        }
+    }
+
+    #[test]
+    fn filter_conflicts() {
+        let query = query("ab", |q| {
+            q.min_length = 4;
+            q.max_length = Some(3);
+        });
+        assert_eq!(
+            super::conflicts(&query),
+            [
+                Conflict::LengthBounds { min: 4, max: 3 },
+                Conflict::MinLengthAboveRack { min: 4, tiles: 2 }
+            ]
+        );
+        assert_eq!(
+            super::filter_conflicts(&query),
+            [Conflict::LengthBounds { min: 4, max: 3 }]
+        );
+    }
+
+    #[test]
+    fn display() {
+        let conflict = Conflict::PatternLength {
+            pattern: (2, Some(2)),
+            min: 3,
+            max: Some(8),
+        };
+        assert_eq!(
+            conflict.to_string(),
+            "the pattern matches words of 2 letters, outside the lengths 3 to 8"
+        );
+        let conflict = Conflict::RequiredNotInRack {
+            missing: letters("zz"),
+        };
+        assert_eq!(
+            conflict.to_string(),
+            "the required letters need zz more than the rack has"
+        );
+    }
+}
@@ -1,8 +1,11 @@
 //! Letter counting module
 
+use crate::fold::{fold_case, fold_word};
+use std::borrow::Cow;
 use std::convert::{TryFrom, TryInto};
 use std::error::Error;
 use std::fmt;
+use std::str::FromStr;
 
 // function to turn 26-based index to 13-based index/offset pair
 #[inline(always)]
@@ -11,8 +14,16 @@ fn to_index_offset(index: u8) -> (usize, u8) {
     (index as usize / 2, index % 2 * 4)
 }
 
+// greatest common divisor, for `permutation_count`
+fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
 /// Error type returned by this module.
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum CountError {
     /// String contains non-ASCII characters
     NotAscii,
@@ -20,6 +31,10 @@ pub enum CountError {
     NotAlphabetic,
     /// Letter counter exceeded the count limit
     CountOverflow,
+    /// String is not a valid list of letter counts
+    InvalidMultiset,
+    /// Stored letter counts don't match the letters of their word
+    CountMismatch,
 }
 
 impl fmt::Display for CountError {
@@ -34,6 +49,8 @@ impl fmt::Display for CountError {
                 NotAlphabetic =>
                     "String contains characters other than letters (numbers, symbols, etc.)",
                 CountOverflow => "Letter counter exceeded the count limit",
+                InvalidMultiset => "String is not a valid list of letter counts",
+                CountMismatch => "Letter counts don't match the letters of the word",
             }
         )
     }
@@ -41,11 +58,91 @@ impl fmt::Display for CountError {
 
 impl Error for CountError {}
 
+/// How `normalize_letters` cleans up user input
+#[derive(Clone, Debug, Default)]
+pub struct NormalizeOptions {
+    /// Trim whitespace around the input
+    pub trim: bool,
+    /// Convert letters to lowercase
+    pub lowercase: bool,
+    /// Characters removed from the input, allowing inputs like `a, b, c`
+    pub separators: Vec<char>,
+    /// Read digits as the letters they look like, per `LEET_DIGITS`, so
+    /// that `h3llo` becomes `hello`
+    pub leet: bool,
+}
+
+/// The letter each digit stands for in "leetspeak", from 0 to 9:
+///
+/// | digit  | 0 | 1 | 2 | 3 | 4 | 5 | 6 | 7 | 8 | 9 |
+/// |--------|---|---|---|---|---|---|---|---|---|---|
+/// | letter | o | i | z | e | a | s | g | t | b | g |
+///
+/// `1` also often stands for `l`, but a digit can only have one reading.
+pub const LEET_DIGITS: [u8; 10] = *b"oizeasgtbg";
+
+/// Checks that a string is only made of ASCII letters, with the same errors
+/// wherever the string comes from (words, racks, query options, ...).
+pub fn validate_letters(s: &str) -> Result<(), CountError> {
+    if !s.is_ascii() {
+        return Err(CountError::NotAscii);
+    }
+
+    if !s.bytes().all(|b| b.is_ascii_alphabetic()) {
+        return Err(CountError::NotAlphabetic);
+    }
+
+    Ok(())
+}
+
+/// Cleans up user input before validation, borrowing it if there's nothing
+/// to change.
+pub fn normalize_letters<'a>(s: &'a str, options: &NormalizeOptions) -> Cow<'a, str> {
+    let s = if options.trim { s.trim() } else { s };
+    let mut s = Cow::Borrowed(s);
+
+    if s.contains(options.separators.as_slice()) {
+        s = Cow::Owned(s.replace(options.separators.as_slice(), ""));
+    }
+    if options.lowercase && s.bytes().any(|b| b.is_ascii_uppercase()) {
+        s = Cow::Owned(fold_word(&s));
+    }
+    if options.leet && s.bytes().any(|b| b.is_ascii_digit()) {
+        s = Cow::Owned(
+            s.chars()
+                .map(|c| match c.to_digit(10) {
+                    Some(digit) => LEET_DIGITS[digit as usize] as char,
+                    None => c,
+                })
+                .collect(),
+        );
+    }
+
+    s
+}
+
+/// The index of a letter, from 0 for A to 25 for Z, whatever its case
+#[inline]
+pub fn letter_index(b: u8) -> usize {
+    (fold_case(b) - b'a') as usize
+}
+
+/// Size of a `CountSet` in its packed form, as stored in dictionary files
+pub const PACKED_SIZE: usize = 13;
+
 // We assume that words only contain at most 15 instances of a letter.
 // The longest word that I can think of - "pneumonultramicroscopicsilicovolcanoconosis"
 // only has a maximum of 8 instances of a letter (the letter "o").
-#[derive(Clone, Hash, PartialEq, Eq)]
-pub struct CountSet([u8; 13]);
+#[cfg(not(feature = "unpacked-counts"))]
+#[derive(Clone, Default, Hash, PartialEq, Eq)]
+pub struct CountSet([u8; PACKED_SIZE]);
+
+// the same limit of 15, one byte per letter: no shifts or masks on access,
+// at twice the memory. It's not the default since it measured no faster to
+// search and slower to hash, index and load (see `benches/count_set.rs`).
+#[cfg(feature = "unpacked-counts")]
+#[derive(Clone, Default, Hash, PartialEq, Eq)]
+pub struct CountSet([u8; 26]);
 
 pub struct CountSetIter<'a> {
     count: &'a CountSet,
@@ -54,24 +151,17 @@ pub struct CountSetIter<'a> {
 
 impl CountSet {
     pub fn from_word(word: &str) -> Result<Self, CountError> {
-        if !word.is_ascii() {
-            return Err(CountError::NotAscii);
-        }
-
-        // We can treat characters as bytes, since we are guaranteed to have only
-        // characters in the ASCII range.
-        if !word.bytes().all(|b| b.is_ascii_alphabetic()) {
-            return Err(CountError::NotAlphabetic);
-        }
+        // We can treat characters as bytes afterwards, since we are guaranteed
+        // to have only letters in the ASCII range.
+        validate_letters(word)?;
 
         // Create a temporary, convenient array for counting letters, then try
         // to convert it later to the more compact form.
         let mut count = [0u8; 26];
 
         word.bytes()
-            .map(|b| b.to_ascii_uppercase())
-            .try_for_each(|b| {
-                let i = b as usize - 65;
+            .map(letter_index)
+            .try_for_each(|i| {
                 count[i] = count[i].checked_add(1)?;
                 Some(())
             })
@@ -80,21 +170,90 @@ impl CountSet {
         count.try_into()
     }
 
+    /// Decodes the packed form of the count set of `word`, as stored in
+    /// dictionary files, checking it against the letters of the word.
+    ///
+    /// Every 4-bit count of the packed form is valid, so a corrupted one
+    /// can only be caught by counting the word again. Digits count as the
+    /// letters they stand for, as in the words of `Dictionary::add_spelling`.
+    pub fn from_bytes_checked(bytes: [u8; PACKED_SIZE], word: &str) -> Result<Self, CountError> {
+        let leet = NormalizeOptions {
+            leet: true,
+            ..NormalizeOptions::default()
+        };
+        let count = Self::from(bytes);
+        if Self::from_word(&normalize_letters(word, &leet))? != count {
+            return Err(CountError::CountMismatch);
+        }
+
+        Ok(count)
+    }
+
+    /// Parses a list of letter counts, either in the `Debug` form
+    /// (`{'A': 2, 'B': 1}`) or as a simple list (`a:2, b:1`).
+    ///
+    /// Letters are case-insensitive, and repeated letters are added up.
+    pub fn parse_multiset(s: &str) -> Result<Self, CountError> {
+        if !s.is_ascii() {
+            return Err(CountError::NotAscii);
+        }
+
+        let s = s.trim();
+        let s = s
+            .strip_prefix('{')
+            .and_then(|s| s.strip_suffix('}'))
+            .unwrap_or(s);
+
+        let mut count = [0u8; 26];
+        for entry in s
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            let (letter, n) = entry.split_once(':').ok_or(CountError::InvalidMultiset)?;
+            let letter = letter.trim().trim_matches(|c| c == '\'' || c == '"');
+            let n = n
+                .trim()
+                .parse::<u8>()
+                .map_err(|_| CountError::InvalidMultiset)?;
+
+            let letter = match *letter.as_bytes() {
+                [b] if b.is_ascii_alphabetic() => b,
+                [_] => return Err(CountError::NotAlphabetic),
+                _ => return Err(CountError::InvalidMultiset),
+            };
+
+            let i = letter_index(letter);
+            count[i] = count[i].checked_add(n).ok_or(CountError::CountOverflow)?;
+        }
+
+        count.try_into()
+    }
+
     // doesn't perform bounds checks, `index` must be between 0 and 25
+    #[cfg(not(feature = "unpacked-counts"))]
     #[inline]
     unsafe fn index_unchecked(&self, index: u8) -> u8 {
         let (index, offset) = to_index_offset(index);
-        (self.0[index] & (0b1111 << offset)) >> offset
+        (self.0.get_unchecked(index) & (0b1111 << offset)) >> offset
     }
 
+    #[cfg(feature = "unpacked-counts")]
     #[inline]
-    pub fn iter(&self) -> CountSetIter {
+    unsafe fn index_unchecked(&self, index: u8) -> u8 {
+        *self.0.get_unchecked(index as usize)
+    }
+
+    #[inline]
+    pub fn iter(&self) -> CountSetIter<'_> {
         CountSetIter {
             count: self,
             index: 0,
         }
     }
 
+    /// The counts as stored in memory, which depends on the representation;
+    /// convert to `[u8; PACKED_SIZE]` for the packed form.
     #[inline]
     pub fn slice(&self) -> &[u8] {
         &self.0
@@ -103,6 +262,159 @@ impl CountSet {
     pub fn contains(&self, other: &Self) -> bool {
         self.iter().zip(other.iter()).all(|(s, o)| s >= o)
     }
+
+    /// Whether `k` copies of these letters fit within `rack`, i.e. every
+    /// count times `k` is at most the count of `rack`. The products are
+    /// computed in `u16`, since they can exceed the 15 a count holds.
+    pub fn scaled_fits(&self, k: u8, rack: &CountSet) -> bool {
+        self.iter()
+            .zip(rack.iter())
+            .all(|(need, have)| need as u16 * k as u16 <= have as u16)
+    }
+
+    /// The largest `k` for which `scaled_fits(k, rack)` holds: how many
+    /// times over these letters can be taken from `rack`. `u8::MAX` for no
+    /// letters at all.
+    pub fn max_multiplicity(&self, rack: &CountSet) -> u8 {
+        self.iter()
+            .zip(rack.iter())
+            .filter(|&(need, _)| need > 0)
+            .map(|(need, have)| have / need)
+            .min()
+            .unwrap_or(u8::MAX)
+    }
+
+    /// Total number of letters
+    pub fn total(&self) -> usize {
+        self.iter().map(|c| c as usize).sum()
+    }
+
+    /// Bit mask of the letters present, bit 0 for A: which letters, whatever
+    /// their counts. A word can only use the letters of a rack without blanks
+    /// if the rack's mask covers its own, which one AND tests.
+    #[cfg(not(feature = "unpacked-counts"))]
+    #[inline]
+    pub fn presence_mask(&self) -> u32 {
+        // even letters in the low nibbles, odd ones in the high ones
+        self.0.iter().enumerate().fold(0, |mask, (i, &b)| {
+            mask | ((b & 0x0f != 0) as u32) << (2 * i) | ((b >> 4 != 0) as u32) << (2 * i + 1)
+        })
+    }
+
+    #[cfg(feature = "unpacked-counts")]
+    #[inline]
+    pub fn presence_mask(&self) -> u32 {
+        self.0
+            .iter()
+            .enumerate()
+            .fold(0, |mask, (i, &c)| mask | ((c != 0) as u32) << i)
+    }
+
+    /// Number of different letters
+    pub fn distinct(&self) -> usize {
+        self.iter().filter(|&c| c > 0).count()
+    }
+
+    /// Number of different arrangements of the letters, the multinomial
+    /// coefficient `total! / (a! * b! * ... * z!)`: how many ways a rack of
+    /// these letters can be scrambled, the word among them. Saturates at
+    /// `u128::MAX`, which only sets of 35 letters or more can reach.
+    pub fn permutation_count(&self) -> u128 {
+        // multiplies in the letters one at a time: with `n` letters placed,
+        // placing the `i`th copy of a letter multiplies the arrangements by
+        // `(n + 1) / i`, which divides exactly once reduced by their gcd
+        let mut count = 1u128;
+        let mut placed = 0u128;
+        for c in self.iter() {
+            for i in 1..=c as u128 {
+                placed += 1;
+                let g = gcd(count, i);
+                count = match (count / g).checked_mul(placed / (i / g)) {
+                    Some(count) => count,
+                    None => return u128::MAX,
+                };
+            }
+        }
+        count
+    }
+
+    /// Number of letters present in exactly one of the two sets, whatever
+    /// their counts: how different the letters of two racks or words are.
+    pub fn hamming_distinct_letters(&self, other: &Self) -> usize {
+        self.iter()
+            .zip(other.iter())
+            .filter(|&(s, o)| (s > 0) != (o > 0))
+            .count()
+    }
+
+    /// The letters of `self` left over after taking away those of `other`,
+    /// ignoring the letters of `other` that `self` doesn't have.
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut count = [0u8; 26];
+        for (i, (s, o)) in self.iter().zip(other.iter()).enumerate() {
+            count[i] = s.saturating_sub(o);
+        }
+
+        // can't overflow, every count is at most the one of `self`
+        count.try_into().unwrap()
+    }
+
+    /// The letters still needed to cover `within` with the letters of
+    /// `self`: how many of each letter `self` is short, zero where it has
+    /// enough. `within.contains(self)` isn't required.
+    ///
+    /// This is `within.difference(self)`, the other way around.
+    #[inline]
+    pub fn complement(&self, within: &Self) -> Self {
+        within.difference(self)
+    }
+}
+
+/// Displays the letters in alphabetical order, e.g. `aab`.
+impl fmt::Display for CountSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, c) in self.iter().enumerate() {
+            for _ in 0..c {
+                write!(f, "{}", (b'a' + i as u8) as char)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses letter counts written as a letter followed by its count, such as
+/// `e2,a1,r1,t1`, or any form accepted by `parse_multiset`.
+impl FromStr for CountSet {
+    type Err = CountError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.contains([':', '{']) {
+            return Self::parse_multiset(s);
+        }
+        if !s.is_ascii() {
+            return Err(CountError::NotAscii);
+        }
+
+        let mut count = [0u8; 26];
+        for entry in s
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            let (letter, n) = entry.split_at(1);
+            let letter = letter.as_bytes()[0];
+            if !letter.is_ascii_alphabetic() {
+                return Err(CountError::NotAlphabetic);
+            }
+            let n = n.parse::<u8>().map_err(|_| CountError::InvalidMultiset)?;
+
+            let i = letter_index(letter);
+            count[i] = count[i].checked_add(n).ok_or(CountError::CountOverflow)?;
+        }
+
+        count.try_into()
+    }
 }
 
 impl fmt::Debug for CountSet {
@@ -125,23 +437,51 @@ impl fmt::Debug for CountSet {
     }
 }
 
-impl From<[u8; 13]> for CountSet {
-    fn from(array: [u8; 13]) -> Self {
+/// Trusts the packed form as is: every 4-bit nibble is a valid count (0 to 15),
+/// so any 13 bytes make a valid `CountSet`, though not necessarily the right
+/// one for a given word; see `Dictionary::count_mismatches` for that check.
+impl From<[u8; PACKED_SIZE]> for CountSet {
+    #[cfg(not(feature = "unpacked-counts"))]
+    fn from(array: [u8; PACKED_SIZE]) -> Self {
         Self(array)
     }
+
+    #[cfg(feature = "unpacked-counts")]
+    fn from(array: [u8; PACKED_SIZE]) -> Self {
+        let mut count = [0; 26];
+        for (i, c) in count.iter_mut().enumerate() {
+            let (index, offset) = to_index_offset(i as u8);
+            *c = (array[index] >> offset) & 0b1111;
+        }
+
+        Self(count)
+    }
 }
 
-impl From<CountSet> for [u8; 13] {
+impl From<CountSet> for [u8; PACKED_SIZE] {
+    #[cfg(not(feature = "unpacked-counts"))]
     fn from(count: CountSet) -> Self {
         count.0
     }
+
+    #[cfg(feature = "unpacked-counts")]
+    fn from(count: CountSet) -> Self {
+        let mut array = [0; PACKED_SIZE];
+        for (i, &c) in count.0.iter().enumerate() {
+            let (index, offset) = to_index_offset(i as u8);
+            array[index] |= c << offset;
+        }
+
+        array
+    }
 }
 
 impl TryFrom<[u8; 26]> for CountSet {
     type Error = CountError;
 
+    #[cfg(not(feature = "unpacked-counts"))]
     fn try_from(array: [u8; 26]) -> Result<Self, Self::Error> {
-        let mut count = [0; 13];
+        let mut count = [0; PACKED_SIZE];
         for (i, &c) in array.iter().enumerate() {
             if c > 15 {
                 return Err(CountError::CountOverflow);
@@ -153,6 +493,15 @@ impl TryFrom<[u8; 26]> for CountSet {
 
         Ok(Self(count))
     }
+
+    #[cfg(feature = "unpacked-counts")]
+    fn try_from(array: [u8; 26]) -> Result<Self, Self::Error> {
+        if array.iter().any(|&c| c > 15) {
+            return Err(CountError::CountOverflow);
+        }
+
+        Ok(Self(array))
+    }
 }
 
 impl From<CountSet> for [u8; 26] {
@@ -218,6 +567,169 @@ mod tests {
         assert_eq!(counted_word, count_set);
     }
 
+    #[test]
+    fn parse_multiset() {
+        let count_set = CountSet::from_word("hello").unwrap();
+        let debug = format!("{:?}", count_set);
+        assert_eq!(CountSet::parse_multiset(&debug).unwrap(), count_set);
+        assert_eq!(
+            CountSet::parse_multiset("h:1, e:1, l:2, o:1").unwrap(),
+            count_set
+        );
+        assert_eq!(
+            CountSet::parse_multiset("{l: 1, O:1, H:1, e: 1, l: 1}").unwrap(),
+            count_set
+        );
+        assert_eq!(CountSet::parse_multiset("{}").unwrap(), CountSet::default());
+
+        for (input, expected) in [
+            ("a2", "InvalidMultiset"),
+            ("a:x", "InvalidMultiset"),
+            ("ab:1", "InvalidMultiset"),
+            ("1:1", "NotAlphabetic"),
+            ("a:10, a:6", "CountOverflow"),
+        ] {
+            let error = CountSet::parse_multiset(input).unwrap_err();
+            assert_eq!(format!("{:?}", error), expected, "input {:?}", input);
+        }
+    }
+
+    #[test]
+    fn from_str() {
+        let count_set = CountSet::from_word("treat").unwrap();
+        assert_eq!("e1,a1,r1,t2".parse::<CountSet>().unwrap(), count_set);
+        assert_eq!(" T1, e1 ,A1,r1,t1 ".parse::<CountSet>().unwrap(), count_set);
+        assert_eq!("a:1, e:1, r:1, t:2".parse::<CountSet>().unwrap(), count_set);
+        assert_eq!("".parse::<CountSet>().unwrap(), CountSet::default());
+
+        for (input, expected) in [
+            ("a", "InvalidMultiset"),
+            ("ab2", "InvalidMultiset"),
+            ("12", "NotAlphabetic"),
+            ("é1", "NotAscii"),
+            ("a10,a6", "CountOverflow"),
+        ] {
+            let error = input.parse::<CountSet>().unwrap_err();
+            assert_eq!(format!("{:?}", error), expected, "input {:?}", input);
+        }
+    }
+
+    // 0, 1 and 15 in every low and high nibble, next to every other boundary
+    // value in the neighboring nibble
+    #[test]
+    fn nibble_boundaries() {
+        const VALUES: [u8; 3] = [0, 1, 15];
+
+        for i in 0..26 {
+            for &a in VALUES.iter() {
+                for &b in VALUES.iter() {
+                    let mut array = [0u8; 26];
+                    array[i] = a;
+                    if i + 1 < 26 {
+                        array[i + 1] = b;
+                    }
+
+                    let count_set = CountSet::try_from(array).unwrap();
+                    let packed = <[u8; 13]>::from(count_set.clone());
+                    let unpacked = CountSet::from(packed);
+                    assert_eq!(unpacked, count_set);
+                    assert_eq!(<[u8; 26]>::from(unpacked), array);
+                }
+            }
+        }
+
+        let mut array = [15u8; 26];
+        let count_set = CountSet::try_from(array).unwrap();
+        assert_eq!(<[u8; PACKED_SIZE]>::from(count_set.clone()), [0xff; 13]);
+        assert!(count_set.iter().all(|c| c == 15));
+
+        array[25] = 16;
+        match CountSet::try_from(array).unwrap_err() {
+            CountError::CountOverflow => {}
+            error => panic!("Wrong 'count_overflow' error! {:?}", error),
+        }
+    }
+
+    #[test]
+    fn from_bytes_checked() {
+        let bytes = <[u8; PACKED_SIZE]>::from(CountSet::from_word("hello").unwrap());
+        let count = CountSet::from_bytes_checked(bytes, "Hello").unwrap();
+        assert_eq!(count, CountSet::from_word("hello").unwrap());
+        // the leetspeak spelling of a word counts as its letters
+        assert_eq!(CountSet::from_bytes_checked(bytes, "h3ll0"), Ok(count));
+
+        for word in ["hell", "helloo", "world"] {
+            assert_eq!(
+                CountSet::from_bytes_checked(bytes, word),
+                Err(CountError::CountMismatch)
+            );
+        }
+        assert_eq!(
+            CountSet::from_bytes_checked(bytes, "he llo"),
+            Err(CountError::NotAlphabetic)
+        );
+    }
+
+    #[test]
+    fn distinct() {
+        assert_eq!(CountSet::from_word("banana").unwrap().distinct(), 3);
+        assert_eq!(CountSet::from_word("Aa").unwrap().distinct(), 1);
+        assert_eq!(CountSet::default().distinct(), 0);
+    }
+
+    #[test]
+    fn permutation_count() {
+        let count = |word| CountSet::from_word(word).unwrap().permutation_count();
+        assert_eq!(count(""), 1);
+        assert_eq!(count("aaa"), 1);
+        assert_eq!(count("cat"), 6);
+        // 6! / (3! * 2!)
+        assert_eq!(count("banana"), 60);
+        // 11! / (4! * 4! * 2!)
+        assert_eq!(count("mississippi"), 34650);
+        // 34 letters, 8 of them twice, close to the limit of a u128
+        assert_eq!(
+            count("abcdefghijklmnopqrstuvwxyzabcdefgh"),
+            (1..=34u128).product::<u128>() / 2u128.pow(8)
+        );
+        assert_eq!(
+            CountSet::try_from([15; 26]).unwrap().permutation_count(),
+            u128::MAX
+        );
+    }
+
+    #[test]
+    fn presence_mask() {
+        assert_eq!(CountSet::default().presence_mask(), 0);
+        assert_eq!(
+            CountSet::from_word("banana").unwrap().presence_mask(),
+            0b10_0000_0000_0011
+        );
+        assert_eq!(
+            CountSet::from_word("abcdefghijklmnopqrstuvwxyz")
+                .unwrap()
+                .presence_mask(),
+            (1 << 26) - 1
+        );
+        for i in 0..26 {
+            let mut array = [0; 26];
+            array[i] = 15;
+            let count_set = CountSet::try_from(array).unwrap();
+            assert_eq!(count_set.presence_mask(), 1 << i);
+        }
+    }
+
+    #[test]
+    fn complement() {
+        let target = CountSet::from_word("banana").unwrap();
+        let available = CountSet::from_word("abnz").unwrap();
+
+        // one a and one n in hand, the z is of no use
+        assert_eq!(available.complement(&target).to_string(), "aan");
+        assert_eq!(target.complement(&target), CountSet::default());
+        assert_eq!(CountSet::default().complement(&target), target);
+    }
+
     #[test]
     fn not_ascii() {
         let error = CountSet::from_word("こんにちは").unwrap_err();
@@ -244,4 +756,107 @@ mod tests {
             _ => panic!("Wrong 'count_overflow' error! {:?}", error),
         }
     }
+
+    #[test]
+    fn total() {
+        assert_eq!(CountSet::from_word("banana").unwrap().total(), 6);
+        assert_eq!(CountSet::default().total(), 0);
+    }
+
+    #[test]
+    fn hamming_distinct_letters() {
+        let word = |w| CountSet::from_word(w).unwrap();
+        // s, i and n are only in one of them, a and t in both
+        assert_eq!(word("stain").hamming_distinct_letters(&word("tat")), 3);
+        assert_eq!(word("tat").hamming_distinct_letters(&word("stain")), 3);
+        assert_eq!(word("banana").hamming_distinct_letters(&word("nab")), 0);
+        assert_eq!(word("ab").hamming_distinct_letters(&CountSet::default()), 2);
+    }
+
+    #[test]
+    fn scaled_fits() {
+        let word = |w| CountSet::from_word(w).unwrap();
+        let rack = word("aabbnnnnttt");
+
+        assert!(word("ban").scaled_fits(2, &rack));
+        assert!(!word("ban").scaled_fits(3, &rack));
+        assert!(word("nt").scaled_fits(3, &rack));
+        assert!(!word("tan").scaled_fits(3, &rack));
+        assert!(word("nn").scaled_fits(2, &rack));
+        assert!(!word("nn").scaled_fits(3, &rack));
+        assert!(word("xyz").scaled_fits(0, &rack));
+        assert!(CountSet::default().scaled_fits(3, &rack));
+        // 15 times 15 doesn't fit a count, nor wrap around
+        let full = CountSet::try_from([15; 26]).unwrap();
+        assert!(full.scaled_fits(1, &full));
+        assert!(!full.scaled_fits(15, &full));
+        assert!(!word("a").scaled_fits(u8::MAX, &full));
+
+        assert_eq!(word("ban").max_multiplicity(&rack), 2);
+        assert_eq!(word("nt").max_multiplicity(&rack), 3);
+        assert_eq!(word("nn").max_multiplicity(&rack), 2);
+        assert_eq!(word("x").max_multiplicity(&rack), 0);
+        assert_eq!(CountSet::default().max_multiplicity(&rack), u8::MAX);
+    }
+
+    #[test]
+    fn difference() {
+        let word = |w| CountSet::from_word(w).unwrap();
+        assert_eq!(word("banana").difference(&word("nab")).to_string(), "aan");
+        assert_eq!(word("ab").difference(&word("abcz")).to_string(), "");
+    }
+
+    #[test]
+    fn normalize_letters() {
+        let options = NormalizeOptions {
+            trim: true,
+            lowercase: true,
+            separators: vec![',', ' '],
+            leet: false,
+        };
+        assert_eq!(super::normalize_letters(" A, b,C ", &options), "abc");
+        assert!(matches!(
+            super::normalize_letters("abc", &options),
+            Cow::Borrowed("abc")
+        ));
+        assert_eq!(
+            super::normalize_letters(" A,b ", &NormalizeOptions::default()),
+            " A,b "
+        );
+    }
+
+    #[test]
+    fn leet() {
+        let options = NormalizeOptions {
+            leet: true,
+            ..NormalizeOptions::default()
+        };
+        assert_eq!(super::normalize_letters("h3ll0", &options), "hello");
+        assert_eq!(
+            super::normalize_letters("0123456789", &options),
+            "oizeasgtbg"
+        );
+        assert!(matches!(
+            super::normalize_letters("hello", &options),
+            Cow::Borrowed("hello")
+        ));
+        assert_eq!(
+            super::normalize_letters("h3ll0", &NormalizeOptions::default()),
+            "h3ll0"
+        );
+    }
+
+    #[test]
+    fn validate_letters() {
+        assert!(super::validate_letters("Hello").is_ok());
+        assert!(super::validate_letters("").is_ok());
+        assert!(matches!(
+            super::validate_letters("héllo"),
+            Err(CountError::NotAscii)
+        ));
+        assert!(matches!(
+            super::validate_letters("h3llo"),
+            Err(CountError::NotAlphabetic)
+        ));
+    }
 }
@@ -1,15 +1,99 @@
 use crate::count::{CountError, CountSet};
+use crate::fold::cmp_folded;
+use crate::hash::Sha256;
+use crate::par::*;
+use crate::search::{SearchDefaults, SearchQuery, MAX_WORD_LENGTH};
+use rand::seq::SliceRandom;
+use rand::Rng;
+#[cfg(feature = "parallel")]
 use rayon::iter::plumbing::{Consumer, UnindexedConsumer};
-use rayon::prelude::*;
+#[cfg(not(feature = "parallel"))]
+use std::collections::hash_map;
 use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
 
-type OffsetLength = (usize, usize);
+pub type OffsetLength = (usize, usize);
+
+/// Highest (rarest) difficulty tier, tiers being stored on 2 bits
+pub const MAX_TIER: u8 = 3;
+
+/// Names of the difficulty tiers, from tier 0 to `MAX_TIER`
+pub const TIER_NAMES: [&str; MAX_TIER as usize + 1] = ["common", "intermediate", "rare", "obscure"];
+
+/// Parses a difficulty tier, either its number or its name in `TIER_NAMES`,
+/// ignoring case
+pub fn parse_tier(s: &str) -> Result<u8, String> {
+    TIER_NAMES
+        .iter()
+        .position(|name| cmp_folded(name, s).is_eq())
+        .map(|tier| tier as u8)
+        .or_else(|| s.parse().ok().filter(|&tier| tier <= MAX_TIER))
+        .ok_or_else(|| {
+            format!(
+                "invalid tier {:?}, expected 0 to {} or one of {}",
+                s,
+                MAX_TIER,
+                TIER_NAMES.join(", ")
+            )
+        })
+}
+
+/// Most word lists a dictionary can tag its words with, the last index being
+/// left to untagged words in dictionary files
+pub const MAX_SOURCES: usize = u16::MAX as usize;
+
+/// Error type returned by `Dictionary::add_source` past `MAX_SOURCES`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TooManySources;
+
+impl fmt::Display for TooManySources {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "more than {} word lists", MAX_SOURCES)
+    }
+}
+
+impl Error for TooManySources {}
+
+/// Error type returned by `Dictionary::add_with_validator`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AddError {
+    /// The word was rejected by the validator
+    Rejected,
+    /// The word was accepted by the validator but isn't a valid word
+    InvalidWord(CountError),
+}
+
+impl fmt::Display for AddError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddError::Rejected => write!(f, "rejected by the validator"),
+            AddError::InvalidWord(err) => fmt::Display::fmt(err, f),
+        }
+    }
+}
+
+impl Error for AddError {}
+
+impl From<CountError> for AddError {
+    fn from(err: CountError) -> Self {
+        AddError::InvalidWord(err)
+    }
+}
 
 #[derive(Debug)]
 pub struct Dictionary {
     word_string: String,
     word_count: HashMap<OffsetLength, CountSet>,
     word_set: HashSet<Box<str>>,
+    // names of the word lists the words came from, if tagged
+    sources: Vec<String>,
+    word_sources: HashMap<OffsetLength, u16>,
+    // entries derived from other words rather than read from a word list
+    derived: HashSet<OffsetLength>,
+    // difficulty tier of the entries, from 0 (easy) to `MAX_TIER`, if known
+    tiers: HashMap<OffsetLength, u8>,
+    search_defaults: SearchDefaults,
 }
 
 impl Dictionary {
@@ -18,10 +102,20 @@ impl Dictionary {
             word_string: String::new(),
             word_count: HashMap::new(),
             word_set: HashSet::new(),
+            sources: Vec::new(),
+            word_sources: HashMap::new(),
+            derived: HashSet::new(),
+            tiers: HashMap::new(),
+            search_defaults: SearchDefaults::default(),
         }
     }
 
-    // for use in file reading *only*
+    /// Creates a dictionary from its raw parts, for use in file reading *only*.
+    ///
+    /// # Safety
+    ///
+    /// Every `(offset, len)` key must be a valid range of `word_string` falling
+    /// on character boundaries.
     pub unsafe fn from_raw_parts(
         word_string: String,
         word_count: HashMap<OffsetLength, CountSet>,
@@ -30,18 +124,301 @@ impl Dictionary {
             word_string,
             word_count,
             word_set: HashSet::new(),
+            sources: Vec::new(),
+            word_sources: HashMap::new(),
+            derived: HashSet::new(),
+            tiers: HashMap::new(),
+            search_defaults: SearchDefaults::default(),
         }
     }
 
     pub fn add(&mut self, word: &str) -> Result<(), CountError> {
+        self.add_spelling(word, word, None)
+    }
+
+    /// Adds a word spelled differently from its letters, such as `h3llo`
+    /// for `hello` (see `NormalizeOptions::leet`): searches use the letter
+    /// counts of `letters`, and list the word as `spelling`. The word is
+    /// tagged with `source` like with `add_with_source`, if any.
+    ///
+    /// The word then doesn't spell its letter counts: take them from its
+    /// entry (`DictionaryEntry::count_set`, `SearchResults::count_sets`)
+    /// rather than from the word. The filters on the text of the words,
+    /// `SearchQuery::starts_with` and `pattern`, test `spelling`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `spelling` and `letters` differ in length, since the
+    /// length of an entry must match its letter counts.
+    pub fn add_spelling(
+        &mut self,
+        spelling: &str,
+        letters: &str,
+        source: Option<u16>,
+    ) -> Result<(), CountError> {
+        assert_eq!(spelling.len(), letters.len(), "spelling of another length");
+
+        let word = spelling;
         if !self.word_set.contains(&Box::from(word)) {
+            // count first, so that an invalid word leaves nothing behind
+            let count_set = CountSet::from_word(letters)?;
             let offset = self.word_string.len();
             let len = word.len();
 
+            self.word_string.push_str(word);
+            self.word_count.insert((offset, len), count_set);
+            self.word_set.insert(Box::from(word));
+            if let Some(source) = source {
+                self.word_sources.insert((offset, len), source);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds the words of another dictionary, in the order of its word
+    /// string, tagged with `source`. Words already in the dictionary keep
+    /// their original source; the others keep their letter counts, whether
+    /// they were derived and their tier, if the other dictionary has tiers.
+    /// The search defaults aren't merged.
+    pub fn extend_from(&mut self, other: &Dictionary, source: u16) {
+        let mut keys = other.word_count.keys().copied().collect::<Vec<_>>();
+        keys.sort_unstable();
+        for key in keys {
+            let (offset, len) = key;
+            let word = &other.word_string[offset..(offset + len)];
+            if self.word_set.contains(word) {
+                continue;
+            }
+
+            let added = (self.word_string.len(), len);
             self.word_string.push_str(word);
             self.word_count
-                .insert((offset, len), CountSet::from_word(word)?);
+                .insert(added, other.word_count[&key].clone());
             self.word_set.insert(Box::from(word));
+            self.word_sources.insert(added, source);
+            if other.derived.contains(&key) {
+                self.derived.insert(added);
+            }
+            if let Some(tier) = other.tier(&key) {
+                self.tiers.insert(added, tier);
+            }
+        }
+    }
+
+    /// Reserves room for at least `words` more words of `bytes` bytes in all
+    pub fn reserve(&mut self, words: usize, bytes: usize) {
+        self.word_string.reserve(bytes);
+        self.word_count.reserve(words);
+        self.word_set.reserve(words);
+    }
+
+    /// Adds a word if `validator` accepts it, checking it with the custom rules
+    /// of the caller before the standard validation.
+    pub fn add_with_validator<F>(&mut self, word: &str, validator: F) -> Result<(), AddError>
+    where
+        F: Fn(&str) -> bool,
+    {
+        if !validator(word) {
+            return Err(AddError::Rejected);
+        }
+
+        Ok(self.add(word)?)
+    }
+
+    /// Registers the name of a word list, returning its index for use in
+    /// `add_with_source`.
+    ///
+    /// Fails past `MAX_SOURCES` word lists, whose indexes wouldn't fit.
+    pub fn add_source(&mut self, name: &str) -> Result<u16, TooManySources> {
+        match self.source_index(name) {
+            Some(index) => Ok(index),
+            None if self.sources.len() >= MAX_SOURCES => Err(TooManySources),
+            None => {
+                self.sources.push(name.to_owned());
+                Ok((self.sources.len() - 1) as u16)
+            }
+        }
+    }
+
+    /// Adds a word tagged with the index of the word list it came from.
+    ///
+    /// Words already in the dictionary keep their original source.
+    pub fn add_with_source(&mut self, word: &str, source: u16) -> Result<(), CountError> {
+        self.add_spelling(word, word, Some(source))
+    }
+
+    /// Adds a word derived from another word, such as an inflection, tagged
+    /// with the source of that word.
+    ///
+    /// Words already in the dictionary stay untagged.
+    pub fn add_derived(&mut self, word: &str, source: Option<u16>) -> Result<(), CountError> {
+        let offset = self.word_string.len();
+        let len = self.len();
+        self.add(word)?;
+        if self.len() > len {
+            let key = (offset, word.len());
+            self.derived.insert(key);
+            if let Some(source) = source {
+                self.word_sources.insert(key, source);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sets the derived entries, for use in file reading.
+    pub fn set_derived(&mut self, derived: HashSet<OffsetLength>) {
+        self.derived = derived;
+    }
+
+    /// Entries derived from other words rather than read from a word list
+    #[inline]
+    pub fn derived(&self) -> &HashSet<OffsetLength> {
+        &self.derived
+    }
+
+    /// Sets the difficulty tier of an entry, from 0 (easy) to `MAX_TIER`.
+    ///
+    /// Once any entry has a tier, the entries without one are in `MAX_TIER`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tier is above `MAX_TIER`.
+    pub fn set_tier(&mut self, key: OffsetLength, tier: u8) {
+        assert!(tier <= MAX_TIER, "tier {} above {}", tier, MAX_TIER);
+        self.tiers.insert(key, tier);
+    }
+
+    /// Sets the tier of each entry, for use in file reading.
+    pub fn set_tiers(&mut self, tiers: HashMap<OffsetLength, u8>) {
+        self.tiers = tiers;
+    }
+
+    /// Whether the entries have difficulty tiers
+    #[inline]
+    pub fn has_tiers(&self) -> bool {
+        !self.tiers.is_empty()
+    }
+
+    // `MAX_TIER` for the entries without one, if any entry has a tier
+    #[inline]
+    fn tier(&self, key: &OffsetLength) -> Option<u8> {
+        if self.tiers.is_empty() {
+            None
+        } else {
+            Some(self.tiers.get(key).copied().unwrap_or(MAX_TIER))
+        }
+    }
+
+    /// Sets the word list names and the source of each entry, for use in
+    /// file reading.
+    pub fn set_sources(&mut self, sources: Vec<String>, word_sources: HashMap<OffsetLength, u16>) {
+        self.sources = sources;
+        self.word_sources = word_sources;
+    }
+
+    /// Names of the word lists the words came from, if tagged
+    #[inline]
+    pub fn sources(&self) -> &[String] {
+        &self.sources
+    }
+
+    #[inline]
+    pub fn source_index(&self, name: &str) -> Option<u16> {
+        self.sources
+            .iter()
+            .position(|source| source == name)
+            .map(|index| index as u16)
+    }
+
+    #[inline]
+    pub fn word_sources(&self) -> &HashMap<OffsetLength, u16> {
+        &self.word_sources
+    }
+
+    /// Removes every word, source, tag and default, keeping the allocated memory
+    /// for reuse.
+    pub fn clear(&mut self) {
+        self.word_string.clear();
+        self.word_count.clear();
+        self.word_set.clear();
+        self.sources.clear();
+        self.word_sources.clear();
+        self.derived.clear();
+        self.tiers.clear();
+        self.search_defaults = SearchDefaults::default();
+    }
+
+    /// Suggested search options, applied unless the search overrides them
+    #[inline]
+    pub fn search_defaults(&self) -> &SearchDefaults {
+        &self.search_defaults
+    }
+
+    #[inline]
+    pub fn set_search_defaults(&mut self, defaults: SearchDefaults) {
+        self.search_defaults = defaults;
+    }
+
+    /// Creates a dictionary from a list of words, stopping at the first
+    /// invalid word.
+    ///
+    /// Use `collect` instead to silently skip invalid words.
+    pub fn from_words<I, S>(words: I) -> Result<Self, CountError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut dict = Self::new();
+        for word in words {
+            dict.add(word.as_ref())?;
+        }
+
+        Ok(dict)
+    }
+
+    /// Adds many words at once, counting their letters in parallel, which is
+    /// faster than `add` in a loop for large lists. The result is the same:
+    /// repeated words and words already in the dictionary are skipped, and
+    /// the others added in the order they come. If a word is invalid, none is
+    /// added.
+    ///
+    /// Without the `parallel` feature, this takes any iterator and counts
+    /// the letters sequentially.
+    #[cfg(feature = "parallel")]
+    pub fn par_extend<I>(&mut self, words: I) -> Result<(), CountError>
+    where
+        I: IntoParallelIterator<Item = String>,
+    {
+        self.extend_counted(words.into_par_iter().collect())
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    pub fn par_extend<I>(&mut self, words: I) -> Result<(), CountError>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        self.extend_counted(words.into_iter().collect())
+    }
+
+    // the offsets of the words must not depend on how the counting was
+    // split, so every word is counted first, then the new ones appended in
+    // the order they came
+    fn extend_counted(&mut self, words: Vec<String>) -> Result<(), CountError> {
+        let counts = words
+            .par_iter()
+            .map(|word| CountSet::from_word(word))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.word_count.reserve(words.len());
+        self.word_set.reserve(words.len());
+        for (word, count_set) in words.into_iter().zip(counts) {
+            if self.word_set.insert(Box::from(word.as_str())) {
+                let offset = self.word_string.len();
+                self.word_string.push_str(&word);
+                self.word_count.insert((offset, word.len()), count_set);
+            }
         }
 
         Ok(())
@@ -63,20 +440,282 @@ impl Dictionary {
     }
 
     #[inline]
-    pub fn par_iter(&self) -> ParDictionaryIter {
-        ParDictionaryIter { dict: self }
+    pub fn is_empty(&self) -> bool {
+        self.word_count.is_empty()
+    }
+
+    /// Total number of letters of all the entries, i.e. the tiles needed to
+    /// spell every word once
+    pub fn total_letters(&self) -> u64 {
+        self.word_count
+            .par_iter()
+            .map(|(_, set)| set.total() as u64)
+            .sum()
+    }
+
+    #[inline]
+    pub fn get(&self, key: &OffsetLength) -> Option<DictionaryEntry<'_>> {
+        let (&(offset, len), count_set) = self.word_count.get_key_value(key)?;
+
+        Some(DictionaryEntry {
+            word: &self.word_string[offset..(offset + len)],
+            count_set,
+            source: self.word_sources.get(key).copied(),
+            derived: self.derived.contains(key),
+            tier: self.tier(key),
+        })
+    }
+
+    /// The entry of a word borrowed from this dictionary, such as the words
+    /// of `SearchResults`, found from where it lies in the word string;
+    /// `None` for any other string, even an equal one.
+    pub fn entry_of(&self, word: &str) -> Option<DictionaryEntry<'_>> {
+        let offset = (word.as_ptr() as usize).checked_sub(self.word_string.as_ptr() as usize)?;
+        self.get(&(offset, word.len()))
+            .filter(|entry| std::ptr::eq(entry.word, word))
+    }
+
+    /// Recomputes the count set of every entry, returning the entries whose
+    /// stored count set doesn't match the letters of their word, reading
+    /// digits as leetspeak letters for the words added with `add_spelling`.
+    pub fn count_mismatches(&self) -> Vec<OffsetLength> {
+        let mut mismatches = self
+            .word_count
+            .par_iter()
+            .filter(|(&(offset, len), set)| {
+                // out-of-bounds entries are reported as mismatches too
+                let word = self.word_string.get(offset..offset.saturating_add(len));
+                word.is_none_or(|word| {
+                    CountSet::from_bytes_checked((*set).clone().into(), word).is_err()
+                })
+            })
+            .map(|(&key, _)| key)
+            .collect::<Vec<_>>();
+
+        mismatches.sort_unstable();
+        mismatches
+    }
+
+    /// SHA-256 hash of the words of the dictionary, for cache keys and
+    /// provenance: the hash of the distinct words in byte order, each
+    /// followed by a newline (`0x0a`). It only depends on the set of words,
+    /// as spelled, not on their order, the file format version or anything
+    /// else the dictionary holds (sources, tiers, defaults...).
+    ///
+    /// The definition is part of the stable API: a release changing the hash
+    /// of a dictionary is a breaking one.
+    pub fn content_hash(&self) -> [u8; 32] {
+        let mut words = self
+            .word_count
+            .keys()
+            .filter_map(|&(offset, len)| self.word_string.get(offset..offset.saturating_add(len)))
+            .collect::<Vec<_>>();
+        words.par_sort_unstable();
+        words.dedup();
+
+        let mut hasher = Sha256::new();
+        for word in words {
+            hasher.update(word.as_bytes());
+            hasher.update(b"\n");
+        }
+        hasher.finish()
+    }
+
+    /// Returns the pairs of entries whose ranges of the word string overlap,
+    /// in order of offset. Only corrupted or hand-edited files have any: such
+    /// entries alias parts of other words.
+    pub fn overlapping_entries(&self) -> Vec<(OffsetLength, OffsetLength)> {
+        let mut entries = self
+            .word_count
+            .keys()
+            .copied()
+            .filter(|&(_, len)| len > 0)
+            .collect::<Vec<_>>();
+        entries.par_sort_unstable();
+
+        // sweep by offset, keeping the entries that haven't ended yet
+        let mut overlaps = Vec::new();
+        let mut open: Vec<OffsetLength> = Vec::new();
+        for &(offset, len) in entries.iter() {
+            open.retain(|&(other_offset, other_len)| other_offset + other_len > offset);
+            overlaps.extend(open.iter().map(|&other| (other, (offset, len))));
+            open.push((offset, len));
+        }
+
+        overlaps
+    }
+
+    /// Returns the groups of entries spelling the same word, each in order of
+    /// offset. Only corrupted or hand-edited files have any, since `add`
+    /// skips words already present.
+    pub fn duplicate_entries(&self) -> Vec<Vec<OffsetLength>> {
+        let mut by_word = HashMap::<&str, Vec<OffsetLength>>::new();
+        for &(offset, len) in self.word_count.keys() {
+            by_word
+                .entry(&self.word_string[offset..(offset + len)])
+                .or_default()
+                .push((offset, len));
+        }
+
+        let mut duplicates = by_word
+            .into_values()
+            .filter(|entries| entries.len() > 1)
+            .map(|mut entries| {
+                entries.sort_unstable();
+                entries
+            })
+            .collect::<Vec<_>>();
+        duplicates.sort_unstable();
+        duplicates
+    }
+
+    /// Removes every entry spelling the same word as an entry at a lower
+    /// offset, returning how many were removed. Their text is left in the
+    /// word string.
+    pub fn remove_duplicates(&mut self) -> usize {
+        let duplicates = self
+            .duplicate_entries()
+            .into_iter()
+            .flat_map(|entries| entries.into_iter().skip(1))
+            .collect::<Vec<_>>();
+
+        for key in duplicates.iter() {
+            self.word_count.remove(key);
+            self.word_sources.remove(key);
+            self.derived.remove(key);
+            self.tiers.remove(key);
+        }
+
+        duplicates.len()
+    }
+
+    /// Picks a word of `len` letters uniformly at random, or `None` if there
+    /// are none. The words are sorted first, so that the same random numbers
+    /// pick the same word whatever the order of the entries.
+    pub fn random_word<R: Rng + ?Sized>(&self, len: usize, rng: &mut R) -> Option<&str> {
+        let mut words = self
+            .word_count
+            .par_iter()
+            .filter(|&(&(_, word_len), _)| word_len == len)
+            .map(|(&(offset, len), _)| &self.word_string[offset..(offset + len)])
+            .collect::<Vec<_>>();
+        words.par_sort_unstable();
+
+        words.choose(rng).copied()
+    }
+
+    /// Iterates over the entries, in parallel with the `parallel` feature
+    #[inline]
+    pub fn par_iter(&self) -> ParDictionaryIter<'_> {
+        ParDictionaryIter {
+            dict: self,
+            #[cfg(not(feature = "parallel"))]
+            entries: self.word_count.iter(),
+        }
+    }
+
+    #[inline]
+    fn entry<'a>(
+        &'a self,
+        &(offset, len): &OffsetLength,
+        count_set: &'a CountSet,
+    ) -> DictionaryEntry<'a> {
+        DictionaryEntry {
+            word: &self.word_string[offset..(offset + len)],
+            count_set,
+            source: if self.word_sources.is_empty() {
+                None
+            } else {
+                self.word_sources.get(&(offset, len)).copied()
+            },
+            derived: !self.derived.is_empty() && self.derived.contains(&(offset, len)),
+            tier: self.tier(&(offset, len)),
+        }
+    }
+
+    /// Number of words matching the query for each length, counted without
+    /// collecting or sorting the words.
+    pub fn count_matches_by_length(&self, query: &SearchQuery) -> [u32; MAX_WORD_LENGTH + 1] {
+        self.par_iter()
+            .filter(|entry| query.matches(entry))
+            .fold_with([0; MAX_WORD_LENGTH + 1], |mut counts, entry| {
+                counts[entry.len()] += 1;
+                counts
+            })
+            .reduce_with(|mut counts, other| {
+                counts.iter_mut().zip(other).for_each(|(c, o)| *c += o);
+                counts
+            })
+            .unwrap_or([0; MAX_WORD_LENGTH + 1])
+    }
+}
+
+impl Default for Dictionary {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Adds every valid word, silently skipping the invalid ones.
+///
+/// Use `Dictionary::add` or `Dictionary::from_words` to handle errors.
+impl<S: AsRef<str>> Extend<S> for Dictionary {
+    fn extend<I: IntoIterator<Item = S>>(&mut self, words: I) {
+        for word in words {
+            let _ = self.add(word.as_ref());
+        }
+    }
+}
+
+/// Collects every valid word, silently skipping the invalid ones.
+///
+/// Use `Dictionary::from_words` to handle errors.
+impl<S: AsRef<str>> FromIterator<S> for Dictionary {
+    fn from_iter<I: IntoIterator<Item = S>>(words: I) -> Self {
+        let mut dict = Self::new();
+        dict.extend(words);
+        dict
     }
 }
 
 pub struct DictionaryEntry<'a> {
     pub word: &'a str,
     pub count_set: &'a CountSet,
+    /// Index of the word list the word came from, if tagged
+    pub source: Option<u16>,
+    /// Whether the word was derived from another word
+    pub derived: bool,
+    tier: Option<u8>,
+}
+
+impl DictionaryEntry<'_> {
+    /// Length of the word, which matches its letter counts in dictionaries
+    /// read from files
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.word.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.word.is_empty()
+    }
+
+    /// Difficulty tier of the word, from 0 (easy) to `MAX_TIER`, if the
+    /// dictionary has tiers
+    #[inline]
+    pub fn tier(&self) -> Option<u8> {
+        self.tier
+    }
 }
 
 pub struct ParDictionaryIter<'a> {
     dict: &'a Dictionary,
+    #[cfg(not(feature = "parallel"))]
+    entries: hash_map::Iter<'a, OffsetLength, CountSet>,
 }
 
+#[cfg(feature = "parallel")]
 impl<'a> ParallelIterator for ParDictionaryIter<'a> {
     type Item = DictionaryEntry<'a>;
 
@@ -84,22 +723,39 @@ impl<'a> ParallelIterator for ParDictionaryIter<'a> {
     where
         C: UnindexedConsumer<Self::Item>,
     {
-        let par_iter = self
-            .dict
+        let dict = self.dict;
+        let par_iter = dict
             .word_count
             .par_iter()
-            .map(|(&(offset, len), set)| DictionaryEntry {
-                word: &self.dict.word_string[offset..(offset + len)],
-                count_set: set,
-            });
+            .map(|(key, set)| dict.entry(key, set));
 
         par_iter.drive_unindexed(consumer)
     }
 }
 
+#[cfg(not(feature = "parallel"))]
+impl<'a> Iterator for ParDictionaryIter<'a> {
+    type Item = DictionaryEntry<'a>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, set) = self.entries.next()?;
+        Some(self.dict.entry(key, set))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+#[cfg(not(feature = "parallel"))]
+impl ExactSizeIterator for ParDictionaryIter<'_> {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::hash::to_hex;
 
     #[test]
     fn sanity_check() {
@@ -151,4 +807,445 @@ mod tests {
 
         assert_eq!(dict.len(), 4);
     }
+
+    #[test]
+    fn validator() {
+        let mut dict = Dictionary::new();
+        let long_enough = |word: &str| word.len() >= 3;
+
+        assert_eq!(dict.add_with_validator("cat", long_enough), Ok(()));
+        assert_eq!(
+            dict.add_with_validator("at", long_enough),
+            Err(AddError::Rejected)
+        );
+        // the validator runs first
+        assert_eq!(
+            dict.add_with_validator("c4", long_enough),
+            Err(AddError::Rejected)
+        );
+        assert_eq!(
+            dict.add_with_validator("c4t", long_enough),
+            Err(AddError::InvalidWord(CountError::NotAlphabetic))
+        );
+        assert_eq!(dict.len(), 1);
+    }
+
+    #[test]
+    fn collect() {
+        let dict = ["apple", "b4nana", "cherry", "apple", "dürian"]
+            .iter()
+            .collect::<Dictionary>();
+        assert_eq!(dict.len(), 2);
+        assert_eq!(dict.word_string(), "applecherry");
+
+        let mut dict = dict;
+        dict.extend(vec![String::from("fig"), String::from("gr@pe")]);
+        assert_eq!(dict.len(), 3);
+
+        let err = Dictionary::from_words(["kiwi", "l1me"]).unwrap_err();
+        match err {
+            CountError::NotAlphabetic => {}
+            _ => panic!("Wrong 'not_alphabetic' error!"),
+        }
+    }
+
+    #[test]
+    fn par_extend() {
+        let words = ["stain", "satin", "ant", "Stain", "ant", "tan", "quit"];
+        let mut added = Dictionary::from_words(["tan", "zoo"]).unwrap();
+        let mut extended = Dictionary::from_words(["tan", "zoo"]).unwrap();
+        for word in words {
+            added.add(word).unwrap();
+        }
+        extended
+            .par_extend(words.map(String::from).to_vec())
+            .unwrap();
+        assert_eq!(extended.word_string(), added.word_string());
+        assert_eq!(extended.word_count(), added.word_count());
+
+        // a single invalid word leaves the dictionary as it was
+        let before = extended.word_string().to_owned();
+        let err = extended
+            .par_extend(vec![String::from("fig"), String::from("l1me")])
+            .unwrap_err();
+        assert_eq!(err, CountError::NotAlphabetic);
+        assert_eq!(extended.word_string(), before);
+        extended.add("fig").unwrap();
+        assert_eq!(extended.len(), added.len() + 1);
+
+        let mut large = Dictionary::new();
+        large.par_extend(numbered_words(50_000)).unwrap();
+        assert_eq!(large.word_string(), numbered_words(50_000).concat());
+    }
+
+    #[test]
+    fn clear() {
+        let mut dict = Dictionary::new();
+        let source = dict.add_source("twl").unwrap();
+        dict.add_with_source("ladies", source).unwrap();
+        dict.add("gentlemen").unwrap();
+        let capacity = dict.word_string.capacity();
+
+        dict.clear();
+        assert!(dict.is_empty());
+        assert!(dict.sources().is_empty());
+        assert_eq!(dict.word_string.capacity(), capacity);
+
+        // cleared words can be added again
+        dict.add("ladies").unwrap();
+        assert_eq!(dict.len(), 1);
+        assert_eq!(dict.word_string(), "ladies");
+    }
+
+    #[test]
+    fn count_matches_by_length() {
+        use crate::rack::{Rack, RackOptions};
+
+        let dict = ["at", "tan", "ant", "ants", "stain", "satin", "quit"]
+            .iter()
+            .collect::<Dictionary>();
+        let query = SearchQuery::new(Rack::parse("satin", &RackOptions::default()).unwrap());
+
+        let counts = dict.count_matches_by_length(&query);
+        assert_eq!(counts[..6], [0, 0, 1, 2, 1, 2]);
+        assert!(counts[6..].iter().all(|&count| count == 0));
+    }
+
+    #[test]
+    fn sources() {
+        let mut dict = Dictionary::new();
+        let twl = dict.add_source("twl").unwrap();
+        let sowpods = dict.add_source("sowpods").unwrap();
+        assert_eq!(dict.add_source("twl").unwrap(), twl);
+
+        dict.add_with_source("cat", twl).unwrap();
+        dict.add_with_source("dog", sowpods).unwrap();
+        dict.add_with_source("cat", sowpods).unwrap();
+        dict.add("emu").unwrap();
+
+        let sources = ["cat", "dog", "emu"]
+            .iter()
+            .map(|&word| {
+                dict.par_iter()
+                    .find_first(|entry| entry.word == word)
+                    .unwrap()
+                    .source
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(sources, [Some(twl), Some(sowpods), None]);
+        assert_eq!(dict.sources(), ["twl", "sowpods"]);
+    }
+
+    #[test]
+    fn too_many_sources() {
+        let mut dict = Dictionary::new();
+        let names = (0..MAX_SOURCES).map(|i| i.to_string()).collect();
+        dict.set_sources(names, HashMap::new());
+
+        assert_eq!(dict.add_source("0"), Ok(0));
+        assert_eq!(dict.add_source("twl"), Err(TooManySources));
+        assert_eq!(dict.sources().len(), MAX_SOURCES);
+    }
+
+    #[test]
+    fn extend_from() {
+        let mut en = Dictionary::new();
+        en.add("cat").unwrap();
+        en.add_derived("cats", None).unwrap();
+        en.set_tier((0, 3), 1);
+        let fr = ["chat", "cat"].iter().collect::<Dictionary>();
+
+        let mut dict = Dictionary::new();
+        let en_source = dict.add_source("en").unwrap();
+        let fr_source = dict.add_source("fr").unwrap();
+        dict.extend_from(&en, en_source);
+        dict.extend_from(&fr, fr_source);
+
+        assert_eq!(dict.len(), 3);
+        let tags = ["cat", "cats", "chat"].map(|word| {
+            let entry = dict
+                .par_iter()
+                .find_first(|entry| entry.word == word)
+                .unwrap();
+            (entry.source, entry.derived, entry.tier())
+        });
+        // the words of a dictionary without tiers are in the last one
+        assert_eq!(
+            tags,
+            [
+                (Some(en_source), false, Some(1)),
+                (Some(en_source), true, Some(MAX_TIER)),
+                (Some(fr_source), false, Some(MAX_TIER))
+            ]
+        );
+        assert_eq!(dict.count_mismatches(), []);
+    }
+
+    #[test]
+    fn derived() {
+        let mut dict = Dictionary::new();
+        let twl = dict.add_source("twl").unwrap();
+        dict.add_with_source("cat", twl).unwrap();
+        dict.add_derived("cats", Some(twl)).unwrap();
+        dict.add_derived("cat", None).unwrap();
+
+        let cats = dict
+            .par_iter()
+            .find_first(|entry| entry.word == "cats")
+            .unwrap();
+        assert!(cats.derived);
+        assert_eq!(cats.source, Some(twl));
+        let cat = dict
+            .par_iter()
+            .find_first(|entry| entry.word == "cat")
+            .unwrap();
+        assert!(!cat.derived);
+        assert_eq!(dict.derived().len(), 1);
+    }
+
+    #[test]
+    fn tiers() {
+        let mut dict = ["the", "cat", "qat"].iter().collect::<Dictionary>();
+        assert!(!dict.has_tiers());
+        assert_eq!(dict.get(&(0, 3)).unwrap().tier(), None);
+
+        dict.set_tier((0, 3), 0);
+        dict.set_tier((3, 3), 1);
+        let tiers = ["the", "cat", "qat"]
+            .iter()
+            .map(|&word| {
+                dict.par_iter()
+                    .find_first(|entry| entry.word == word)
+                    .unwrap()
+                    .tier()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(tiers, [Some(0), Some(1), Some(MAX_TIER)]);
+
+        assert_eq!(parse_tier("2"), Ok(2));
+        assert_eq!(parse_tier("Common"), Ok(0));
+        assert_eq!(parse_tier("obscure"), Ok(MAX_TIER));
+        assert!(parse_tier("4").is_err());
+        assert!(parse_tier("hard").is_err());
+        assert!(parse_tier("COMMONS").is_err());
+    }
+
+    #[test]
+    fn entry_of() {
+        let mut dict = Dictionary::new();
+        let red = dict.add_source("red").unwrap();
+        dict.add_with_source("cat", red).unwrap();
+        dict.add("act").unwrap();
+
+        let words = dict.par_iter().map(|entry| entry.word).collect::<Vec<_>>();
+        for word in words {
+            let entry = dict.entry_of(word).unwrap();
+            assert_eq!(entry.word, word);
+            assert_eq!(entry.source, (word == "cat").then_some(red));
+        }
+        // equal strings that don't come from the dictionary
+        assert!(dict.entry_of("cat").is_none());
+        assert!(dict.entry_of(&dict.word_string()[1..3]).is_none());
+    }
+
+    #[test]
+    fn spelling() {
+        let mut dict = Dictionary::new();
+        dict.add_spelling("h3llo", "hello", Some(0)).unwrap();
+        dict.add("hello").unwrap();
+        assert_eq!(dict.len(), 2);
+
+        let entry = dict.get(&(0, 5)).unwrap();
+        assert_eq!(entry.word, "h3llo");
+        assert_eq!(entry.source, Some(0));
+        assert_eq!(entry.count_set, &CountSet::from_word("hello").unwrap());
+        assert!(dict.count_mismatches().is_empty());
+        assert!(dict.add_spelling("c4t", "c4t", None).is_err());
+    }
+
+    // golden values: caches outside this crate are keyed on the hash, so it
+    // must not change between releases
+    #[test]
+    fn content_hash() {
+        let dict = ["dog", "Cat"].iter().collect::<Dictionary>();
+        assert_eq!(
+            to_hex(&dict.content_hash()),
+            "85aa45103ead7bf3779d0f046e49982c41094f9351b1ad247d04f202650c4a8a"
+        );
+        assert_eq!(
+            to_hex(&Dictionary::new().content_hash()),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+
+        // only the set of words counts
+        let mut same = ["Cat", "dog"].iter().collect::<Dictionary>();
+        same.set_tier((0, 3), 1);
+        same.add_source("twl").unwrap();
+        assert_eq!(same.content_hash(), dict.content_hash());
+        let other = ["dog", "cat"].iter().collect::<Dictionary>();
+        assert_ne!(other.content_hash(), dict.content_hash());
+    }
+
+    #[test]
+    fn count_mismatches() {
+        let mut word_count = HashMap::new();
+        word_count.insert((0, 3), CountSet::from_word("cat").unwrap());
+        word_count.insert((3, 3), CountSet::from_word("dot").unwrap());
+        word_count.insert((6, 4), CountSet::from_word("bird").unwrap());
+        let dict = unsafe { Dictionary::from_raw_parts(String::from("catdogbird"), word_count) };
+
+        assert_eq!(dict.count_mismatches(), [(3, 3)]);
+    }
+
+    // "tan" and "tank" overlap "stank", and "tan" is also spelled at the end
+    fn corrupted() -> Dictionary {
+        let mut word_count = HashMap::new();
+        for (key, word) in [
+            ((0, 5), "stank"),
+            ((1, 3), "tan"),
+            ((1, 4), "tank"),
+            ((5, 3), "cat"),
+            ((8, 3), "tan"),
+        ] {
+            word_count.insert(key, CountSet::from_word(word).unwrap());
+        }
+        unsafe { Dictionary::from_raw_parts(String::from("stankcattan"), word_count) }
+    }
+
+    #[test]
+    fn overlapping_entries() {
+        assert_eq!(
+            corrupted().overlapping_entries(),
+            [((0, 5), (1, 3)), ((0, 5), (1, 4)), ((1, 3), (1, 4))]
+        );
+
+        let dict = ["stank", "tan", "cat"].iter().collect::<Dictionary>();
+        assert!(dict.overlapping_entries().is_empty());
+    }
+
+    #[test]
+    fn duplicate_entries() {
+        assert_eq!(corrupted().duplicate_entries(), [vec![(1, 3), (8, 3)]]);
+    }
+
+    #[test]
+    fn remove_duplicates() {
+        let mut dict = corrupted();
+        assert_eq!(dict.remove_duplicates(), 1);
+        assert_eq!(dict.len(), 4);
+        assert!(dict.get(&(1, 3)).is_some());
+        assert!(dict.duplicate_entries().is_empty());
+    }
+
+    #[test]
+    fn total_letters() {
+        let dict = ["ladies", "and", "gentlemen", "and"]
+            .iter()
+            .collect::<Dictionary>();
+        assert_eq!(dict.total_letters(), 18);
+        assert_eq!(Dictionary::new().total_letters(), 0);
+    }
+
+    #[test]
+    fn random_word() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let dict = ["stain", "satin", "saint", "ant", "tan"]
+            .iter()
+            .collect::<Dictionary>();
+        let pick = |len, seed| dict.random_word(len, &mut StdRng::seed_from_u64(seed));
+
+        assert!(["stain", "satin", "saint"].contains(&pick(5, 0).unwrap()));
+        assert_eq!(pick(3, 7), pick(3, 7));
+        assert_eq!(pick(4, 0), None);
+        // every word can be picked
+        let mut picked = (0..100)
+            .filter_map(|seed| pick(5, seed))
+            .collect::<Vec<_>>();
+        picked.sort_unstable();
+        picked.dedup();
+        assert_eq!(picked, ["saint", "satin", "stain"]);
+    }
+
+    // a dictionary of `n` words of 4 letters or more
+    fn numbered(n: usize) -> Dictionary {
+        numbered_words(n).into_iter().collect()
+    }
+
+    // `n` different words of 4 letters or more
+    fn numbered_words(n: usize) -> Vec<String> {
+        (0..n)
+            .map(|mut i| {
+                let mut word = b"aaa".to_vec();
+                loop {
+                    word.push(b'a' + (i % 26) as u8);
+                    i /= 26;
+                    if i == 0 {
+                        break String::from_utf8(word).unwrap();
+                    }
+                }
+            })
+            .collect()
+    }
+
+    // checks every way of consuming the entries against the number of words
+    fn check_par_iter(dict: &Dictionary) {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let n = dict.len();
+        let mut words = dict.par_iter().map(|entry| entry.word).collect::<Vec<_>>();
+        assert_eq!(words.len(), n);
+        words.par_sort_unstable();
+        words.dedup();
+        assert_eq!(words.len(), n);
+
+        let count = dict
+            .par_iter()
+            .fold_with(0, |count, _| count + 1)
+            .reduce_with(|a, b| a + b);
+        // there's always a fold, even without entries, so something to reduce
+        assert_eq!(count, Some(n));
+
+        let letters = AtomicUsize::new(0);
+        dict.par_iter()
+            .for_each(|entry| _ = letters.fetch_add(entry.len(), Ordering::Relaxed));
+        assert_eq!(
+            letters.into_inner(),
+            words.iter().map(|word| word.len()).sum::<usize>()
+        );
+    }
+
+    #[test]
+    fn par_iter() {
+        for n in [0, 1, 2, 50_000] {
+            let dict = numbered(n);
+            assert_eq!(dict.len(), n);
+            check_par_iter(&dict);
+        }
+    }
+
+    // the same from inside nested scopes, where the identities of `fold` and
+    // `reduce` still make up for the lack of entries
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn par_iter_nested() {
+        for n in [0, 1, 50_000] {
+            let dict = numbered(n);
+            rayon::scope(|s| {
+                s.spawn(|_| {
+                    rayon::scope(|s| {
+                        s.spawn(|_| {
+                            check_par_iter(&dict);
+                            let count = dict
+                                .par_iter()
+                                .fold(|| 0, |count, _| count + 1)
+                                .reduce(|| 0, |a, b| a + b);
+                            assert_eq!(count, n);
+                        })
+                    })
+                })
+            });
+        }
+    }
 }
@@ -0,0 +1,171 @@
+//! Echo of the effective query at the top of machine-readable outputs
+//!
+//! Archived results can then be traced back to exactly what produced them:
+//! the crate version, the dictionaries (with hashes of the files and of their
+//! words), the query after cleanup and the random seed, if any. The echo is a block of
+//! `# name: value` comment lines, which `FromStr` parses back.
+
+use crate::hash::{from_hex, to_hex};
+use crate::search::SearchQuery;
+use std::fmt;
+use std::str::FromStr;
+
+/// A dictionary file as used by a query
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DictionaryEcho {
+    pub path: String,
+    /// `format::file_fnv_hash` of the file
+    pub hash: u64,
+    /// `Dictionary::content_hash` of the words, if known; on a `# words:`
+    /// line after the dictionary
+    pub words: Option<[u8; 32]>,
+}
+
+pub struct QueryEcho {
+    pub version: String,
+    pub dictionaries: Vec<DictionaryEcho>,
+    pub query: SearchQuery,
+    /// Seed of the random racks or words, if any
+    pub seed: Option<u64>,
+}
+
+impl QueryEcho {
+    /// An echo of the query for this version of the crate
+    pub fn new(query: SearchQuery) -> Self {
+        Self {
+            version: String::from(env!("CARGO_PKG_VERSION")),
+            dictionaries: Vec::new(),
+            query,
+            seed: None,
+        }
+    }
+}
+
+impl fmt::Display for QueryEcho {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# version: {}", self.version)?;
+        for dictionary in self.dictionaries.iter() {
+            // the path goes last, since it may contain anything
+            writeln!(
+                f,
+                "# dictionary: fnv1a64:{:016x} {}",
+                dictionary.hash, dictionary.path
+            )?;
+            if let Some(words) = &dictionary.words {
+                writeln!(f, "# words: sha256:{}", to_hex(words))?;
+            }
+        }
+        writeln!(f, "# query: {}", self.query)?;
+        if let Some(seed) = self.seed {
+            writeln!(f, "# seed: {}", seed)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for QueryEcho {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut version = None;
+        let mut dictionaries = Vec::new();
+        let mut query = None;
+        let mut seed = None;
+
+        for line in s.lines() {
+            let (name, value) = line
+                .strip_prefix("# ")
+                .and_then(|line| line.split_once(": "))
+                .ok_or_else(|| format!("invalid echo line {:?}", line))?;
+
+            match name {
+                "version" => version = Some(value.to_owned()),
+                "dictionary" => {
+                    let (hash, path) = value
+                        .strip_prefix("fnv1a64:")
+                        .and_then(|value| value.split_once(' '))
+                        .ok_or_else(|| format!("invalid echoed dictionary {:?}", value))?;
+                    let hash = u64::from_str_radix(hash, 16)
+                        .map_err(|_| format!("invalid echoed dictionary hash {:?}", hash))?;
+                    dictionaries.push(DictionaryEcho {
+                        path: path.to_owned(),
+                        hash,
+                        words: None,
+                    });
+                }
+                "words" => {
+                    let words = value
+                        .strip_prefix("sha256:")
+                        .and_then(from_hex)
+                        .ok_or_else(|| format!("invalid echoed words hash {:?}", value))?;
+                    let dictionary = dictionaries
+                        .last_mut()
+                        .ok_or("echoed words hash before any dictionary")?;
+                    dictionary.words = Some(words);
+                }
+                "query" => query = Some(value.parse()?),
+                "seed" => {
+                    seed = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("invalid echoed seed {:?}", value))?,
+                    )
+                }
+                _ => return Err(format!("unknown echo line {:?}", name)),
+            }
+        }
+
+        Ok(Self {
+            version: version.ok_or("missing version in echo")?,
+            dictionaries,
+            query: query.ok_or("missing query in echo")?,
+            seed,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::count::CountSet;
+    use crate::rack::{Rack, RackOptions};
+
+    #[test]
+    fn round_trip() {
+        let mut query = SearchQuery::new(Rack::parse("Q U ? ?", &RackOptions::default()).unwrap());
+        query.min_length = 3;
+        query.require = CountSet::from_word("q").unwrap();
+        let mut echo = QueryEcho::new(query);
+        echo.dictionaries.push(DictionaryEcho {
+            path: String::from("my words.dict"),
+            hash: 0xaf63dc4c8601ec8c,
+            words: Some([0xab; 32]),
+        });
+        echo.dictionaries.push(DictionaryEcho {
+            path: String::from("other.dict"),
+            hash: 1,
+            words: None,
+        });
+        echo.seed = Some(42);
+
+        let text = echo.to_string();
+        assert!(text.starts_with(&format!("# version: {}\n", env!("CARGO_PKG_VERSION"))));
+        assert!(text.contains(&format!(
+            "\n# dictionary: fnv1a64:af63dc4c8601ec8c my words.dict\n# words: sha256:{}\n",
+            "ab".repeat(32)
+        )));
+        assert!(text.contains(";require=q;"));
+
+        let parsed = text.parse::<QueryEcho>().unwrap();
+        assert_eq!(parsed.dictionaries, echo.dictionaries);
+        assert_eq!(parsed.seed, Some(42));
+        assert_eq!(parsed.to_string(), text);
+
+        assert!("# colour: red\n".parse::<QueryEcho>().is_err());
+        assert!("# version: 1\n".parse::<QueryEcho>().is_err());
+        assert!("# words: sha256:00\n".parse::<QueryEcho>().is_err());
+        let orphan = format!("# words: sha256:{}\n", "00".repeat(32));
+        assert!(orphan.parse::<QueryEcho>().is_err());
+    }
+}
@@ -0,0 +1,128 @@
+//! Dictionary exporting to text formats
+
+use crate::dict::Dictionary;
+use crate::par::*;
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+/// Text formats a dictionary can be exported to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// One word per line
+    Words,
+    /// A `word,a,b,...,z` header, then each word with its letter counts
+    CsvCounts,
+    /// A line per class of anagrams: its letters in alphabetical order, then
+    /// its words, separated by spaces, in the order of the letters
+    AnagramKeys,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "words" => Ok(ExportFormat::Words),
+            "csv-counts" => Ok(ExportFormat::CsvCounts),
+            "anagram-keys" => Ok(ExportFormat::AnagramKeys),
+            _ => Err(format!(
+                "unknown export format {:?} (expected words, csv-counts or anagram-keys)",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ExportFormat::Words => "words",
+            ExportFormat::CsvCounts => "csv-counts",
+            ExportFormat::AnagramKeys => "anagram-keys",
+        })
+    }
+}
+
+/// Writes every entry of the dictionary, sorted by word.
+pub fn export<W: Write>(dict: &Dictionary, format: ExportFormat, writer: &mut W) -> io::Result<()> {
+    let mut entries = dict.par_iter().collect::<Vec<_>>();
+    entries.par_sort_unstable_by_key(|entry| entry.word);
+
+    match format {
+        ExportFormat::Words => {
+            for entry in entries.iter() {
+                writeln!(writer, "{}", entry.word)?;
+            }
+        }
+        ExportFormat::CsvCounts => {
+            write!(writer, "word")?;
+            for letter in b'a'..=b'z' {
+                write!(writer, ",{}", letter as char)?;
+            }
+            writeln!(writer)?;
+
+            for entry in entries.iter() {
+                write!(writer, "{}", entry.word)?;
+                for count in <[u8; 26]>::from(entry.count_set.clone()).iter() {
+                    write!(writer, ",{}", count)?;
+                }
+                writeln!(writer)?;
+            }
+        }
+        ExportFormat::AnagramKeys => {
+            let mut keyed = entries
+                .iter()
+                .map(|entry| (entry.count_set.to_string(), entry.word))
+                .collect::<Vec<_>>();
+            keyed.par_sort_unstable();
+
+            for class in keyed.chunk_by(|a, b| a.0 == b.0) {
+                write!(writer, "{}", class[0].0)?;
+                for (_, word) in class.iter() {
+                    write!(writer, " {}", word)?;
+                }
+                writeln!(writer)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anagram_keys() {
+        let dict = ["seat", "east", "Eats", "tea", "ate", "quit"]
+            .iter()
+            .collect::<Dictionary>();
+        let mut out = Vec::new();
+        export(&dict, ExportFormat::AnagramKeys, &mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "aest Eats east seat\naet ate tea\niqtu quit\n"
+        );
+    }
+
+    #[test]
+    fn csv_counts() {
+        let dict = ["bob", "abba"].iter().collect::<Dictionary>();
+        let mut out = Vec::new();
+        export(&dict, ExportFormat::CsvCounts, &mut out).unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        let lines = out.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("word,a,b,c,"));
+        assert!(lines[0].ends_with(",x,y,z"));
+        assert_eq!(lines[1], format!("abba,2,2{}", ",0".repeat(24)));
+        assert_eq!(
+            lines[2],
+            format!("bob,0,2{},1{}", ",0".repeat(12), ",0".repeat(11))
+        );
+    }
+}
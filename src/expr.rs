@@ -0,0 +1,497 @@
+//! Score expressions, scoring rules typed on the command line
+//!
+//! An expression is arithmetic on integers and on values of the word, like
+//! `len*2 + count(q)*10`:
+//!
+//! - `len`: number of letters of the word
+//! - `score`: Scrabble score of the word made from the rack, blanks scoring 0
+//! - `rarity`: rarity of the word's letters (`score::rarity`)
+//! - `unique`: number of different letters of the word
+//! - `blanks`: number of blanks the word needs from the rack
+//! - `count(q)`: number of `q`s of the word, ignoring case
+//!
+//! Operators are `*`, `/` and `%` (the remainder), then `+` and `-`, all
+//! left associative, and a unary `-`; parentheses group. Arithmetic is on
+//! 64-bit integers and saturates instead of overflowing; dividing by 0 gives
+//! 0, so any expression scores every word. Expressions nest at most
+//! `MAX_DEPTH` deep.
+
+use crate::count::CountSet;
+use crate::fold::fold_case;
+use crate::rack::Rack;
+use crate::score::{rack_score, rarity, Scorer};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Names of the values of a word, in the order of `Value`
+const VALUES: [&str; 5] = ["len", "score", "rarity", "unique", "blanks"];
+
+/// Deepest nesting of parentheses and negations, and deepest tree of
+/// operations, of an expression, as parsing and evaluating recurse that deep
+pub const MAX_DEPTH: usize = 256;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Value {
+    Len,
+    Score,
+    Rarity,
+    Unique,
+    Blanks,
+}
+
+impl Value {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "len" => Value::Len,
+            "score" => Value::Score,
+            "rarity" => Value::Rarity,
+            "unique" => Value::Unique,
+            "blanks" => Value::Blanks,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl Op {
+    fn apply(self, a: i64, b: i64) -> i64 {
+        match self {
+            Op::Add => a.saturating_add(b),
+            Op::Sub => a.saturating_sub(b),
+            Op::Mul => a.saturating_mul(b),
+            Op::Div if b == 0 => 0,
+            Op::Div => a.saturating_div(b),
+            // only i64::MIN % -1 overflows, and its remainder is 0
+            Op::Rem => a.checked_rem(b).unwrap_or(0),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Node {
+    Number(i64),
+    Value(Value),
+    /// `count()` of the letter, from 0 for A
+    Count(usize),
+    Neg(Box<Node>),
+    Binary(Op, Box<Node>, Box<Node>),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ExprError {
+    /// An identifier that isn't one of the values nor `count`
+    UnknownIdentifier(String),
+    /// Something that doesn't belong here, at this column (from 1)
+    Unexpected { column: usize, found: String },
+    /// The expression stops in the middle, after an operator or `(`
+    UnexpectedEnd,
+    /// `count` of something else than a letter
+    NotALetter(String),
+    /// A number that doesn't fit in 64 bits
+    TooLarge(String),
+    /// Parentheses, negations or operations nested deeper than `MAX_DEPTH`
+    TooDeep,
+}
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ExprError::*;
+
+        match self {
+            UnknownIdentifier(name) => write!(
+                f,
+                "unknown identifier {} (expected {} or count(letter))",
+                name,
+                VALUES.join(", ")
+            ),
+            Unexpected { column, found } => write!(f, "unexpected {} at column {}", found, column),
+            UnexpectedEnd => f.write_str("the expression ends too early"),
+            NotALetter(s) => write!(f, "count() takes a letter, not {}", s),
+            TooLarge(s) => write!(f, "{} is too large", s),
+            TooDeep => write!(f, "the expression nests deeper than {} levels", MAX_DEPTH),
+        }
+    }
+}
+
+impl Error for ExprError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Token<'a> {
+    Number(&'a str),
+    Ident(&'a str),
+    Op(char),
+    Open,
+    Close,
+}
+
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(s) | Token::Ident(s) => f.write_str(s),
+            Token::Op(c) => write!(f, "{}", c),
+            Token::Open => f.write_str("("),
+            Token::Close => f.write_str(")"),
+        }
+    }
+}
+
+/// The tokens of `s`, with their column
+fn tokenize(s: &str) -> Result<Vec<(usize, Token<'_>)>, ExprError> {
+    let mut tokens = Vec::new();
+    let mut rest = s.char_indices().peekable();
+    while let Some((start, c)) = rest.next() {
+        let column = s[..start].chars().count() + 1;
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '+' | '-' | '*' | '/' | '%' => Token::Op(c),
+            '(' => Token::Open,
+            ')' => Token::Close,
+            c if c.is_ascii_alphanumeric() || c == '_' => {
+                // numbers are digits only, identifiers can have digits after
+                // their first character
+                let number = c.is_ascii_digit();
+                let continues = |c: char| {
+                    c.is_ascii_digit() || !number && (c.is_ascii_alphabetic() || c == '_')
+                };
+                let mut end = start + 1;
+                while let Some(&(i, c)) = rest.peek() {
+                    if !continues(c) {
+                        break;
+                    }
+                    end = i + 1;
+                    rest.next();
+                }
+                if number {
+                    Token::Number(&s[start..end])
+                } else {
+                    Token::Ident(&s[start..end])
+                }
+            }
+            c => {
+                return Err(ExprError::Unexpected {
+                    column,
+                    found: format!("{:?}", c),
+                })
+            }
+        };
+        tokens.push((column, token));
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<(usize, Token<'a>)>,
+    next: usize,
+    // parentheses and negations open around the next token
+    nesting: usize,
+}
+
+/// A parsed node with its height, 0 for a leaf
+type Parsed = (Node, usize);
+
+/// The height of a node over children at most `height` high, unless too high
+fn above(height: usize) -> Result<usize, ExprError> {
+    if height < MAX_DEPTH {
+        Ok(height + 1)
+    } else {
+        Err(ExprError::TooDeep)
+    }
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.next).map(|&(_, token)| token)
+    }
+
+    fn advance(&mut self) -> Result<(usize, Token<'a>), ExprError> {
+        let token = self.tokens.get(self.next).ok_or(ExprError::UnexpectedEnd)?;
+        self.next += 1;
+        Ok(*token)
+    }
+
+    fn unexpected(&self, (column, token): (usize, Token<'_>)) -> ExprError {
+        ExprError::Unexpected {
+            column,
+            found: token.to_string(),
+        }
+    }
+
+    fn expect(&mut self, expected: Token<'_>) -> Result<(), ExprError> {
+        let token = self.advance()?;
+        if token.1 == expected {
+            Ok(())
+        } else {
+            Err(self.unexpected(token))
+        }
+    }
+
+    /// `parse` one level deeper in parentheses or negations
+    fn nested(
+        &mut self,
+        parse: fn(&mut Self) -> Result<Parsed, ExprError>,
+    ) -> Result<Parsed, ExprError> {
+        if self.nesting == MAX_DEPTH {
+            return Err(ExprError::TooDeep);
+        }
+        self.nesting += 1;
+        let parsed = parse(self);
+        self.nesting -= 1;
+        parsed
+    }
+
+    /// Sums and differences of terms
+    fn expr(&mut self) -> Result<Parsed, ExprError> {
+        let (mut node, mut height) = self.term()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Op('+')) => Op::Add,
+                Some(Token::Op('-')) => Op::Sub,
+                _ => return Ok((node, height)),
+            };
+            self.next += 1;
+            let (right, right_height) = self.term()?;
+            height = above(height.max(right_height))?;
+            node = Node::Binary(op, Box::new(node), Box::new(right));
+        }
+    }
+
+    /// Products, quotients and remainders of factors
+    fn term(&mut self) -> Result<Parsed, ExprError> {
+        let (mut node, mut height) = self.factor()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Op('*')) => Op::Mul,
+                Some(Token::Op('/')) => Op::Div,
+                Some(Token::Op('%')) => Op::Rem,
+                _ => return Ok((node, height)),
+            };
+            self.next += 1;
+            let (right, right_height) = self.factor()?;
+            height = above(height.max(right_height))?;
+            node = Node::Binary(op, Box::new(node), Box::new(right));
+        }
+    }
+
+    fn factor(&mut self) -> Result<Parsed, ExprError> {
+        let token = self.advance()?;
+        match token.1 {
+            Token::Op('-') => {
+                let (node, height) = self.nested(Self::factor)?;
+                Ok((Node::Neg(Box::new(node)), above(height)?))
+            }
+            Token::Number(s) => s
+                .parse()
+                .map(|n| (Node::Number(n), 0))
+                .map_err(|_| ExprError::TooLarge(s.to_owned())),
+            Token::Ident("count") => {
+                self.expect(Token::Open)?;
+                let letter = match self.advance()? {
+                    (_, Token::Ident(s))
+                        if s.len() == 1 && s.as_bytes()[0].is_ascii_alphabetic() =>
+                    {
+                        fold_case(s.as_bytes()[0]) - b'a'
+                    }
+                    (_, Token::Close) => {
+                        return Err(ExprError::NotALetter(String::from("nothing")))
+                    }
+                    (_, other) => return Err(ExprError::NotALetter(other.to_string())),
+                };
+                self.expect(Token::Close)?;
+                Ok((Node::Count(letter as usize), 0))
+            }
+            Token::Ident(name) => Value::parse(name)
+                .map(|value| (Node::Value(value), 0))
+                .ok_or_else(|| ExprError::UnknownIdentifier(name.to_owned())),
+            Token::Open => {
+                let parsed = self.nested(Self::expr)?;
+                self.expect(Token::Close)?;
+                Ok(parsed)
+            }
+            _ => Err(self.unexpected(token)),
+        }
+    }
+}
+
+/// A score expression, parsed and checked to only have known identifiers
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScoreExpr {
+    root: Node,
+}
+
+impl ScoreExpr {
+    pub fn parse(s: &str) -> Result<Self, ExprError> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            next: 0,
+            nesting: 0,
+        };
+        let (root, _) = parser.expr()?;
+        match parser.tokens.get(parser.next) {
+            Some(&token) => Err(parser.unexpected(token)),
+            None => Ok(Self { root }),
+        }
+    }
+
+    /// The value of the expression for a word with these letter counts, made
+    /// from the rack
+    pub fn eval(&self, counts: &CountSet, rack: &Rack) -> i64 {
+        fn eval(node: &Node, counts: &CountSet, rack: &Rack) -> i64 {
+            match node {
+                Node::Number(n) => *n,
+                Node::Value(Value::Len) => counts.total() as i64,
+                Node::Value(Value::Score) => rack_score(rack, counts) as i64,
+                Node::Value(Value::Rarity) => rarity(counts) as i64,
+                Node::Value(Value::Unique) => counts.distinct() as i64,
+                Node::Value(Value::Blanks) => counts
+                    .iter()
+                    .zip(rack.letters().iter())
+                    .map(|(need, &have)| (need as i64 - have as i64).max(0))
+                    .sum(),
+                Node::Count(letter) => counts.iter().nth(*letter).unwrap_or(0) as i64,
+                Node::Neg(node) => eval(node, counts, rack).saturating_neg(),
+                Node::Binary(op, a, b) => op.apply(eval(a, counts, rack), eval(b, counts, rack)),
+            }
+        }
+
+        eval(&self.root, counts, rack)
+    }
+}
+
+impl FromStr for ScoreExpr {
+    type Err = ExprError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl Scorer for ScoreExpr {
+    fn score(&self, _: &str, counts: &CountSet, rack: &Rack) -> i64 {
+        self.eval(counts, rack)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rack::RackOptions;
+
+    fn eval_with(expr: &str, word: &str, rack: &str) -> i64 {
+        let rack = Rack::parse(rack, &RackOptions::default()).unwrap();
+        ScoreExpr::parse(expr)
+            .unwrap()
+            .eval(&CountSet::from_word(word).unwrap(), &rack)
+    }
+
+    fn eval(expr: &str) -> i64 {
+        eval_with(expr, "", "")
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(eval("2 + 3 * 4"), 14);
+        assert_eq!(eval("(2 + 3) * 4"), 20);
+        assert_eq!(eval("10 - 4 - 3"), 3);
+        assert_eq!(eval("100 / 10 / 5"), 2);
+        assert_eq!(eval("7 / 2 + 7 % 2"), 4);
+        assert_eq!(eval("-2 * 3"), -6);
+        assert_eq!(eval("-(2 + 3) * --2"), -10);
+        assert_eq!(eval("2 - -3"), 5);
+        assert_eq!(eval("-7 / 2"), -3);
+    }
+
+    #[test]
+    fn values() {
+        assert_eq!(eval_with("len*2 + count(q)*10", "quiz", "quiz"), 18);
+        assert_eq!(eval_with("count(Z) + count(e)", "quiz", ""), 1);
+        assert_eq!(eval_with("score", "quiz", "qui?"), 12);
+        assert_eq!(eval_with("rarity", "quiz", ""), 28);
+        assert_eq!(eval_with("unique", "sass", ""), 2);
+        assert_eq!(eval_with("blanks", "sass", "sa??"), 2);
+    }
+
+    #[test]
+    fn overflow() {
+        let max = i64::MAX;
+        assert_eq!(eval("9223372036854775807 + 1"), max);
+        assert_eq!(eval("9223372036854775807 * 9223372036854775807"), max);
+        assert_eq!(eval("-9223372036854775807 - 10"), i64::MIN);
+        assert_eq!(eval("-(-9223372036854775807 - 1)"), max);
+        assert_eq!(eval("(-9223372036854775807 - 1) / -1"), max);
+        assert_eq!(eval("(-9223372036854775807 - 1) % -1"), 0);
+        assert_eq!(eval("len / 0 + 5 % 0"), 0);
+        assert_eq!(
+            ScoreExpr::parse("9223372036854775808"),
+            Err(ExprError::TooLarge(String::from("9223372036854775808")))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let err = |expr| ScoreExpr::parse(expr).unwrap_err().to_string();
+        assert_eq!(
+            err("len + lenght"),
+            "unknown identifier lenght (expected len, score, rarity, unique, blanks or count(letter))"
+        );
+        // names are exact
+        assert_eq!(
+            ScoreExpr::parse("Len"),
+            Err(ExprError::UnknownIdentifier(String::from("Len")))
+        );
+        assert_eq!(err("len +"), "the expression ends too early");
+        assert_eq!(err("(len"), "the expression ends too early");
+        assert_eq!(err(""), "the expression ends too early");
+        assert_eq!(err("len 2"), "unexpected 2 at column 5");
+        assert_eq!(err("len + * 2"), "unexpected * at column 7");
+        assert_eq!(err("len)"), "unexpected ) at column 4");
+        assert_eq!(err("len ^ 2"), "unexpected '^' at column 5");
+        assert_eq!(err("count"), "the expression ends too early");
+        assert_eq!(err("count q"), "unexpected q at column 7");
+        assert_eq!(err("count(qu)"), "count() takes a letter, not qu");
+        assert_eq!(err("count()"), "count() takes a letter, not nothing");
+        assert_eq!(err("count(1)"), "count() takes a letter, not 1");
+        assert_eq!(err("2len"), "unexpected len at column 2");
+    }
+
+    #[test]
+    fn depth() {
+        let nested = |open: &str, inner: &str, close: &str, n: usize| {
+            ScoreExpr::parse(&format!("{}{}{}", open.repeat(n), inner, close.repeat(n)))
+        };
+        for n in [MAX_DEPTH - 1, MAX_DEPTH] {
+            assert!(nested("(", "1", ")", n).is_ok());
+            assert!(nested("-", "1", "", n).is_ok());
+            assert!(nested("", "1", "+1", n).is_ok());
+            assert!(nested("", "1", "*1", n).is_ok());
+        }
+        assert_eq!(eval(&format!("{}1", "-".repeat(MAX_DEPTH))), 1);
+        assert_eq!(eval(&format!("1{}", "+1".repeat(MAX_DEPTH))), 257);
+
+        // rather than overflowing the stack
+        for n in [MAX_DEPTH + 1, 10_000] {
+            assert_eq!(nested("(", "1", ")", n), Err(ExprError::TooDeep));
+            assert_eq!(nested("-", "1", "", n), Err(ExprError::TooDeep));
+            assert_eq!(nested("", "1", "+1", n), Err(ExprError::TooDeep));
+            assert_eq!(nested("", "1", "*1", n), Err(ExprError::TooDeep));
+            assert_eq!(nested("(1+", "1", ")", n), Err(ExprError::TooDeep));
+        }
+        // each operation of a chain is a level of its tree, however few
+        // parentheses the chains are in
+        let chains = |n| {
+            (0..n).fold(String::from("1"), |inner, _| {
+                format!("({}{})", inner, "+1".repeat(MAX_DEPTH / 2))
+            })
+        };
+        assert!(ScoreExpr::parse(&chains(2)).is_ok());
+        assert_eq!(ScoreExpr::parse(&chains(3)), Err(ExprError::TooDeep));
+    }
+}
@@ -0,0 +1,180 @@
+//! Sorting more words than fit in memory, for searches matching millions of
+//! words
+//!
+//! The words are sorted in runs of a bounded length, each written to a
+//! temporary file, then merged back while they're read, so that only one
+//! run and a word per file are in memory at a time.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Lines, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// tells apart the runs of several sorters of the same process
+static RUN_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Sorts words pushed one at a time, spilling them to temporary files in
+/// sorted runs of at most `run_len` words.
+pub struct ExternalSorter {
+    run_len: usize,
+    dir: PathBuf,
+    buffer: Vec<String>,
+    runs: Vec<PathBuf>,
+}
+
+impl ExternalSorter {
+    /// A sorter keeping at most `run_len` words in memory, writing its runs
+    /// to `dir`
+    pub fn new(run_len: usize, dir: PathBuf) -> Self {
+        Self {
+            run_len: run_len.max(1),
+            dir,
+            buffer: Vec::new(),
+            runs: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, word: &str) -> io::Result<()> {
+        self.buffer.push(word.to_owned());
+        if self.buffer.len() >= self.run_len {
+            self.spill()?;
+        }
+
+        Ok(())
+    }
+
+    /// Number of runs written to files so far
+    pub fn runs(&self) -> usize {
+        self.runs.len()
+    }
+
+    fn spill(&mut self) -> io::Result<()> {
+        self.buffer.sort_unstable();
+        let path = self.dir.join(format!(
+            "word-puzzle-searcher-run-{}-{}",
+            std::process::id(),
+            RUN_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        // never an existing file, which a predictable name in a shared
+        // directory could be (such as a link planted there)
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        // removed along with the others if writing fails
+        self.runs.push(path);
+
+        let mut writer = BufWriter::new(file);
+        for word in self.buffer.drain(..) {
+            writeln!(writer, "{}", word)?;
+        }
+        writer.flush()
+    }
+
+    /// The words in byte order, merged from the runs and the words left in
+    /// memory. The temporary files are removed when the iterator is dropped.
+    pub fn finish(mut self) -> io::Result<SortedWords> {
+        self.buffer.sort_unstable();
+        let mut sorted = SortedWords {
+            files: Vec::new(),
+            memory: std::mem::take(&mut self.buffer).into_iter(),
+            heap: BinaryHeap::new(),
+            runs: std::mem::take(&mut self.runs),
+        };
+
+        for path in sorted.runs.iter() {
+            sorted.files.push(BufReader::new(File::open(path)?).lines());
+        }
+        for source in 0..=sorted.files.len() {
+            sorted.refill(source)?;
+        }
+
+        Ok(sorted)
+    }
+}
+
+impl Drop for ExternalSorter {
+    fn drop(&mut self) {
+        for path in self.runs.iter() {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Iterator over the words of an `ExternalSorter`, in byte order
+pub struct SortedWords {
+    files: Vec<Lines<BufReader<File>>>,
+    // the words that were never spilled, the last source
+    memory: std::vec::IntoIter<String>,
+    // the next word of each source, smallest first
+    heap: BinaryHeap<Reverse<(String, usize)>>,
+    runs: Vec<PathBuf>,
+}
+
+impl SortedWords {
+    // queues the next word of a source, if any
+    fn refill(&mut self, source: usize) -> io::Result<()> {
+        let next = match self.files.get_mut(source) {
+            Some(lines) => lines.next().transpose()?,
+            None => self.memory.next(),
+        };
+        if let Some(word) = next {
+            self.heap.push(Reverse((word, source)));
+        }
+
+        Ok(())
+    }
+}
+
+impl Iterator for SortedWords {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse((word, source)) = self.heap.pop()?;
+        Some(self.refill(source).map(|()| word))
+    }
+}
+
+impl Drop for SortedWords {
+    fn drop(&mut self) {
+        for path in self.runs.iter() {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sort(words: &[&str], run_len: usize) -> (Vec<String>, usize) {
+        let mut sorter = ExternalSorter::new(run_len, std::env::temp_dir());
+        for word in words {
+            sorter.push(word).unwrap();
+        }
+        let runs = sorter.runs();
+        let paths = sorter.runs.clone();
+
+        let sorted = sorter
+            .finish()
+            .unwrap()
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert!(paths.iter().all(|path| !path.exists()));
+        (sorted, runs)
+    }
+
+    #[test]
+    fn external_sort() {
+        let words = ["stain", "ant", "satin", "tan", "ants", "saint", "quit"];
+        let mut expected = words.map(String::from).to_vec();
+        expected.sort_unstable();
+
+        for (run_len, runs) in [(1, 7), (2, 3), (3, 2), (7, 1), (100, 0)] {
+            assert_eq!(sort(&words, run_len), (expected.clone(), runs));
+        }
+        assert_eq!(sort(&[], 2), (Vec::new(), 0));
+    }
+}
@@ -0,0 +1,82 @@
+//! Case folding and word order, ASCII only
+//!
+//! Every comparison of words or letters that ignores case goes through this
+//! module, and folds ASCII letters only: `I` is always `i`, never the
+//! dotless `ı` of Turkish, and non-ASCII characters are left as they are, so
+//! they never fold into a letter. Nothing depends on the locale.
+//!
+//! Sorted output is in byte order, the order of `str`'s `Ord`: uppercase
+//! letters before lowercase ones (`Zulu` before `ant`), whatever the
+//! environment. Only the sorted index orders words ignoring case, with
+//! `cmp_folded`, and that order doesn't reach the output.
+
+use std::cmp::Ordering;
+
+/// Folds the case of a letter. Every comparison between letters goes through
+/// here, so that racks, words and filters agree whatever their case.
+#[inline]
+pub fn fold_case(b: u8) -> u8 {
+    b.to_ascii_lowercase()
+}
+
+/// Folds the case of a word: its ASCII letters in lowercase, anything else
+/// unchanged. Words that only differ by case fold the same.
+pub fn fold_word(word: &str) -> String {
+    word.to_ascii_lowercase()
+}
+
+/// Whether two characters are the same letter, ignoring case
+#[inline]
+pub fn eq_folded(a: char, b: char) -> bool {
+    a.eq_ignore_ascii_case(&b)
+}
+
+/// Compares two words ignoring case
+pub fn cmp_folded(a: &str, b: &str) -> Ordering {
+    a.bytes().map(fold_case).cmp(b.bytes().map(fold_case))
+}
+
+/// Whether `word` starts with `prefix`, ignoring case
+pub fn starts_with_folded(word: &str, prefix: &str) -> bool {
+    word.len() >= prefix.len()
+        && word
+            .bytes()
+            .zip(prefix.bytes())
+            .all(|(w, p)| fold_case(w) == fold_case(p))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the Turkish dotted and dotless i don't fold into `i`, nor `I` into them
+    #[test]
+    fn turkish_i() {
+        assert_eq!(fold_word("ISTANBUL"), "istanbul");
+        assert_eq!(fold_word("\u{130}stanbul"), "\u{130}stanbul");
+        assert_eq!(fold_word("\u{131}I"), "\u{131}i");
+        assert!(eq_folded('I', 'i'));
+        assert!(!eq_folded('\u{130}', 'i'));
+        assert!(!eq_folded('\u{131}', 'I'));
+        assert!(!starts_with_folded("\u{130}stanbul", "i"));
+        assert_ne!(cmp_folded("\u{130}", "i"), Ordering::Equal);
+    }
+
+    #[test]
+    fn folded() {
+        assert!(starts_with_folded("Satin", "sA"));
+        assert!(!starts_with_folded("sa", "sat"));
+        assert_eq!(cmp_folded("Zulu", "ant"), Ordering::Greater);
+        assert_eq!(cmp_folded("ANT", "ant"), Ordering::Equal);
+    }
+
+    #[test]
+    fn byte_order() {
+        let mut words = ["ant", "Zulu", "\u{130}stanbul", "zoo", "Ant", "istanbul"];
+        words.sort_unstable();
+        assert_eq!(
+            words,
+            ["Ant", "Zulu", "ant", "istanbul", "zoo", "\u{130}stanbul"]
+        );
+    }
+}
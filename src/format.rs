@@ -1,18 +1,47 @@
 //! Dictionary file format reading/parsing and writing
 
-use crate::count::CountSet;
-use crate::dict::Dictionary;
-use rayon::prelude::*;
-use std::collections::HashMap;
+use crate::count::{CountSet, PACKED_SIZE};
+use crate::dict::{Dictionary, OffsetLength};
+use crate::hash::Sha256;
+use crate::index::SortedIndex;
+use crate::par::*;
+use crate::search::SearchDefaults;
+use crate::trace::{event, span};
+use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
 use std::error::Error;
 use std::fmt;
-use std::io::{self, ErrorKind, Read, Write};
+use std::io::{self, Read, Write};
 
-const FORMAT_VERSION: u32 = 1;
+/// Version of the dictionary format written by `write_dict`
+///
+/// Version 2 is version 1 followed by a list of sections, each made of a
+/// 4-byte tag, a length and a payload, and ending with an `END\0` tag.
+pub const FORMAT_VERSION: u32 = 2;
+/// Oldest version of the dictionary format `read_dict` can read
+pub const MIN_FORMAT_VERSION: u32 = 1;
+
+const SECTION_END: &[u8; 4] = b"END\0";
+/// Word list names, then the index of the source of each entry (in the order
+/// of the entry table), `NO_SOURCE` if untagged
+pub const SECTION_SOURCES: &[u8; 4] = b"SRCS";
+const NO_SOURCE: u16 = u16::MAX;
+/// Suggested search options, as a list of 4-byte option tags each followed
+/// by a value
+pub const SECTION_DEFAULTS: &[u8; 4] = b"DFLT";
+const DEFAULT_MIN_LENGTH: &[u8; 4] = b"MINL";
+/// A bit per entry (in the order of the entry table, lowest bit first), set
+/// for the entries derived from other words
+pub const SECTION_DERIVED: &[u8; 4] = b"DRVD";
+/// The difficulty tier of every entry on 2 bits (in the order of the entry
+/// table, lowest bits first), present only in dictionaries with tiers
+pub const SECTION_TIERS: &[u8; 4] = b"TIER";
+// 2: sorted ignoring case
+const SORTED_INDEX_VERSION: u32 = 2;
 
 const USIZE: usize = std::mem::size_of::<usize>();
-const COUNT_SET_SIZE: usize = std::mem::size_of::<CountSet>();
+// count sets are stored packed, whatever their representation in memory
+const COUNT_SET_SIZE: usize = PACKED_SIZE;
 const WORD_COUNT_STRIDE: usize = USIZE * 2 + COUNT_SET_SIZE;
 
 /// Error type returned by the `read_dict` function
@@ -20,6 +49,15 @@ const WORD_COUNT_STRIDE: usize = USIZE * 2 + COUNT_SET_SIZE;
 pub enum ReadError {
     /// wrong format
     FormatError,
+    /// The length of the entry at `offset` doesn't match its letter counts
+    LengthMismatch {
+        offset: usize,
+        len: usize,
+        letters: usize,
+    },
+    /// Data left after the end of the dictionary, in bytes; only returned
+    /// when asked to be strict
+    TrailingData(u64),
     /// Error returned by an I/O operation
     IoError(io::Error),
 }
@@ -28,8 +66,21 @@ impl fmt::Display for ReadError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         use ReadError::*;
 
-        if let IoError(err) = self {
-            return fmt::Display::fmt(err, f);
+        match self {
+            IoError(err) => return fmt::Display::fmt(err, f),
+            LengthMismatch {
+                offset,
+                len,
+                letters,
+            } => {
+                return write!(
+                    f,
+                    "entry at offset {} has length {} but {} letters",
+                    offset, len, letters
+                )
+            }
+            TrailingData(bytes) => return write!(f, "{} bytes of trailing data", bytes),
+            _ => {}
         }
 
         write!(
@@ -51,7 +102,36 @@ impl From<io::Error> for ReadError {
     }
 }
 
-pub fn read_dict<R: Read>(reader: &mut R) -> Result<Dictionary, ReadError> {
+/// The fixed-size header at the start of a dictionary file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DictHeader {
+    pub version: u32,
+    /// Number of entries
+    pub word_count: usize,
+    /// Length of the word string in bytes
+    pub str_length: usize,
+}
+
+impl DictHeader {
+    /// Estimates the memory taken by the dictionary once read, in bytes,
+    /// before reading it: the word string and the table of letter counts,
+    /// without the optional sections.
+    pub fn estimated_memory(&self) -> usize {
+        // hash tables keep at least one free bucket in eight, with a power of
+        // two of buckets, and a control byte per bucket
+        let buckets = match self.word_count {
+            0 => 0,
+            n => n.saturating_mul(8).div_ceil(7).next_power_of_two(),
+        };
+        let bucket_size = std::mem::size_of::<(OffsetLength, CountSet)>() + 1;
+
+        self.str_length
+            .saturating_add(buckets.saturating_mul(bucket_size))
+    }
+}
+
+/// Reads the header of a dictionary file, leaving the reader right after it.
+pub fn peek_header<R: Read + ?Sized>(reader: &mut R) -> Result<DictHeader, ReadError> {
     let mut magic = [0; 4];
     reader.read_exact(&mut magic)?;
     if &magic != b"DICT" {
@@ -61,32 +141,139 @@ pub fn read_dict<R: Read>(reader: &mut R) -> Result<Dictionary, ReadError> {
     let mut version = [0; 4];
     reader.read_exact(&mut version)?;
     let version = u32::from_le_bytes(version);
-    if version != FORMAT_VERSION {
+    if !(MIN_FORMAT_VERSION..=FORMAT_VERSION).contains(&version) {
         return Err(ReadError::FormatError);
     }
 
-    let mut word_count_length = [0; USIZE];
-    reader.read_exact(&mut word_count_length)?;
-    let word_count_length = usize::from_le_bytes(word_count_length);
+    let mut word_count = [0; USIZE];
+    reader.read_exact(&mut word_count)?;
+    let word_count = usize::from_le_bytes(word_count);
 
     let mut str_length = [0; USIZE];
     reader.read_exact(&mut str_length)?;
     let str_length = usize::from_le_bytes(str_length);
 
-    let mut word_string = vec![0; str_length];
-    reader.read_exact(&mut word_string)?;
-    let word_string = String::from_utf8(word_string).map_err(|_| ReadError::FormatError)?;
+    Ok(DictHeader {
+        version,
+        word_count,
+        str_length,
+    })
+}
+
+/// What `read_dict_report` noticed while reading a dictionary file
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ReadReport {
+    /// Bytes left after the end of the dictionary, e.g. junk left by writers
+    /// that didn't truncate the file
+    pub trailing_bytes: u64,
+}
+
+/// Reads a dictionary file, ignoring any data after it.
+pub fn read_dict<R: Read + ?Sized>(reader: &mut R) -> Result<Dictionary, ReadError> {
+    read_dict_report(reader).map(|(dict, _)| dict)
+}
+
+/// Reads a dictionary file, reporting anything unusual that isn't an error,
+/// such as data after the dictionary.
+pub fn read_dict_report<R: Read + ?Sized>(
+    reader: &mut R,
+) -> Result<(Dictionary, ReadReport), ReadError> {
+    span!("read_dict");
+    let raw = read_raw(reader)?;
 
-    let mut word_count_buf = vec![0; word_count_length * WORD_COUNT_STRIDE];
-    reader.read_exact(&mut word_count_buf).map_err(|e| {
-        if e.kind() == ErrorKind::UnexpectedEof {
-            ReadError::FormatError
-        } else {
-            ReadError::IoError(e)
+    // upholds the safety requirements of `from_raw_parts`, and catches most
+    // mismatches between the header and the rest of the file
+    if !raw.entries.par_iter().all(|&(key, _)| raw.in_bounds(key)) {
+        return Err(ReadError::FormatError);
+    }
+
+    // a corrupted length would silently change both the word and the length
+    // filters it passes
+    let mismatch = raw
+        .entries
+        .par_iter()
+        .find_first(|((_, len), set)| set.total() != *len);
+    if let Some(&((offset, len), ref set)) = mismatch {
+        return Err(ReadError::LengthMismatch {
+            offset,
+            len,
+            letters: set.total(),
+        });
+    }
+
+    event!(
+        words = raw.entries.len(),
+        word_bytes = raw.word_string.len(),
+        version = raw.version,
+        trailing_bytes = raw.trailing_bytes,
+        "dictionary read"
+    );
+    let report = ReadReport {
+        trailing_bytes: raw.trailing_bytes,
+    };
+    Ok((unsafe { raw.into_dict() }, report))
+}
+
+/// A dictionary file as stored, its entries not checked against its words
+pub(crate) struct RawDict {
+    pub(crate) version: u32,
+    pub(crate) word_string: String,
+    /// The entry table, in the order of the file
+    pub(crate) entries: Vec<(OffsetLength, CountSet)>,
+    sources: Option<Sources>,
+    derived: Option<HashSet<OffsetLength>>,
+    tiers: Option<HashMap<OffsetLength, u8>>,
+    search_defaults: SearchDefaults,
+    pub(crate) trailing_bytes: u64,
+}
+
+impl RawDict {
+    /// Whether the entry is a range of the word string on character
+    /// boundaries
+    pub(crate) fn in_bounds(&self, (offset, len): OffsetLength) -> bool {
+        offset
+            .checked_add(len)
+            .and_then(|end| self.word_string.get(offset..end))
+            .is_some()
+    }
+
+    /// # Safety
+    ///
+    /// Every entry must be `in_bounds`.
+    pub(crate) unsafe fn into_dict(self) -> Dictionary {
+        let word_count = self.entries.into_iter().collect::<HashMap<_, _>>();
+        let mut dict = Dictionary::from_raw_parts(self.word_string, word_count);
+        if let Some((names, word_sources)) = self.sources {
+            dict.set_sources(names, word_sources);
         }
-    })?;
+        if let Some(derived) = self.derived {
+            dict.set_derived(derived);
+        }
+        if let Some(tiers) = self.tiers {
+            dict.set_tiers(tiers);
+        }
+        dict.set_search_defaults(self.search_defaults);
+        dict
+    }
+}
+
+/// Reads every part of a dictionary file, checking only its structure.
+pub(crate) fn read_raw<R: Read + ?Sized>(reader: &mut R) -> Result<RawDict, ReadError> {
+    let DictHeader {
+        version,
+        word_count: word_count_length,
+        str_length,
+    } = peek_header(reader)?;
+
+    let word_string = read_bytes(reader, str_length)?;
+    let word_string = String::from_utf8(word_string).map_err(|_| ReadError::FormatError)?;
 
-    let word_count = (0..word_count_length)
+    let table_length = word_count_length
+        .checked_mul(WORD_COUNT_STRIDE)
+        .ok_or(ReadError::FormatError)?;
+    let word_count_buf = read_bytes(reader, table_length)?;
+
+    let entries = (0..word_count_length)
         .into_par_iter()
         .map(|i| &word_count_buf[(i * WORD_COUNT_STRIDE)..((i + 1) * WORD_COUNT_STRIDE)])
         .map(|count_element| {
@@ -103,22 +290,770 @@ pub fn read_dict<R: Read>(reader: &mut R) -> Result<Dictionary, ReadError> {
 
             ((offset, len), set)
         })
-        .collect::<HashMap<_, _>>();
+        .collect::<Vec<_>>();
+
+    let mut sources = None;
+    let mut derived = None;
+    let mut tiers = None;
+    let mut search_defaults = SearchDefaults::default();
+    if version >= 2 {
+        loop {
+            let mut tag = [0; 4];
+            reader.read_exact(&mut tag)?;
+            if &tag == SECTION_END {
+                break;
+            }
+
+            let length = read_usize(reader)?;
+            let payload = read_bytes(reader, length)?;
 
-    Ok(unsafe { Dictionary::from_raw_parts(word_string, word_count) })
+            // unknown sections are skipped, for forward compatibility
+            if &tag == SECTION_SOURCES {
+                let keys = entries.iter().map(|&(key, _)| key);
+                sources = Some(parse_sources(&payload, keys)?);
+            } else if &tag == SECTION_DEFAULTS {
+                search_defaults = parse_defaults(&payload)?;
+            } else if &tag == SECTION_DERIVED {
+                let keys = entries.iter().map(|&(key, _)| key);
+                derived = Some(parse_derived(&payload, keys)?);
+            } else if &tag == SECTION_TIERS {
+                let keys = entries.iter().map(|&(key, _)| key);
+                tiers = Some(parse_tiers(&payload, keys)?);
+            }
+        }
+    }
+
+    Ok(RawDict {
+        version,
+        word_string,
+        entries,
+        sources,
+        derived,
+        tiers,
+        search_defaults,
+        trailing_bytes: io::copy(reader, &mut io::sink())?,
+    })
 }
 
-pub fn write_dict<W: Write>(dict: &Dictionary, writer: &mut W) -> io::Result<()> {
+/// 64-bit FNV-1a hash of everything the reader yields, identifying the
+/// contents of a dictionary file; not meant to resist tampering.
+pub fn file_fnv_hash<R: Read + ?Sized>(reader: &mut R) -> io::Result<u64> {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    let mut hash = OFFSET_BASIS;
+    let mut buf = [0; 8192];
+    loop {
+        let read = match reader.read(&mut buf) {
+            Ok(0) => return Ok(hash),
+            Ok(read) => read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        for &b in buf[..read].iter() {
+            hash = (hash ^ b as u64).wrapping_mul(PRIME);
+        }
+    }
+}
+
+/// SHA-256 hash of everything the reader yields, identifying a dictionary
+/// file byte for byte, unlike `Dictionary::content_hash`, which only depends
+/// on the words. Stable across releases, as SHA-256 is.
+pub fn file_hash<R: Read + ?Sized>(reader: &mut R) -> io::Result<[u8; 32]> {
+    let mut hasher = Sha256::new();
+    let mut buf = [0; 8192];
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) => return Ok(hasher.finish()),
+            Ok(read) => hasher.update(&buf[..read]),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Writes an annotated hex dump of a dictionary file, field by field, as far
+/// as it can be read; for debugging the format.
+pub fn explain_dict<R: Read + ?Sized, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+) -> Result<(), ReadError> {
+    let mut dump = Dump {
+        writer,
+        position: 0,
+    };
+
+    let mut header = [0; 8 + USIZE * 2];
+    reader.read_exact(&mut header)?;
+    dump.field(&header[..4], "magic")?;
+    let version = u32::from_le_bytes(header[4..8].try_into().unwrap());
+    dump.field(&header[4..8], &format!("format version {}", version))?;
+    let word_count = usize::from_le_bytes(header[8..(8 + USIZE)].try_into().unwrap());
+    dump.field(
+        &header[8..(8 + USIZE)],
+        &format!("entry count {}", word_count),
+    )?;
+    let str_length = usize::from_le_bytes(header[(8 + USIZE)..].try_into().unwrap());
+    dump.field(
+        &header[(8 + USIZE)..],
+        &format!("word string length {}", str_length),
+    )?;
+    // the header is narrated before being checked, to show what's wrong
+    peek_header(&mut header.as_slice())?;
+
+    let word_string = read_bytes(reader, str_length)?;
+    dump.field(&word_string, "word string")?;
+
+    for i in 0..word_count {
+        let entry = read_bytes(reader, WORD_COUNT_STRIDE)?;
+        let offset = usize::from_le_bytes(entry[..USIZE].try_into().unwrap());
+        let len = usize::from_le_bytes(entry[USIZE..(USIZE * 2)].try_into().unwrap());
+        let set: [u8; COUNT_SET_SIZE] = entry[(USIZE * 2)..].try_into().unwrap();
+        let word = offset
+            .checked_add(len)
+            .and_then(|end| word_string.get(offset..end))
+            .map_or_else(
+                || String::from("out of bounds"),
+                |word| format!("{:?}", String::from_utf8_lossy(word)),
+            );
+
+        dump.field(
+            &entry[..USIZE],
+            &format!("entry {}: offset {} ({})", i, offset, word),
+        )?;
+        dump.field(&entry[USIZE..(USIZE * 2)], &format!("  length {}", len))?;
+        // the letters of an entry are counted again, to show corrupted counts
+        let checked = offset
+            .checked_add(len)
+            .and_then(|end| word_string.get(offset..end))
+            .and_then(|word| std::str::from_utf8(word).ok())
+            .map(|word| CountSet::from_bytes_checked(set, word));
+        let counts = match checked {
+            Some(Err(err)) => format!("{:?} ({})", CountSet::from(set), err),
+            _ => format!("{:?}", CountSet::from(set)),
+        };
+        dump.field(&entry[(USIZE * 2)..], &format!("  counts {}", counts))?;
+    }
+
+    if version >= 2 {
+        loop {
+            let tag = read_bytes(reader, 4)?;
+            let name = String::from_utf8_lossy(&tag).into_owned();
+            if tag == SECTION_END {
+                dump.field(&tag, "end of sections")?;
+                break;
+            }
+            dump.field(&tag, &format!("section {:?}", name))?;
+
+            let length = read_bytes(reader, USIZE)?;
+            dump.field(&length, "  length")?;
+            let payload = read_bytes(reader, usize::from_le_bytes(length[..].try_into().unwrap()))?;
+            dump.field(&payload, "  payload")?;
+        }
+    }
+
+    let mut trailing = Vec::new();
+    reader.read_to_end(&mut trailing)?;
+    if !trailing.is_empty() {
+        dump.field(&trailing, "trailing data")?;
+    }
+
+    Ok(())
+}
+
+// writes fields as hex, 16 bytes per line, with their position in the file
+struct Dump<'a, W: Write> {
+    writer: &'a mut W,
+    position: usize,
+}
+
+impl<W: Write> Dump<'_, W> {
+    fn field(&mut self, bytes: &[u8], label: &str) -> io::Result<()> {
+        if bytes.is_empty() {
+            writeln!(self.writer, "{:08x}  {:<48}  {}", self.position, "", label)?;
+        }
+
+        for (i, line) in bytes.chunks(16).enumerate() {
+            let hex = line
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<Vec<_>>()
+                .join(" ");
+            writeln!(
+                self.writer,
+                "{:08x}  {:<48}  {}",
+                self.position + i * 16,
+                hex,
+                if i == 0 { label } else { "" }
+            )?;
+        }
+        self.position += bytes.len();
+
+        Ok(())
+    }
+}
+
+// reads exactly `len` bytes without allocating them upfront, since `len`
+// comes from the file and may be garbage
+fn read_bytes<R: Read + ?Sized>(reader: &mut R, len: usize) -> Result<Vec<u8>, ReadError> {
+    let mut bytes = Vec::new();
+    reader.take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != len {
+        return Err(ReadError::FormatError);
+    }
+
+    Ok(bytes)
+}
+
+fn read_usize<R: Read + ?Sized>(reader: &mut R) -> io::Result<usize> {
+    let mut bytes = [0; USIZE];
+    reader.read_exact(&mut bytes)?;
+    Ok(usize::from_le_bytes(bytes))
+}
+
+type Sources = (Vec<String>, HashMap<OffsetLength, u16>);
+
+fn parse_sources<I>(mut payload: &[u8], keys: I) -> Result<Sources, ReadError>
+where
+    I: ExactSizeIterator<Item = OffsetLength>,
+{
+    let name_count = read_usize(&mut payload)?;
+    let mut names = Vec::new();
+    for _ in 0..name_count {
+        let len = read_usize(&mut payload)?;
+        let name = payload.get(..len).ok_or(ReadError::FormatError)?;
+        names.push(String::from_utf8(name.to_vec()).map_err(|_| ReadError::FormatError)?);
+        payload = &payload[len..];
+    }
+
+    if payload.len() != keys.len() * 2 {
+        return Err(ReadError::FormatError);
+    }
+
+    let mut word_sources = HashMap::new();
+    for (key, source) in keys.zip(payload.chunks_exact(2)) {
+        let source = u16::from_le_bytes([source[0], source[1]]);
+        if source == NO_SOURCE {
+            continue;
+        }
+        if source as usize >= names.len() {
+            return Err(ReadError::FormatError);
+        }
+        word_sources.insert(key, source);
+    }
+
+    Ok((names, word_sources))
+}
+
+fn parse_derived<I>(payload: &[u8], keys: I) -> Result<HashSet<OffsetLength>, ReadError>
+where
+    I: ExactSizeIterator<Item = OffsetLength>,
+{
+    if payload.len() != keys.len().div_ceil(8) {
+        return Err(ReadError::FormatError);
+    }
+
+    Ok(keys
+        .enumerate()
+        .filter(|&(i, _)| payload[i / 8] & (1 << (i % 8)) != 0)
+        .map(|(_, key)| key)
+        .collect())
+}
+
+fn parse_tiers<I>(payload: &[u8], keys: I) -> Result<HashMap<OffsetLength, u8>, ReadError>
+where
+    I: ExactSizeIterator<Item = OffsetLength>,
+{
+    if payload.len() != keys.len().div_ceil(4) {
+        return Err(ReadError::FormatError);
+    }
+
+    Ok(keys
+        .enumerate()
+        .map(|(i, key)| (key, (payload[i / 4] >> (i % 4 * 2)) & 0b11))
+        .collect())
+}
+
+fn parse_defaults(payload: &[u8]) -> Result<SearchDefaults, ReadError> {
+    if !payload.len().is_multiple_of(4 + USIZE) {
+        return Err(ReadError::FormatError);
+    }
+
+    let mut defaults = SearchDefaults::default();
+    for option in payload.chunks_exact(4 + USIZE) {
+        let value = usize::from_le_bytes(option[4..].try_into().unwrap());
+        // unknown options are skipped, like unknown sections
+        if &option[..4] == DEFAULT_MIN_LENGTH {
+            defaults.min_length = Some(value);
+        }
+    }
+
+    Ok(defaults)
+}
+
+/// Writes a dictionary file, with the entry table in the order of the
+/// dictionary's hash map, which differs from one run to the next.
+pub fn write_dict<W: Write + ?Sized>(dict: &Dictionary, writer: &mut W) -> io::Result<()> {
+    write_dict_entries(dict, dict.word_count().keys().collect(), writer)
+}
+
+/// Writes a dictionary file with the entry table in word string order, so
+/// that the same dictionary always gives a byte-identical file.
+pub fn write_dict_deterministic<W: Write + ?Sized>(
+    dict: &Dictionary,
+    writer: &mut W,
+) -> io::Result<()> {
+    let mut keys = dict.word_count().keys().collect::<Vec<_>>();
+    keys.par_sort_unstable();
+    write_dict_entries(dict, keys, writer)
+}
+
+fn write_dict_entries<W: Write + ?Sized>(
+    dict: &Dictionary,
+    keys: Vec<&OffsetLength>,
+    writer: &mut W,
+) -> io::Result<()> {
     writer.write_all(b"DICT")?;
     writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
     writer.write_all(&dict.len().to_le_bytes())?;
     writer.write_all(&dict.word_string().len().to_le_bytes())?;
     writer.write_all(dict.word_string().as_bytes())?;
-    for (&(offset, len), set) in dict.word_count().iter() {
+    for key @ &(offset, len) in keys.iter().copied() {
         writer.write_all(&offset.to_le_bytes())?;
         writer.write_all(&len.to_le_bytes())?;
-        writer.write_all(set.slice())?;
+        writer.write_all(&<[u8; COUNT_SET_SIZE]>::from(
+            dict.word_count()[key].clone(),
+        ))?;
+    }
+
+    if !dict.sources().is_empty() {
+        let mut payload = Vec::new();
+        payload.extend_from_slice(&dict.sources().len().to_le_bytes());
+        for name in dict.sources().iter() {
+            payload.extend_from_slice(&name.len().to_le_bytes());
+            payload.extend_from_slice(name.as_bytes());
+        }
+        // same order as the entry table
+        for key in keys.iter().copied() {
+            let source = dict.word_sources().get(key).copied().unwrap_or(NO_SOURCE);
+            payload.extend_from_slice(&source.to_le_bytes());
+        }
+        write_section(writer, SECTION_SOURCES, &payload)?;
     }
 
+    if !dict.derived().is_empty() {
+        let mut payload = vec![0u8; keys.len().div_ceil(8)];
+        for (i, key) in keys.iter().enumerate() {
+            if dict.derived().contains(*key) {
+                payload[i / 8] |= 1 << (i % 8);
+            }
+        }
+        write_section(writer, SECTION_DERIVED, &payload)?;
+    }
+
+    if dict.has_tiers() {
+        let mut payload = vec![0u8; keys.len().div_ceil(4)];
+        for (i, key) in keys.iter().enumerate() {
+            let tier = dict.get(key).and_then(|entry| entry.tier()).unwrap();
+            payload[i / 4] |= tier << (i % 4 * 2);
+        }
+        write_section(writer, SECTION_TIERS, &payload)?;
+    }
+
+    if let Some(min_length) = dict.search_defaults().min_length {
+        let mut payload = Vec::new();
+        payload.extend_from_slice(DEFAULT_MIN_LENGTH);
+        payload.extend_from_slice(&min_length.to_le_bytes());
+        write_section(writer, SECTION_DEFAULTS, &payload)?;
+    }
+
+    writer.write_all(SECTION_END)?;
+
     Ok(())
 }
+
+fn write_section<W: Write + ?Sized>(
+    writer: &mut W,
+    tag: &[u8; 4],
+    payload: &[u8],
+) -> io::Result<()> {
+    writer.write_all(tag)?;
+    writer.write_all(&payload.len().to_le_bytes())?;
+    writer.write_all(payload)
+}
+
+/// Reads a sorted index sidecar file, checking it against its dictionary.
+pub fn read_sorted_index<R: Read + ?Sized>(
+    reader: &mut R,
+    dict: &Dictionary,
+) -> Result<SortedIndex, ReadError> {
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != b"SIDX" {
+        return Err(ReadError::FormatError);
+    }
+
+    let mut version = [0; 4];
+    reader.read_exact(&mut version)?;
+    let version = u32::from_le_bytes(version);
+    if version != SORTED_INDEX_VERSION {
+        return Err(ReadError::FormatError);
+    }
+
+    let mut entries_length = [0; USIZE];
+    reader.read_exact(&mut entries_length)?;
+    let entries_length = usize::from_le_bytes(entries_length);
+    if entries_length != dict.len() {
+        return Err(ReadError::FormatError);
+    }
+
+    let mut entries_buf = vec![0; entries_length * USIZE * 2];
+    reader.read_exact(&mut entries_buf)?;
+
+    let entries = entries_buf
+        .chunks_exact(USIZE * 2)
+        .map(|entry| {
+            let offset = usize::from_le_bytes(entry[0..USIZE].try_into().unwrap());
+            let len = usize::from_le_bytes(entry[USIZE..].try_into().unwrap());
+            (offset, len)
+        })
+        .collect();
+
+    SortedIndex::from_sorted(dict, entries).ok_or(ReadError::FormatError)
+}
+
+pub fn write_sorted_index<W: Write>(index: &SortedIndex, writer: &mut W) -> io::Result<()> {
+    writer.write_all(b"SIDX")?;
+    writer.write_all(&SORTED_INDEX_VERSION.to_le_bytes())?;
+    writer.write_all(&index.entries().len().to_le_bytes())?;
+    for &(offset, len) in index.entries().iter() {
+        writer.write_all(&offset.to_le_bytes())?;
+        writer.write_all(&len.to_le_bytes())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::to_hex;
+
+    #[test]
+    fn peek_header() {
+        let dict = ["ladies", "and", "gentlemen"]
+            .iter()
+            .collect::<Dictionary>();
+        let mut file = Vec::new();
+        write_dict(&dict, &mut file).unwrap();
+
+        let mut reader = file.as_slice();
+        let header = super::peek_header(&mut reader).unwrap();
+        assert_eq!(
+            header,
+            DictHeader {
+                version: FORMAT_VERSION,
+                word_count: 3,
+                str_length: 18,
+            }
+        );
+
+        // the reader is left at the start of the word string
+        assert!(reader.starts_with(dict.word_string().as_bytes()));
+    }
+
+    #[test]
+    fn estimated_memory() {
+        let header = |word_count, str_length| DictHeader {
+            version: FORMAT_VERSION,
+            word_count,
+            str_length,
+        };
+        let bucket = std::mem::size_of::<(OffsetLength, CountSet)>() + 1;
+
+        assert_eq!(header(0, 0).estimated_memory(), 0);
+        // 3 entries need 4 buckets, 7 need 8 and 8 need 16
+        assert_eq!(header(3, 18).estimated_memory(), 18 + 4 * bucket);
+        assert_eq!(header(7, 30).estimated_memory(), 30 + 8 * bucket);
+        assert_eq!(header(8, 30).estimated_memory(), 30 + 16 * bucket);
+        // corrupted headers don't overflow
+        assert_eq!(header(usize::MAX, 1).estimated_memory(), usize::MAX);
+    }
+
+    #[test]
+    fn sources_round_trip() {
+        let mut dict = Dictionary::new();
+        let twl = dict.add_source("twl").unwrap();
+        let collins = dict.add_source("collins").unwrap();
+        dict.add_with_source("cat", twl).unwrap();
+        dict.add_with_source("qi", collins).unwrap();
+        dict.add("dog").unwrap();
+
+        let mut file = Vec::new();
+        write_dict(&dict, &mut file).unwrap();
+        let read = read_dict(&mut file.as_slice()).unwrap();
+
+        assert_eq!(read.sources(), dict.sources());
+        assert_eq!(read.word_sources(), dict.word_sources());
+    }
+
+    #[test]
+    fn tiers_round_trip() {
+        let mut dict = ["a", "b", "c", "d", "e"].iter().collect::<Dictionary>();
+        for (i, tier) in [0, 1, 2, 3].into_iter().enumerate() {
+            dict.set_tier((i, 1), tier);
+        }
+
+        let mut file = Vec::new();
+        write_dict(&dict, &mut file).unwrap();
+        let read = read_dict(&mut file.as_slice()).unwrap();
+
+        let tiers = (0..5)
+            .map(|i| read.get(&(i, 1)).unwrap().tier())
+            .collect::<Vec<_>>();
+        // "e" has no tier of its own
+        assert_eq!(tiers, [Some(0), Some(1), Some(2), Some(3), Some(3)]);
+
+        let mut file = Vec::new();
+        write_dict(&["a"].iter().collect::<Dictionary>(), &mut file).unwrap();
+        assert!(!read_dict(&mut file.as_slice()).unwrap().has_tiers());
+    }
+
+    #[test]
+    fn file_hash() {
+        assert_eq!(
+            to_hex(&super::file_hash(&mut &b"abc"[..]).unwrap()),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+
+        // the same words in different files
+        let dict = ["cat", "dog"].iter().collect::<Dictionary>();
+        let mut sourced = Dictionary::new();
+        let source = sourced.add_source("twl").unwrap();
+        sourced.add_with_source("dog", source).unwrap();
+        sourced.add_with_source("cat", source).unwrap();
+        let (mut file, mut sourced_file) = (Vec::new(), Vec::new());
+        write_dict(&dict, &mut file).unwrap();
+        write_dict(&sourced, &mut sourced_file).unwrap();
+
+        let hash = |file: &[u8]| super::file_hash(&mut &file[..]).unwrap();
+        assert_ne!(hash(&file), hash(&sourced_file));
+        let read = |file: &[u8]| read_dict(&mut &file[..]).unwrap().content_hash();
+        assert_eq!(read(&file), read(&sourced_file));
+        assert_eq!(read(&file), dict.content_hash());
+    }
+
+    #[test]
+    fn file_fnv_hash() {
+        // reference values of FNV-1a
+        assert_eq!(
+            super::file_fnv_hash(&mut &b""[..]).unwrap(),
+            0xcbf29ce484222325
+        );
+        assert_eq!(
+            super::file_fnv_hash(&mut &b"a"[..]).unwrap(),
+            0xaf63dc4c8601ec8c
+        );
+    }
+
+    #[test]
+    fn derived_round_trip() {
+        let mut dict = Dictionary::new();
+        // more than a byte of entries
+        for word in ["a", "b", "c", "d", "e", "f", "g", "h", "i"] {
+            dict.add(word).unwrap();
+        }
+        dict.add_derived("cats", None).unwrap();
+
+        let mut file = Vec::new();
+        write_dict(&dict, &mut file).unwrap();
+        let read = read_dict(&mut file.as_slice()).unwrap();
+
+        assert_eq!(read.derived(), dict.derived());
+        assert!(read.get(&(9, 4)).unwrap().derived);
+    }
+
+    // a file replaced while being read, between the header and the rest
+    #[test]
+    fn mismatched_header() {
+        const HEADER_SIZE: usize = 8 + USIZE * 2;
+
+        let write = |words: &[&str]| {
+            let mut file = Vec::new();
+            write_dict(&words.iter().collect::<Dictionary>(), &mut file).unwrap();
+            file
+        };
+        let old = write(&["cat", "dog"]);
+        let new = write(&["elephant", "giraffe", "hippopotamus"]);
+
+        let mut mixed = old[..HEADER_SIZE].chain(&new[HEADER_SIZE..]);
+        assert!(matches!(read_dict(&mut mixed), Err(ReadError::FormatError)));
+
+        let mut mixed = new[..HEADER_SIZE].chain(&old[HEADER_SIZE..]);
+        assert!(read_dict(&mut mixed).is_err());
+    }
+
+    #[test]
+    fn corrupted_length() {
+        let dict = ["cat", "dog"].iter().collect::<Dictionary>();
+        let mut file = Vec::new();
+        write_dict(&dict, &mut file).unwrap();
+
+        // shortens the first entry of the table, which stays within the string
+        let table = 8 + USIZE * 2 + dict.word_string().len();
+        let len = table + USIZE;
+        let offset = usize::from_le_bytes(file[table..len].try_into().unwrap());
+        file[len..(len + USIZE)].copy_from_slice(&2usize.to_le_bytes());
+
+        match read_dict(&mut file.as_slice()).unwrap_err() {
+            ReadError::LengthMismatch {
+                offset: o,
+                len: 2,
+                letters: 3,
+            } if o == offset => {}
+            err => panic!("Wrong 'length_mismatch' error! {:?}", err),
+        }
+    }
+
+    #[test]
+    fn explain() {
+        let mut dict = Dictionary::new();
+        let source = dict.add_source("twl").unwrap();
+        dict.add_with_source("cat", source).unwrap();
+        let mut file = Vec::new();
+        write_dict(&dict, &mut file).unwrap();
+
+        let mut out = Vec::new();
+        explain_dict(&mut file.as_slice(), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines = out.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines[0],
+            format!("00000000  44 49 43 54{}  magic", " ".repeat(37))
+        );
+        assert!(lines[1].ends_with("format version 2"));
+        assert!(lines[4].starts_with(&format!("{:08x}  63 61 74 ", 8 + USIZE * 2)));
+        assert!(lines[4].ends_with("word string"));
+        assert!(lines[5].ends_with("entry 0: offset 0 (\"cat\")"));
+        assert!(out.contains("section \"SRCS\""));
+        assert!(lines.last().unwrap().ends_with("end of sections"));
+        assert!(!out.contains("match"));
+
+        // a corrupted count, one `y` more, is pointed out
+        let mut corrupted = file.clone();
+        corrupted[8 + USIZE * 2 + 3 + USIZE * 2 + 12] = 1;
+        let mut out = Vec::new();
+        explain_dict(&mut corrupted.as_slice(), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("'Y': 1} (Letter counts don't match the letters of the word)"));
+
+        // corrupted files are explained up to the error
+        let mut out = Vec::new();
+        assert!(explain_dict(&mut &file[..30], &mut out).is_err());
+        assert!(String::from_utf8(out).unwrap().contains("word string"));
+    }
+
+    #[test]
+    fn trailing_data() {
+        let dict = ["cat", "dog"].iter().collect::<Dictionary>();
+        let mut file = Vec::new();
+        write_dict(&dict, &mut file).unwrap();
+
+        let (_, report) = read_dict_report(&mut file.as_slice()).unwrap();
+        assert_eq!(report.trailing_bytes, 0);
+
+        file.extend_from_slice(b"junk");
+        let (read, report) = read_dict_report(&mut file.as_slice()).unwrap();
+        assert_eq!(report.trailing_bytes, 4);
+        assert_eq!(read.word_count(), dict.word_count());
+    }
+
+    #[test]
+    fn deterministic() {
+        let write = || {
+            let mut dict = Dictionary::new();
+            let source = dict.add_source("twl").unwrap();
+            for word in ["ladies", "and", "gentlemen", "we", "are", "floating"] {
+                dict.add_with_source(word, source).unwrap();
+            }
+
+            let mut file = Vec::new();
+            write_dict_deterministic(&dict, &mut file).unwrap();
+            file
+        };
+
+        // separate maps get separate hash seeds
+        let file = write();
+        assert!((0..5).all(|_| write() == file));
+        let read = read_dict(&mut file.as_slice()).unwrap();
+        assert_eq!(read.len(), 6);
+        assert_eq!(read.word_sources().len(), 6);
+    }
+
+    #[test]
+    fn search_defaults_round_trip() {
+        let mut dict = ["bee", "hive"].iter().collect::<Dictionary>();
+        let mut file = Vec::new();
+        write_dict(&dict, &mut file).unwrap();
+        let read = read_dict(&mut file.as_slice()).unwrap();
+        assert!(read.search_defaults().is_empty());
+
+        dict.set_search_defaults(SearchDefaults {
+            min_length: Some(4),
+        });
+        let mut file = Vec::new();
+        write_dict(&dict, &mut file).unwrap();
+        let read = read_dict(&mut file.as_slice()).unwrap();
+        assert_eq!(read.search_defaults(), dict.search_defaults());
+    }
+
+    #[test]
+    fn read_version_1() {
+        let dict = ["cat", "dog"].iter().collect::<Dictionary>();
+        let mut file = Vec::new();
+        write_dict(&dict, &mut file).unwrap();
+
+        // version 1 is version 2 without sections
+        file[4..8].copy_from_slice(&1u32.to_le_bytes());
+        file.truncate(file.len() - SECTION_END.len());
+
+        let read = read_dict(&mut file.as_slice()).unwrap();
+        assert_eq!(read.word_count(), dict.word_count());
+    }
+
+    // words with 15 of a letter (the limit) in every low and high nibble,
+    // alone and next to each other
+    #[test]
+    fn nibble_boundary_round_trip() {
+        let mut dict = Dictionary::new();
+        for i in 0..26u8 {
+            let letter = (b'a' + i) as char;
+            dict.add(&letter.to_string().repeat(15)).unwrap();
+            dict.add(&letter.to_string()).unwrap();
+
+            if i < 25 {
+                let next = (b'a' + i + 1) as char;
+                dict.add(&format!("{}{}", letter.to_string().repeat(15), next))
+                    .unwrap();
+                dict.add(&format!("{}{}", letter, next.to_string().repeat(15)))
+                    .unwrap();
+                dict.add(&format!(
+                    "{}{}",
+                    letter.to_string().repeat(15),
+                    next.to_string().repeat(15)
+                ))
+                .unwrap();
+            }
+        }
+
+        let mut file = Vec::new();
+        write_dict(&dict, &mut file).unwrap();
+        let read = read_dict(&mut file.as_slice()).unwrap();
+
+        assert_eq!(read.len(), dict.len());
+        assert!(read.count_mismatches().is_empty());
+        for (key, count_set) in dict.word_count().iter() {
+            assert_eq!(read.word_count().get(key), Some(count_set));
+        }
+    }
+}
@@ -0,0 +1,244 @@
+//! Glob patterns naming several dictionary files, such as `dicts/*.dict`
+//!
+//! The patterns are expanded here rather than by the shell, which doesn't
+//! expand them on Windows. `*` matches any run of characters and `?` any one
+//! character within a path component, `[abc]` and `[a-z]` one character of a
+//! set and `[!abc]` one character out of it. Names starting with a dot are
+//! only matched by patterns starting with one, as by shells.
+//!
+//! Paths are matched through their `OsStr` bytes, so that names that aren't
+//! valid UTF-8 still match: each invalid byte counts as a character.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Whether the path is a pattern: it has wildcards and isn't an existing
+/// file (whose name happens to contain them)
+pub fn is_pattern(path: &Path) -> bool {
+    has_wildcards(path.as_os_str().as_encoded_bytes()) && !path.is_file()
+}
+
+/// Files matching the pattern, sorted by path so that the order doesn't
+/// depend on the file system. Matching nothing isn't an error.
+pub fn expand(pattern: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths = vec![PathBuf::new()];
+    for component in pattern.components() {
+        let name = component.as_os_str();
+        if !has_wildcards(name.as_encoded_bytes()) {
+            for path in paths.iter_mut() {
+                path.push(name);
+            }
+            continue;
+        }
+
+        let mut matched = Vec::new();
+        for dir in paths {
+            let listed = if dir.as_os_str().is_empty() {
+                fs::read_dir(".")
+            } else {
+                fs::read_dir(&dir)
+            };
+            let entries = match listed {
+                Ok(entries) => entries,
+                // a component before the wildcards that isn't a directory
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        io::ErrorKind::NotFound | io::ErrorKind::NotADirectory
+                    ) =>
+                {
+                    continue
+                }
+                Err(err) => return Err(err),
+            };
+            for entry in entries {
+                let file_name = entry?.file_name();
+                if matches(name.as_encoded_bytes(), file_name.as_encoded_bytes()) {
+                    matched.push(dir.join(file_name));
+                }
+            }
+        }
+        paths = matched;
+    }
+
+    paths.retain(|path| path.is_file());
+    paths.sort();
+    Ok(paths)
+}
+
+fn has_wildcards(bytes: &[u8]) -> bool {
+    bytes.iter().any(|b| matches!(b, b'*' | b'?' | b'['))
+}
+
+/// Whether a file name matches a pattern of one path component
+pub fn matches(pattern: &[u8], name: &[u8]) -> bool {
+    if name.first() == Some(&b'.') && pattern.first() != Some(&b'.') {
+        return false;
+    }
+
+    matches_from(pattern, name)
+}
+
+// matches by giving the last `*` one more character on a mismatch. The name
+// is stepped through a UTF-8 character at a time, however many bytes it
+// takes, so that `?` and `[...]` only ever see whole characters
+fn matches_from(pattern: &[u8], name: &[u8]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    // the pattern after the last `*`, and where in the name to try it next
+    let mut star = None;
+    while n < name.len() {
+        if pattern.get(p) == Some(&b'*') {
+            p += 1;
+            star = Some((p, n));
+            continue;
+        }
+        if let Some((pattern_len, name_len)) = match_one(&pattern[p..], &name[n..]) {
+            p += pattern_len;
+            n += name_len;
+            continue;
+        }
+        match star {
+            // the `*` takes one more character
+            Some((star_p, star_n)) => {
+                let (_, len) = next_char(&name[star_n..]).unwrap();
+                star = Some((star_p, star_n + len));
+                (p, n) = (star_p, star_n + len);
+            }
+            None => return false,
+        }
+    }
+
+    pattern[p..].iter().all(|&b| b == b'*')
+}
+
+// the lengths of the first token of the pattern, other than `*`, and of the
+// character of the name it matches, if it does
+fn match_one(pattern: &[u8], name: &[u8]) -> Option<(usize, usize)> {
+    let (c, len) = next_char(name)?;
+    match *pattern.first()? {
+        b'?' => Some((1, len)),
+        b'[' => match class(&pattern[1..], c) {
+            Some((found, rest)) => found.then_some((pattern.len() - rest.len(), len)),
+            // an unterminated set is a plain `[`
+            None => (name[0] == b'[').then_some((1, 1)),
+        },
+        b => (name[0] == b).then_some((1, 1)),
+    }
+}
+
+// whether the set of a `[...]` class, following the `[`, has `c`, and the
+// rest of the pattern after the `]`, or `None` if the set is unterminated. A
+// `]` first in the set is one of its characters
+fn class(pattern: &[u8], c: u32) -> Option<(bool, &[u8])> {
+    let (negated, mut rest) = match pattern.first() {
+        Some(b'!' | b'^') => (true, &pattern[1..]),
+        _ => (false, pattern),
+    };
+    let mut found = false;
+    let mut first = true;
+    loop {
+        let (start, len) = next_char(rest)?;
+        rest = &rest[len..];
+        if start == ']' as u32 && !first {
+            return Some((found != negated, rest));
+        }
+        first = false;
+
+        let end = match rest {
+            [b'-', next, ..] if *next != b']' => {
+                let (end, len) = next_char(&rest[1..])?;
+                rest = &rest[(1 + len)..];
+                end
+            }
+            _ => start,
+        };
+        found |= (start..=end).contains(&c);
+    }
+}
+
+// the first character of the bytes and its length, a byte that doesn't start
+// a valid UTF-8 sequence standing for itself, above every `char`
+fn next_char(bytes: &[u8]) -> Option<(u32, usize)> {
+    let &first = bytes.first()?;
+    let len = match first {
+        0x00..=0x7f => 1,
+        0xc2..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf4 => 4,
+        _ => 0,
+    };
+    match bytes.get(..len).map(std::str::from_utf8) {
+        Some(Ok(s)) if len > 0 => Some((s.chars().next().unwrap() as u32, len)),
+        _ => Some((0x11_0000 + first as u32, 1)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches() {
+        let cases = [
+            ("*.dict", "en.dict", true),
+            ("*.dict", "en.dict.idx", false),
+            ("*.dict", ".hidden.dict", false),
+            (".*.dict", ".hidden.dict", true),
+            ("e?.dict", "en.dict", true),
+            ("e?.dict", "e.dict", false),
+            ("?.dict", "é.dict", true),
+            ("[ef]*", "fr.dict", true),
+            ("[!ef]*", "fr.dict", false),
+            ("[a-z][a-z]", "en", true),
+            ("[a-z][a-z]", "e1", false),
+            ("[]]", "]", true),
+            ("[x", "[x", true),
+            ("*", "", true),
+            ("a*b*c", "abxbc", true),
+            ("a*a*a*a*a*a*a*a*b", &"a".repeat(60), false),
+            ("*[ab]?*.dict", "xxbé.dict", true),
+        ];
+        for (pattern, name, expected) in cases {
+            assert_eq!(
+                super::matches(pattern.as_bytes(), name.as_bytes()),
+                expected,
+                "{} {}",
+                pattern,
+                name
+            );
+        }
+
+        // a byte that isn't UTF-8 is a character
+        assert!(super::matches(b"?de.dict", b"\xffde.dict"));
+        assert!(super::matches(b"[!a]*", b"\xff"));
+    }
+
+    #[test]
+    fn expand() {
+        let dir =
+            std::env::temp_dir().join(format!("word-puzzle-searcher-glob-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for path in ["fr/words.dict", "en/words.dict", "en/notes.txt", "de.dict"] {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+
+        assert_eq!(
+            super::expand(&dir.join("*/*.dict")).unwrap(),
+            [dir.join("en/words.dict"), dir.join("fr/words.dict")]
+        );
+        assert_eq!(
+            super::expand(&dir.join("*")).unwrap(),
+            [dir.join("de.dict")]
+        );
+        assert!(super::expand(&dir.join("missing/*.dict"))
+            .unwrap()
+            .is_empty());
+        assert!(is_pattern(&dir.join("*.dict")));
+        assert!(!is_pattern(&dir.join("de.dict")));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,363 @@
+//! A log of searches, to look back at solving sessions
+//!
+//! Each search appends a line of JSON to the log (JSONL) with the time, the
+//! query as echoed by `SearchQuery`, the hash of the dictionary and the
+//! number of results. A line is written with a single write to a file opened
+//! in append mode, so that neither a crash nor another search writing at the
+//! same time can leave a partial line in the middle of the log.
+
+use std::fmt::{self, Write as _};
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::iter::Peekable;
+use std::path::Path;
+use std::str::{Chars, FromStr};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Environment variable turning the log off when set, whatever the options
+pub const NO_HISTORY_VAR: &str = "WPS_NO_HISTORY";
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// A search, as logged
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HistoryEntry {
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    /// The query, as displayed by `SearchQuery`
+    pub query: String,
+    /// `format::file_fnv_hash` of the dictionary file
+    pub dictionary_hash: u64,
+    /// Number of words found, before paging
+    pub results: usize,
+}
+
+impl HistoryEntry {
+    /// An entry for a search made now
+    pub fn now(query: String, dictionary_hash: u64, results: usize) -> Self {
+        Self {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs()),
+            query,
+            dictionary_hash,
+            results,
+        }
+    }
+
+    /// The rack of the query, with its letters in alphabetical order
+    pub fn rack(&self) -> Option<&str> {
+        self.query
+            .split(';')
+            .find_map(|field| field.strip_prefix("rack="))
+    }
+
+    /// The day of the search, in UTC
+    pub fn date(&self) -> Date {
+        Date::from_days(self.timestamp / SECONDS_PER_DAY)
+    }
+
+    /// Appends the entry to the log at `path`, creating it if needed
+    pub fn append_to(&self, path: &Path) -> io::Result<()> {
+        let line = format!("{}\n", self);
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        file.write_all(line.as_bytes())
+    }
+}
+
+/// Writes the entry as a line of JSON, without the line break.
+impl fmt::Display for HistoryEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{\"timestamp\":{},\"query\":", self.timestamp)?;
+        write_json_string(f, &self.query)?;
+        write!(
+            f,
+            ",\"dictionary\":\"fnv1a64:{:016x}\",\"results\":{}}}",
+            self.dictionary_hash, self.results
+        )
+    }
+}
+
+/// Reads a line of the log. Unknown fields are ignored, so that logs written
+/// by later versions can still be read.
+impl FromStr for HistoryEntry {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut timestamp = None;
+        let mut query = None;
+        let mut dictionary_hash = None;
+        let mut results = None;
+
+        for (name, value) in parse_json_object(s)? {
+            let invalid = || format!("invalid history field {}={:?}", name, value);
+            match name.as_str() {
+                "timestamp" => timestamp = Some(value.parse().map_err(|_| invalid())?),
+                "query" => query = Some(value),
+                "dictionary" => {
+                    let hash = value.strip_prefix("fnv1a64:").ok_or_else(invalid)?;
+                    dictionary_hash = Some(u64::from_str_radix(hash, 16).map_err(|_| invalid())?);
+                }
+                "results" => results = Some(value.parse().map_err(|_| invalid())?),
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            timestamp: timestamp.ok_or("missing timestamp in history")?,
+            query: query.ok_or("missing query in history")?,
+            dictionary_hash: dictionary_hash.ok_or("missing dictionary in history")?,
+            results: results.ok_or("missing results in history")?,
+        })
+    }
+}
+
+pub(crate) fn write_json_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    f.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+// the fields of a JSON object of strings and non-negative integers, which is
+// all the log holds, numbers being kept as text
+fn parse_json_object(s: &str) -> Result<Vec<(String, String)>, String> {
+    let mut chars = s.trim().chars().peekable();
+    let mut fields = Vec::new();
+
+    expect(&mut chars, '{')?;
+    skip_whitespace(&mut chars);
+    if chars.peek() == Some(&'}') {
+        chars.next();
+    } else {
+        loop {
+            skip_whitespace(&mut chars);
+            let name = parse_json_string(&mut chars)?;
+            skip_whitespace(&mut chars);
+            expect(&mut chars, ':')?;
+            skip_whitespace(&mut chars);
+            let value = match chars.peek() {
+                Some('"') => parse_json_string(&mut chars)?,
+                _ => {
+                    let mut number = String::new();
+                    while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                        number.push(c);
+                        chars.next();
+                    }
+                    if number.is_empty() {
+                        return Err(format!("invalid value of history field {:?}", name));
+                    }
+                    number
+                }
+            };
+            fields.push((name, value));
+
+            skip_whitespace(&mut chars);
+            match chars.next() {
+                Some(',') => continue,
+                Some('}') => break,
+                _ => return Err(String::from("expected ',' or '}' in history line")),
+            }
+        }
+    }
+
+    if chars.next().is_some() {
+        return Err(String::from("trailing characters in history line"));
+    }
+    Ok(fields)
+}
+
+fn parse_json_string(chars: &mut Peekable<Chars>) -> Result<String, String> {
+    expect(chars, '"')?;
+    let mut s = String::new();
+    loop {
+        match chars.next().ok_or("unterminated string in history line")? {
+            '"' => return Ok(s),
+            '\\' => match chars.next().ok_or("unterminated string in history line")? {
+                'n' => s.push('\n'),
+                'r' => s.push('\r'),
+                't' => s.push('\t'),
+                'b' => s.push('\u{8}'),
+                'f' => s.push('\u{c}'),
+                'u' => {
+                    let hex = chars.by_ref().take(4).collect::<String>();
+                    let c = u32::from_str_radix(&hex, 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .ok_or_else(|| format!("invalid escape \\u{} in history line", hex))?;
+                    s.push(c);
+                }
+                c @ ('"' | '\\' | '/') => s.push(c),
+                c => return Err(format!("invalid escape \\{} in history line", c)),
+            },
+            c => s.push(c),
+        }
+    }
+}
+
+fn expect(chars: &mut Peekable<Chars>, expected: char) -> Result<(), String> {
+    match chars.next() {
+        Some(c) if c == expected => Ok(()),
+        _ => Err(format!("expected {:?} in history line", expected)),
+    }
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+/// A day of the proleptic Gregorian calendar, written `YYYY-MM-DD`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    pub year: u32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    /// The date a number of days after 1970-01-01
+    pub fn from_days(days: u64) -> Self {
+        // Howard Hinnant's `civil_from_days`, for days after the epoch
+        let z = days + 719_468;
+        let era = z / 146_097;
+        let day_of_era = z % 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+        Self {
+            year: year as u32,
+            month: month as u32,
+            day: day as u32,
+        }
+    }
+}
+
+impl FromStr for Date {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid date {:?}, expected YYYY-MM-DD", s);
+        let mut parts = s.splitn(3, '-').map(|part| part.parse::<u32>());
+        let (Some(Ok(year)), Some(Ok(month)), Some(Ok(day))) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid());
+        };
+
+        let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+        let days_in_month = match month {
+            2 if leap => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            1..=12 => 31,
+            _ => return Err(invalid()),
+        };
+        if year < 1970 || !(1..=days_in_month).contains(&day) {
+            return Err(invalid());
+        }
+
+        Ok(Self { year, month, day })
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// Writes the time of the entry in UTC, then its number of results and its
+/// query, as listed by the `history` subcommand.
+pub fn write_entry<W: Write>(writer: &mut W, entry: &HistoryEntry) -> io::Result<()> {
+    let seconds = entry.timestamp % SECONDS_PER_DAY;
+    writeln!(
+        writer,
+        "{} {:02}:{:02}:{:02}  {:>6}  {}",
+        entry.date(),
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        entry.results,
+        entry.query
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let entry = HistoryEntry {
+            timestamp: 1_792_238_400,
+            query: String::from("rack=aest?;starts_with=\"q\\\n\u{1}"),
+            dictionary_hash: 0x0123_4567_89ab_cdef,
+            results: 12,
+        };
+
+        let line = entry.to_string();
+        assert_eq!(
+            line,
+            "{\"timestamp\":1792238400,\"query\":\"rack=aest?;starts_with=\\\"q\\\\\\n\\u0001\",\
+             \"dictionary\":\"fnv1a64:0123456789abcdef\",\"results\":12}"
+        );
+        assert_eq!(line.parse(), Ok(entry.clone()));
+        assert_eq!(entry.rack(), Some("aest?"));
+
+        // spacing and unknown fields are accepted
+        let spaced = " { \"results\" : 3 , \"extra\":\"\\u00e9\", \"timestamp\": 0,\
+                      \"query\":\"\", \"dictionary\":\"fnv1a64:ff\" } ";
+        let parsed = spaced.parse::<HistoryEntry>().unwrap();
+        assert_eq!((parsed.results, parsed.dictionary_hash), (3, 255));
+        assert_eq!(parsed.rack(), None);
+
+        assert!("{\"timestamp\":1}".parse::<HistoryEntry>().is_err());
+        assert!(line[..line.len() - 1].parse::<HistoryEntry>().is_err());
+        assert!(format!("{}}}", line).parse::<HistoryEntry>().is_err());
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(Date::from_days(0).to_string(), "1970-01-01");
+        assert_eq!(Date::from_days(11_016).to_string(), "2000-02-29");
+        assert_eq!(Date::from_days(20_743).to_string(), "2026-10-17");
+
+        let date = "2026-10-17".parse::<Date>().unwrap();
+        assert_eq!(date, Date::from_days(20_743));
+        assert!(date < "2026-11-01".parse().unwrap());
+        assert!("2000-02-29".parse::<Date>().is_ok());
+        for invalid in [
+            "1900-02-29",
+            "2026-02-29",
+            "2026-13-01",
+            "2026-10",
+            "26-1-x",
+        ] {
+            assert!(invalid.parse::<Date>().is_err(), "{}", invalid);
+        }
+
+        let entry = HistoryEntry {
+            timestamp: 20_743 * SECONDS_PER_DAY + 3723,
+            query: String::from("rack=a"),
+            dictionary_hash: 0,
+            results: 7,
+        };
+        let mut out = Vec::new();
+        write_entry(&mut out, &entry).unwrap();
+        assert_eq!(out, b"2026-10-17 01:02:03       7  rack=a\n");
+    }
+}
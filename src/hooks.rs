@@ -0,0 +1,125 @@
+//! Hooks, the words made by adding one letter at the front or the back of a
+//! word, as played in Scrabble
+
+use crate::count::{CountError, CountSet};
+use crate::dict::Dictionary;
+use crate::fold::fold_case;
+use crate::index::SortedIndex;
+// `Dictionary::par_iter` is a plain iterator without the feature
+#[cfg(feature = "parallel")]
+use crate::par::*;
+use std::collections::HashMap;
+
+/// The hooks of a word, each list in alphabetical order of the added letter
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Hooks<'a> {
+    /// Words made by adding a letter before the word
+    pub front: Vec<&'a str>,
+    /// Words made by adding a letter after the word
+    pub back: Vec<&'a str>,
+}
+
+/// Finds the hooks of `word`, ignoring case. The word itself doesn't need to
+/// be in the dictionary.
+///
+/// With the sorted index of the dictionary, each of the 52 words that could
+/// be hooks is looked up in it; otherwise the entries one letter longer than
+/// the word are scanned.
+pub fn hooks<'a>(
+    dict: &'a Dictionary,
+    sorted: Option<&SortedIndex>,
+    word: &str,
+) -> Result<Hooks<'a>, CountError> {
+    CountSet::from_word(word)?;
+    let word = word.bytes().map(fold_case).collect::<Vec<_>>();
+
+    // the only words that can be hooks, by their folded spelling, if there's
+    // no sorted index to look them up in
+    let candidates = sorted.is_none().then(|| {
+        let mut candidates = dict
+            .par_iter()
+            .filter(|entry| entry.len() == word.len() + 1)
+            .map(|entry| {
+                (
+                    entry.word.bytes().map(fold_case).collect::<Vec<_>>(),
+                    entry.word,
+                )
+            })
+            .collect::<Vec<_>>();
+        // of the spellings of a word differing by case, the first in byte
+        // order is the hook, whatever order the entries came in
+        candidates.sort_unstable();
+        candidates.dedup_by(|a, b| a.0 == b.0);
+        candidates.into_iter().collect::<HashMap<_, _>>()
+    });
+    let find = |candidate: &[u8]| match (sorted, &candidates) {
+        // the spellings equal to the candidate ignoring case come first of
+        // those starting with it, in byte order too
+        (Some(index), _) => {
+            let candidate = std::str::from_utf8(candidate).ok()?;
+            let &(offset, len) = index.prefix_range(dict, candidate).first()?;
+            (len == candidate.len()).then(|| &dict.word_string()[offset..(offset + len)])
+        }
+        (None, candidates) => candidates.as_ref()?.get(candidate).copied(),
+    };
+
+    let mut hooks = Hooks::default();
+    let mut candidate = Vec::with_capacity(word.len() + 1);
+    for letter in b'a'..=b'z' {
+        candidate.clear();
+        candidate.push(letter);
+        candidate.extend_from_slice(&word);
+        hooks.front.extend(find(&candidate));
+
+        candidate.clear();
+        candidate.extend_from_slice(&word);
+        candidate.push(letter);
+        hooks.back.extend(find(&candidate));
+    }
+
+    Ok(hooks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hooks() {
+        let dict = ["ant", "Pant", "rant", "ants", "anti", "tan", "plant", "an"]
+            .iter()
+            .collect::<Dictionary>();
+
+        let index = SortedIndex::build(&dict);
+
+        // by a scan, then by the sorted index
+        for sorted in [None, Some(&index)] {
+            let found = super::hooks(&dict, sorted, "ANT").unwrap();
+            assert_eq!(found.front, ["Pant", "rant"]);
+            assert_eq!(found.back, ["anti", "ants"]);
+
+            // the word doesn't need to be in the dictionary
+            let found = super::hooks(&dict, sorted, "ran").unwrap();
+            assert!(found.front.is_empty());
+            assert_eq!(found.back, ["rant"]);
+            assert_eq!(super::hooks(&dict, sorted, "plan").unwrap().back, ["plant"]);
+            assert_eq!(
+                super::hooks(&dict, sorted, "xyz").unwrap(),
+                Hooks::default()
+            );
+            assert!(super::hooks(&dict, sorted, "an t").is_err());
+        }
+    }
+
+    #[test]
+    fn case_variants() {
+        // in both insertion orders, as the entries come in hash map order
+        for words in [["pant", "Pant", "PANT"], ["PANT", "Pant", "pant"]] {
+            let dict = words.iter().collect::<Dictionary>();
+            let index = SortedIndex::build(&dict);
+            for sorted in [None, Some(&index)] {
+                assert_eq!(super::hooks(&dict, sorted, "ant").unwrap().front, ["PANT"]);
+            }
+        }
+    }
+}
@@ -0,0 +1,248 @@
+//! Optional index structures for accelerating searches
+
+use crate::count::CountSet;
+use crate::dict::{Dictionary, OffsetLength};
+use crate::fold::{cmp_folded, starts_with_folded};
+use crate::par::*;
+use std::cmp::Ordering;
+
+/// The optional indexes available to a search.
+#[derive(Debug, Default)]
+pub struct Indexes {
+    pub letters: Option<LetterIndex>,
+    pub sorted: Option<SortedIndex>,
+}
+
+/// Per-letter posting lists: for each letter, the sorted list of entries
+/// containing at least one instance of it.
+#[derive(Debug)]
+pub struct LetterIndex {
+    postings: Vec<Vec<OffsetLength>>,
+}
+
+impl LetterIndex {
+    /// Builds the posting lists in one pass over the entries, through the
+    /// presence mask of each.
+    pub fn build(dict: &Dictionary) -> Self {
+        let mut postings = vec![Vec::new(); 26];
+        for (&key, set) in dict.word_count() {
+            let mut mask = set.presence_mask();
+            while mask != 0 {
+                postings[mask.trailing_zeros() as usize].push(key);
+                mask &= mask - 1;
+            }
+        }
+        let postings = postings
+            .into_par_iter()
+            .map(|mut list| {
+                list.sort_unstable();
+                list
+            })
+            .collect();
+
+        Self { postings }
+    }
+
+    /// Returns the posting list of the letter at `index` (0 for A, 25 for Z).
+    #[inline]
+    pub fn postings(&self, index: usize) -> &[OffsetLength] {
+        &self.postings[index]
+    }
+
+    /// Intersects the posting lists of every letter present in `letters`.
+    ///
+    /// Returns `None` if `letters` is empty, in which case the index cannot
+    /// narrow anything down and a full scan should be used instead.
+    pub fn candidates(&self, letters: &CountSet) -> Option<Vec<OffsetLength>> {
+        let mut lists = letters
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| *c > 0)
+            .map(|(i, _)| self.postings(i))
+            .collect::<Vec<_>>();
+
+        // start from the rarest letter so the intersection stays small
+        lists.sort_by_key(|list| list.len());
+        let (first, rest) = lists.split_first()?;
+
+        Some(
+            first
+                .iter()
+                .filter(|key| rest.iter().all(|list| list.binary_search(key).is_ok()))
+                .copied()
+                .collect(),
+        )
+    }
+}
+
+/// Entries sorted by word ignoring case (then by exact spelling, to keep the
+/// order total), for binary searching words and prefixes.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SortedIndex {
+    entries: Vec<OffsetLength>,
+}
+
+impl SortedIndex {
+    pub fn build(dict: &Dictionary) -> Self {
+        let mut entries = dict.word_count().keys().copied().collect::<Vec<_>>();
+        let word = |&(offset, len): &OffsetLength| &dict.word_string()[offset..(offset + len)];
+        entries.par_sort_unstable_by(|a, b| cmp_words(word(a), word(b)));
+
+        Self { entries }
+    }
+
+    /// Creates an index from entries already sorted by word.
+    ///
+    /// Returns `None` if the entries aren't exactly those of the dictionary, or
+    /// aren't sorted.
+    pub fn from_sorted(dict: &Dictionary, entries: Vec<OffsetLength>) -> Option<Self> {
+        let words = entries
+            .iter()
+            .map(|key| dict.get(key).map(|entry| entry.word))
+            .collect::<Option<Vec<_>>>()?;
+        if words.len() != dict.len()
+            || !words
+                .windows(2)
+                .all(|pair| cmp_words(pair[0], pair[1]) == Ordering::Less)
+        {
+            return None;
+        }
+
+        Some(Self { entries })
+    }
+
+    #[inline]
+    pub fn entries(&self) -> &[OffsetLength] {
+        &self.entries
+    }
+
+    /// The entries whose word starts with `prefix`, ignoring case
+    pub fn prefix_range(&self, dict: &Dictionary, prefix: &str) -> &[OffsetLength] {
+        let word = |&(offset, len): &OffsetLength| &dict.word_string()[offset..(offset + len)];
+        let start = self
+            .entries
+            .partition_point(|key| cmp_folded(word(key), prefix) == Ordering::Less);
+        let len =
+            self.entries[start..].partition_point(|key| starts_with_folded(word(key), prefix));
+
+        &self.entries[start..(start + len)]
+    }
+
+    pub fn contains(&self, dict: &Dictionary, word: &str) -> bool {
+        self.entries
+            .binary_search_by(|&(offset, len)| {
+                cmp_words(&dict.word_string()[offset..(offset + len)], word)
+            })
+            .is_ok()
+    }
+}
+
+// the order of the sorted index
+fn cmp_words(a: &str, b: &str) -> Ordering {
+    cmp_folded(a, b).then_with(|| a.cmp(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn candidates() {
+        let mut dict = Dictionary::new();
+        dict.add("quiet").unwrap();
+        dict.add("quote").unwrap();
+        dict.add("queen").unwrap();
+        dict.add("tent").unwrap();
+        let index = LetterIndex::build(&dict);
+
+        let words = |letters| {
+            let mut words = index
+                .candidates(&CountSet::from_word(letters).unwrap())
+                .unwrap()
+                .iter()
+                .map(|key| dict.get(key).unwrap().word)
+                .collect::<Vec<_>>();
+            words.sort_unstable();
+            words
+        };
+
+        assert_eq!(words("q"), ["queen", "quiet", "quote"]);
+        assert_eq!(words("qt"), ["quiet", "quote"]);
+        assert_eq!(words("t"), ["quiet", "quote", "tent"]);
+        assert!(words("z").is_empty());
+        assert!(index
+            .candidates(&CountSet::from_word("").unwrap())
+            .is_none());
+    }
+
+    #[test]
+    fn prefix_range() {
+        let dict = ["stop", "star", "start", "tar", "sta", "st", "su"]
+            .iter()
+            .collect::<Dictionary>();
+        let index = SortedIndex::build(&dict);
+
+        let words = |prefix| {
+            index
+                .prefix_range(&dict, prefix)
+                .iter()
+                .map(|key| dict.get(key).unwrap().word)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(words("sta"), ["sta", "star", "start"]);
+        assert_eq!(words("st"), ["st", "sta", "star", "start", "stop"]);
+        assert_eq!(
+            words(""),
+            ["st", "sta", "star", "start", "stop", "su", "tar"]
+        );
+        assert!(words("x").is_empty());
+
+        assert!(index.contains(&dict, "star"));
+        assert!(!index.contains(&dict, "stars"));
+    }
+
+    #[test]
+    fn from_sorted() {
+        let dict = ["b", "a", "c"].iter().collect::<Dictionary>();
+        let index = SortedIndex::build(&dict);
+
+        let entries = index.entries().to_vec();
+        assert_eq!(
+            SortedIndex::from_sorted(&dict, entries.clone()),
+            Some(index)
+        );
+
+        let mut reversed = entries.clone();
+        reversed.reverse();
+        assert_eq!(SortedIndex::from_sorted(&dict, reversed), None);
+        assert_eq!(SortedIndex::from_sorted(&dict, entries[1..].to_vec()), None);
+    }
+
+    #[test]
+    fn sorted_ignores_case() {
+        let mut dict = Dictionary::new();
+        for word in ["Zoo", "apple", "Apple", "banana", "APRICOT"] {
+            dict.add(word).unwrap();
+        }
+        let index = SortedIndex::build(&dict);
+
+        let words = |keys: &[_]| {
+            keys.iter()
+                .map(|key| dict.get(key).unwrap().word)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            words(index.entries()),
+            ["Apple", "apple", "APRICOT", "banana", "Zoo"]
+        );
+        assert_eq!(
+            words(index.prefix_range(&dict, "ap")),
+            ["Apple", "apple", "APRICOT"]
+        );
+        assert_eq!(words(index.prefix_range(&dict, "APP")), ["Apple", "apple"]);
+        assert!(index.contains(&dict, "Apple"));
+        assert!(!index.contains(&dict, "APPLE"));
+        assert!(SortedIndex::from_sorted(&dict, index.entries().to_vec()).is_some());
+    }
+}
@@ -0,0 +1,163 @@
+//! Regular English inflections, derived from lemmas with spelling heuristics
+//!
+//! Only the regular rules are known, so irregular words get regular (wrong)
+//! forms, such as `childs` or `runned`.
+
+use crate::fold::fold_word;
+
+/// The plural of a noun, or the third person singular of a verb:
+/// `cat → cats`, `bus → buses`, `fly → flies`, `day → days`.
+pub fn plural(word: &str) -> String {
+    let lower = fold_word(word);
+    if ["s", "x", "z", "ch", "sh"]
+        .iter()
+        .any(|ending| lower.ends_with(ending))
+    {
+        format!("{}es", word)
+    } else if ends_with_consonant_y(&lower) {
+        format!("{}ies", &word[..(word.len() - 1)])
+    } else {
+        format!("{}s", word)
+    }
+}
+
+/// The past tense of a verb: `bake → baked`, `try → tried`,
+/// `stop → stopped`, `play → played`.
+pub fn past(word: &str) -> String {
+    let lower = fold_word(word);
+    if lower.ends_with('e') {
+        format!("{}d", word)
+    } else if ends_with_consonant_y(&lower) {
+        format!("{}ied", &word[..(word.len() - 1)])
+    } else if doubles_final_consonant(&lower) {
+        format!("{}{}ed", word, &word[(word.len() - 1)..])
+    } else {
+        format!("{}ed", word)
+    }
+}
+
+/// The present participle of a verb: `bake → baking`, `die → dying`,
+/// `see → seeing`, `run → running`, `try → trying`.
+pub fn present_participle(word: &str) -> String {
+    let lower = fold_word(word);
+    if lower.ends_with("ie") {
+        format!("{}ying", &word[..(word.len() - 2)])
+    } else if ["ee", "ye", "oe"]
+        .iter()
+        .any(|ending| lower.ends_with(ending))
+        || lower == "e"
+    {
+        format!("{}ing", word)
+    } else if lower.ends_with('e') {
+        format!("{}ing", &word[..(word.len() - 1)])
+    } else if doubles_final_consonant(&lower) {
+        format!("{}{}ing", word, &word[(word.len() - 1)..])
+    } else {
+        format!("{}ing", word)
+    }
+}
+
+/// Every regular inflection of a word, or none for an empty word
+pub fn inflections(word: &str) -> Vec<String> {
+    if word.is_empty() {
+        return Vec::new();
+    }
+
+    vec![plural(word), past(word), present_participle(word)]
+}
+
+#[inline]
+fn is_vowel(c: u8) -> bool {
+    matches!(c, b'a' | b'e' | b'i' | b'o' | b'u')
+}
+
+fn ends_with_consonant_y(word: &str) -> bool {
+    match *word.as_bytes() {
+        [.., c, b'y'] => !is_vowel(c),
+        _ => false,
+    }
+}
+
+// one-syllable words ending in consonant-vowel-consonant double the final
+// consonant (`run → running`), but not longer words (`visit → visited`),
+// where it depends on the stress
+fn doubles_final_consonant(word: &str) -> bool {
+    let bytes = word.as_bytes();
+    let syllables = bytes
+        .iter()
+        .zip(std::iter::once(&b'_').chain(bytes.iter()))
+        .filter(|&(&c, &previous)| is_vowel(c) && !is_vowel(previous))
+        .count();
+
+    match *bytes {
+        [.., a, b, c] => {
+            syllables == 1
+                && !is_vowel(a)
+                && is_vowel(b)
+                && !is_vowel(c)
+                && !matches!(c, b'w' | b'x' | b'y')
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plurals() {
+        for (word, expected) in [
+            ("cat", "cats"),
+            ("bus", "buses"),
+            ("box", "boxes"),
+            ("quiz", "quizes"),
+            ("church", "churches"),
+            ("wish", "wishes"),
+            ("fly", "flies"),
+            ("day", "days"),
+            ("Sky", "Skies"),
+        ] {
+            assert_eq!(plural(word), expected);
+        }
+    }
+
+    #[test]
+    fn pasts() {
+        for (word, expected) in [
+            ("bake", "baked"),
+            ("agree", "agreed"),
+            ("try", "tried"),
+            ("play", "played"),
+            ("stop", "stopped"),
+            ("visit", "visited"),
+            ("fix", "fixed"),
+            ("snow", "snowed"),
+            ("rain", "rained"),
+        ] {
+            assert_eq!(past(word), expected);
+        }
+    }
+
+    #[test]
+    fn present_participles() {
+        for (word, expected) in [
+            ("run", "running"),
+            ("bake", "baking"),
+            ("die", "dying"),
+            ("see", "seeing"),
+            ("dye", "dyeing"),
+            ("try", "trying"),
+            ("open", "opening"),
+            ("box", "boxing"),
+        ] {
+            assert_eq!(present_participle(word), expected);
+        }
+    }
+
+    #[test]
+    fn inflections() {
+        assert_eq!(super::inflections("run"), ["runs", "runned", "running"]);
+        assert!(super::inflections("").is_empty());
+    }
+}
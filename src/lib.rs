@@ -0,0 +1,61 @@
+//! A search library for word puzzle games
+//!
+//! The items of the `prelude` are stable: they only change in breaking
+//! releases. Experimental items are only public with the `unstable` feature,
+//! and may change in any release. `tests/data/api.txt` lists the whole public
+//! API, so that changes to it are deliberate.
+
+#[cfg(feature = "archives")]
+pub mod archive;
+pub mod batch;
+#[cfg(feature = "unstable")]
+pub mod browse;
+pub mod builder;
+pub mod capabilities;
+pub mod compare;
+pub mod conflicts;
+pub mod count;
+pub mod dict;
+pub mod echo;
+pub mod export;
+pub mod expr;
+pub mod external;
+pub mod fold;
+pub mod format;
+pub mod glob;
+pub mod hash;
+pub mod history;
+pub mod hooks;
+pub mod index;
+#[cfg(feature = "unstable")]
+pub mod inflect;
+#[cfg(not(feature = "unstable"))]
+mod inflect;
+#[cfg(feature = "profile-memory")]
+pub mod memory;
+mod par;
+pub mod pattern;
+pub mod rack;
+pub mod sample;
+pub mod score;
+pub mod search;
+pub mod shortlist;
+pub mod stem;
+pub mod suggest;
+pub mod template;
+mod trace;
+pub mod used;
+pub mod validate;
+
+/// The stable core of the library: building, reading and searching
+/// dictionaries
+pub mod prelude {
+    pub use crate::builder::{BuildError, BuildOptions, DictionaryBuilder};
+    pub use crate::count::{CountError, CountSet};
+    pub use crate::dict::{Dictionary, DictionaryEntry, OffsetLength};
+    pub use crate::format::{read_dict, write_dict, ReadError};
+    pub use crate::index::{Indexes, LetterIndex, SortedIndex};
+    pub use crate::pattern::Pattern;
+    pub use crate::rack::{Rack, RackOptions};
+    pub use crate::search::{SearchQuery, SearchResults, SortOrder};
+}
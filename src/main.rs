@@ -1,122 +1,2378 @@
-mod count;
-mod dict;
-mod format;
-
-use crate::count::CountSet;
-use crate::dict::Dictionary;
-use crate::format::{read_dict, write_dict};
-use rayon::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::env;
 use std::error::Error;
-use std::fs::{File, OpenOptions};
-use std::io::{BufRead, BufReader};
-use std::path::PathBuf;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use structopt::clap::{self, App, ErrorKind};
 use structopt::StructOpt;
+#[cfg(feature = "archives")]
+use word_puzzle_searcher::archive::{self, ArchivePath};
+use word_puzzle_searcher::batch::{batch_lines, run_ordered, BatchError, BatchLine};
+#[cfg(feature = "tui")]
+use word_puzzle_searcher::browse::{self, Browser};
+use word_puzzle_searcher::builder::{
+    lint, prescan, BuildOptions, DictionaryBuilder, ListFormat, TierSource, TierThresholds,
+};
+use word_puzzle_searcher::capabilities::{self, DEFAULT_DICTIONARY};
+use word_puzzle_searcher::compare::{
+    compare, diff, random_racks, DictionaryDiff, TILE_DISTRIBUTION,
+};
+use word_puzzle_searcher::conflicts::{conflicts, filter_conflicts, Conflict};
+use word_puzzle_searcher::count::{
+    normalize_letters, validate_letters, CountError, CountSet, NormalizeOptions,
+};
+use word_puzzle_searcher::dict::{parse_tier, Dictionary, MAX_TIER, TIER_NAMES};
+use word_puzzle_searcher::echo::{DictionaryEcho, QueryEcho};
+use word_puzzle_searcher::export::{export, ExportFormat};
+use word_puzzle_searcher::expr::ScoreExpr;
+use word_puzzle_searcher::external::ExternalSorter;
+use word_puzzle_searcher::fold::fold_word;
+use word_puzzle_searcher::format::{
+    explain_dict, file_fnv_hash, file_hash, peek_header, read_dict_report, read_sorted_index,
+    write_dict, write_dict_deterministic, write_sorted_index, ReadError, ReadReport,
+};
+use word_puzzle_searcher::glob;
+use word_puzzle_searcher::hash::to_hex;
+use word_puzzle_searcher::history::{write_entry, Date, HistoryEntry, NO_HISTORY_VAR};
+use word_puzzle_searcher::hooks::hooks;
+use word_puzzle_searcher::index::{Indexes, LetterIndex, SortedIndex};
+#[cfg(feature = "profile-memory")]
+use word_puzzle_searcher::memory::{self, TrackingAllocator};
+use word_puzzle_searcher::pattern::Pattern;
+use word_puzzle_searcher::rack::{Rack, RackOptions};
+use word_puzzle_searcher::score::{self, rack_score, Scorer, ScrabbleScorer, SCORERS};
+use word_puzzle_searcher::search::{
+    OptionOrigin, SearchDefaults, SearchQuery, SearchResults, SortOrder,
+};
+use word_puzzle_searcher::shortlist::{
+    shortlist, write_shortlist, ShortlistFormat, ShortlistOptions,
+};
+use word_puzzle_searcher::suggest::closest;
+use word_puzzle_searcher::template::{Placeholder, Template};
+use word_puzzle_searcher::used::UsedWords;
+use word_puzzle_searcher::validate::{validate, ReportFormat, Severity};
 
+#[cfg(feature = "profile-memory")]
+#[global_allocator]
+static ALLOCATOR: TrackingAllocator = TrackingAllocator;
+
+// prints the peak heap memory when dropped, so at the end of `main` whichever
+// way it returns
+#[cfg(feature = "profile-memory")]
+struct PeakReport;
+
+#[cfg(feature = "profile-memory")]
+impl Drop for PeakReport {
+    fn drop(&mut self) {
+        let peak = memory::peak_bytes();
+        eprintln!(
+            "Peak heap memory: {} bytes ({:.1} MiB)",
+            peak,
+            peak as f64 / (1 << 20) as f64
+        );
+    }
+}
+
+// searches matching fewer words are sorted in memory even with
+// `--external-sort`, and runs of external sorts have this many words
+const EXTERNAL_SORT_MIN_RESULTS: usize = 1_000_000;
+const EXTERNAL_SORT_RUN_LEN: usize = 1_000_000;
+
+// number of added and removed words listed by `generate --diff-against`
+// without `--diff-full`
+const DIFF_EXAMPLES: usize = 10;
+
+// exit code of `search --batch` when some racks failed, but not all
+const BATCH_PARTIAL_FAILURE_CODE: i32 = 2;
+
+// exit code of `validate` when a finding reaches --fail-on
+const VALIDATE_FAILURE_CODE: i32 = 2;
+
+/// Order of `search --sort`: one of `SortOrder`, or by score
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SearchSort {
+    Order(SortOrder),
+    Score,
+}
+
+impl FromStr for SearchSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "score" => Ok(SearchSort::Score),
+            _ => s.parse().map(SearchSort::Order).map_err(|_| {
+                format!(
+                    "unknown sort order {:?} (expected alpha, consumed or score)",
+                    s
+                )
+            }),
+        }
+    }
+}
+
+// Every subcommand writes only its data (words, reports, dictionary files
+// written to `-`) to the standard output, and everything else, such as
+// progress messages and warnings, to the standard error.
 #[derive(StructOpt, Debug)]
 #[structopt(
     name = "Word Puzzle Searcher",
     about = "A search program for word puzzle games"
 )]
+struct Args {
+    /// Fails on dictionary files with data after the end of the dictionary,
+    /// instead of warning about it
+    #[structopt(long, global = true)]
+    strict_format: bool,
+
+    /// Guarantees the same output, byte for byte, for the same inputs and
+    /// options whatever the number of threads: dictionary files get ordered
+    /// entry tables (text output is always sorted with fixed tie-breaks),
+    /// and the commands depending on chance or interaction refuse it (`pick`
+    /// without --seed, `search --tui`)
+    #[structopt(long, global = true)]
+    deterministic: bool,
+
+    /// Hides informational messages, such as progress and summaries, keeping
+    /// warnings, errors and requested reports
+    #[structopt(short, long, global = true)]
+    quiet: bool,
+
+    /// Prints more informational messages, such as the room `generate`
+    /// reserved for the words against what they took
+    #[structopt(short, long, global = true, conflicts_with = "quiet")]
+    verbose: bool,
+
+    /// Prints the peak heap memory allocated by the command on stderr, once
+    /// done; needs a build with the `profile-memory` feature
+    #[structopt(long, global = true)]
+    profile_memory: bool,
+
+    /// Fails unless the dictionary file is given with --dictionary, instead
+    /// of reading `default.dict` from the current directory
+    #[structopt(long, global = true)]
+    no_default_dictionary: bool,
+
+    #[structopt(subcommand)]
+    command: Opt,
+}
+
+// parsed once, so the size of the search options doesn't matter
+#[allow(clippy::large_enum_variant)]
+#[derive(StructOpt, Debug)]
 enum Opt {
     /// Generates a dictionary file
     Generate {
-        /// Output file
-        #[structopt(short, long, parse(from_os_str), default_value = "default.dict")]
+        /// Output file, or `-` for the standard output
+        #[structopt(short, long, parse(from_os_str), default_value = DEFAULT_DICTIONARY)]
         output: PathBuf,
 
         /// File containing a list of words separated in lines
-        #[structopt(name = "FILE", parse(from_os_str))]
-        file: PathBuf,
+        #[structopt(name = "FILE", parse(from_os_str), required_unless = "source")]
+        file: Option<PathBuf>,
+
+        /// Word list tagged with a source name, as `name:path`; can be repeated
+        /// and used to filter searches with `--from-source`
+        #[structopt(long, number_of_values = 1)]
+        source: Vec<String>,
+
+        /// Reports words that differ only by case
+        #[structopt(long)]
+        case_report: bool,
+
+        /// Converts words to lowercase, merging words that differ only by
+        /// case; only ASCII letters have a case, whatever the locale
+        #[structopt(long)]
+        fold_case: bool,
+
+        /// Trims whitespace around each word
+        #[structopt(long)]
+        trim: bool,
+
+        /// Skips invalid words instead of failing
+        #[structopt(long)]
+        skip_invalid: bool,
+
+        /// Keeps blank lines, which are skipped by default
+        #[structopt(long)]
+        keep_blank: bool,
+
+        /// Also writes a sorted index next to the dictionary file, speeding up
+        /// `--starts-with` searches
+        #[structopt(long)]
+        sorted_index: bool,
+
+        /// Minimum word length used by searches of this dictionary that don't
+        /// set `--min-length`
+        #[structopt(long)]
+        default_min_length: Option<usize>,
+
+        /// Adds the regular plurals, past tenses and -ing forms of every word,
+        /// guessed with spelling rules and tagged as derived
+        #[structopt(long)]
+        expand_inflections: bool,
+
+        /// Reads digits as the letters they look like, so that `h3llo` is
+        /// added as `hello`: 0 o, 1 i, 2 z, 3 e, 4 a, 5 s, 6 g, 7 t, 8 b, 9 g
+        #[structopt(long)]
+        leet: bool,
+
+        /// With `--leet`, keeps the words spelled with digits in results,
+        /// still made from the rack as the letters they stand for. Search's
+        /// --starts-with and --pattern test the spelling kept: `h3llo`
+        /// matches `h_llo`, but neither `he*` nor --starts-with he
+        #[structopt(long, requires = "leet")]
+        keep_leet_spelling: bool,
+
+        /// Reads a difficulty tier for each word from a second column, after
+        /// a space, tab or comma: 0 (easy) to 3, or common, intermediate, rare
+        /// or obscure; words without one are in 3
+        #[structopt(long, conflicts_with = "tier-thresholds")]
+        tier_column: bool,
+
+        /// Reads a frequency for each word from a second column instead, and
+        /// turns it into a tier with up to 3 decreasing thresholds, such as
+        /// `1e-5,1e-7` for tier 0 from 1e-5, tier 1 from 1e-7, and tier 2 below
+        #[structopt(long)]
+        tier_thresholds: Option<TierThresholds>,
+
+        /// Word list format: words, one per line, or anagram-keys, as written
+        /// by `export --format anagram-keys`
+        #[structopt(long, default_value = "words")]
+        format: ListFormat,
+
+        /// Prints the number of words added and removed since this older
+        /// dictionary file, with a few of each, on stderr
+        #[structopt(long, parse(from_os_str))]
+        diff_against: Option<PathBuf>,
+
+        /// With `--diff-against`, prints every added and removed word
+        #[structopt(long, requires = "diff-against")]
+        diff_full: bool,
+
+        /// With `--diff-against`, also writes every added and removed word to
+        /// this file, as the `diff` subcommand prints them
+        #[structopt(long, parse(from_os_str), requires = "diff-against")]
+        diff_output: Option<PathBuf>,
+
+        /// Does nothing if the output file (and sorted index, with
+        /// `--sorted-index`) was modified after every word list, like make;
+        /// only modification times are compared, not the options
+        #[structopt(long)]
+        if_newer: bool,
+
+        /// With `--if-newer`, generates the dictionary even if it's up to date
+        #[structopt(long, requires = "if-newer")]
+        force: bool,
     },
     /// Searches for words given a list of letters
     Search {
-        /// Dictionary file
-        #[structopt(short, long, parse(from_os_str), default_value = "default.dict")]
+        /// Dictionary file, or a glob pattern of several (`'dicts/*.dict'`)
+        /// searched together, each of their words having the stem of its
+        /// file as source (`en` for `dicts/en.dict`). The words keep their
+        /// tiers, and the files' default minimum length applies if they all
+        /// have the same
+        #[structopt(short, long, parse(from_os_str), default_value = DEFAULT_DICTIONARY)]
         dictionary: PathBuf,
 
+        /// Fails if a dictionary file matching the `--dictionary` pattern
+        /// can't be read, instead of skipping it with a warning
+        #[structopt(long)]
+        strict: bool,
+
         /// Available letters in the word puzzle
-        letters: String,
+        #[structopt(required_unless_one = &["counts", "batch"])]
+        letters: Option<String>,
 
-        /// Minimum length of the words to be searched
-        #[structopt(short, long, default_value = "3")]
-        min_length: usize,
+        /// Available letters as counts instead, such as `e2,a1,r1,t1`, for
+        /// racks too large to type out; no blanks
+        #[structopt(long, conflicts_with = "letters")]
+        counts: Option<CountSet>,
+
+        /// Lets words use each letter of the rack any number of times, so that
+        /// the rack only gives the letters available; by default a word uses
+        /// each letter at most as many times as the rack has it
+        #[structopt(long, conflicts_with = "show-remaining")]
+        unlimited_repeats: bool,
+
+        /// Minimum length of the words to be searched [default: the
+        /// dictionary's default, else 3]; the 1 and 2 letter words that
+        /// games such as Scrabble allow need `--min-length 1` or 2
+        #[structopt(short, long)]
+        min_length: Option<usize>,
 
-        /// Minimum length of the words to be searched
+        /// Lists the words of any length, from 1 letter, as `--min-length 1`
+        #[structopt(long, conflicts_with = "min-length")]
+        allow_short: bool,
+
+        /// Maximum length of the words to be searched
         #[structopt(short = "M", long)]
         max_length: Option<usize>,
 
         /// Separator for the list of words
         #[structopt(short, long, default_value = "\n")]
         separator: String,
+
+        /// Ends every word with a NUL byte instead of the separator, for
+        /// `xargs -0`
+        #[structopt(
+            long,
+            conflicts_with_all = &["separator", "group-by-length", "fallback", "tui"]
+        )]
+        print0: bool,
+
+        /// Letters that every word must contain
+        #[structopt(short, long, default_value = "")]
+        require: String,
+
+        /// Text that every word must start with
+        #[structopt(long, default_value = "")]
+        starts_with: String,
+
+        /// Builds per-letter posting lists to speed up `--require` searches
+        #[structopt(long)]
+        letter_index: bool,
+
+        /// Prints statistics about how the search was performed
+        #[structopt(long)]
+        stats: bool,
+
+        /// Checks that every stored letter count matches its word on load
+        #[structopt(long)]
+        verify_counts: bool,
+
+        /// Reports letters of the rack that don't appear in any result
+        #[structopt(long)]
+        report_unusable: bool,
+
+        /// Groups the words by length, under a header for each length
+        #[structopt(long)]
+        group_by_length: bool,
+
+        /// Lists the anagrams together instead, a line for each group with
+        /// its sorted letters, its size and its words, in the order of their
+        /// first word: `aelpt (4): leapt, petal, plate, pleat`
+        #[structopt(
+            long,
+            conflicts_with_all = &[
+                "group-by-length", "separator", "print0", "template", "tui", "longest",
+                "histogram", "show-remaining", "show-multiplicity", "collapse-variants",
+                "external-sort", "batch"
+            ]
+        )]
+        anagram_summary: bool,
+
+        /// Minimum Scrabble score per letter of the words (blanks score 0)
+        #[structopt(long)]
+        min_efficiency: Option<f64>,
+
+        /// Lists the words that can NOT be made from the letters instead;
+        /// requires `--limit` or another filter such as `--max-length`
+        #[structopt(long)]
+        invert: bool,
+
+        /// Maximum number of words to list
+        #[structopt(long)]
+        limit: Option<usize>,
+
+        /// Number of words to skip before listing, to page through the results
+        /// with `--limit`; pages follow the `--sort` order, which is the same
+        /// from one search to the next
+        #[structopt(long, default_value = "0")]
+        offset: usize,
+
+        /// Only lists words from the word list with this source name
+        #[structopt(long)]
+        from_source: Option<String>,
+
+        /// Order of the words: alpha, in byte order whatever the locale
+        /// (`Zulu` before `ant`), or consumed for the words using the most
+        /// letters of the rack (not counting blanks) first, or score for the
+        /// highest scores first (see `--scorer` and `--score-expr`), then
+        /// alphabetically
+        #[structopt(long, default_value = "alpha")]
+        sort: SearchSort,
+
+        /// How `--sort score` scores words: scrabble for their Scrabble score
+        /// from the rack (blanks score 0), or rarity for words of letters with
+        /// few tiles in a Scrabble set first [default: scrabble]
+        #[structopt(long)]
+        scorer: Option<String>,
+
+        /// Scores words for `--sort score` with an expression, like
+        /// `'len*2 + count(q)*10'`: integers, + - * / % and parentheses on
+        /// len, score, rarity, unique (different letters), blanks (needed
+        /// from the rack) and count(letter). Arithmetic saturates and division
+        /// by 0 gives 0
+        #[structopt(long, conflicts_with = "scorer")]
+        score_expr: Option<ScoreExpr>,
+
+        /// Lists the words with the most of these letters first (each
+        /// occurrence counts), then alphabetically, instead of `--sort`
+        #[structopt(long, conflicts_with_all = &["sort", "tui", "longest"])]
+        prioritize: Option<String>,
+
+        /// Caps each letter of the rack at its number of tiles in the bag,
+        /// given as `a:9, b:2, ...` [default: a standard Scrabble bag]
+        #[structopt(long, name = "BAG")]
+        bag_limits: Option<Option<String>>,
+
+        /// Shows the letters of the rack each word leaves unused, blanks aside
+        #[structopt(long)]
+        show_remaining: bool,
+
+        /// Prints the number of words of each length as a bar chart instead
+        /// of listing them
+        #[structopt(long)]
+        histogram: bool,
+
+        /// Pattern that every word must match: lowercase letters stand for
+        /// themselves, `_` for any letter, `*` for any letters, and uppercase
+        /// letters for the same unknown letter everywhere they appear
+        /// (`*XX*` finds double letters)
+        #[structopt(long)]
+        pattern: Option<Pattern>,
+
+        /// Leaves out the words added by `generate --expand-inflections`
+        #[structopt(long)]
+        exclude_derived: bool,
+
+        /// Highest difficulty tier of the words, from 0 (easy) to 3 or by name
+        /// (common, intermediate, rare, obscure), for dictionaries generated
+        /// with tiers
+        #[structopt(long, parse(try_from_str = parse_tier))]
+        max_tier: Option<u8>,
+
+        /// Difficulty tier of the words, as for --max-tier, to only find the
+        /// words of one tier
+        #[structopt(long, parse(try_from_str = parse_tier), conflicts_with = "max-tier")]
+        tier: Option<u8>,
+
+        /// Maximum number of different letters in the words
+        #[structopt(long)]
+        max_unique: Option<usize>,
+
+        /// Minimum number of tiles of the rack the words use, blanks
+        /// included, for plays rewarding longer words
+        #[structopt(long)]
+        min_use: Option<usize>,
+
+        /// Only lists the words that can be made this many times over from
+        /// the rack at once, blanks aside: `--multiplicity 2` needs two of
+        /// each of their letters for every one they have
+        #[structopt(long)]
+        multiplicity: Option<u8>,
+
+        /// Shows how many times over the rack makes each word, blanks aside
+        #[structopt(long, conflicts_with = "show-remaining")]
+        show_multiplicity: bool,
+
+        /// Lists a single word of the variants sharing a stem, such as `walk,
+        /// walks, walked, walking`: the shortest, with the number of variants
+        /// left out (`walk [+3]`). The stems come from regular suffixes only
+        #[structopt(
+            long,
+            conflicts_with_all = &["show-remaining", "show-multiplicity", "tui"]
+        )]
+        collapse_variants: bool,
+
+        /// Lists the variants left out by --collapse-variants after each word
+        /// instead of their number (`walk [walks walked walking]`)
+        #[structopt(long, requires = "collapse-variants")]
+        show_variants: bool,
+
+        /// Writes each word as this template instead, with the placeholders
+        /// <word>, <length>, <score>, <remaining>, <sources> and <tier>, and
+        /// the escapes \t, \n, \\, \< and \>: `'<word>\t<score>'`. The
+        /// separator still follows each word
+        #[structopt(
+            long,
+            conflicts_with_all = &[
+                "show-remaining", "show-multiplicity", "collapse-variants", "tui", "longest",
+                "histogram", "external-sort", "batch"
+            ]
+        )]
+        template: Option<Template>,
+
+        /// Browses the results interactively: type to narrow them down, Tab
+        /// to change the order, Enter to print the selected word. The results
+        /// are printed as usual when not in a terminal (requires the `tui`
+        /// feature)
+        #[structopt(long)]
+        tui: bool,
+
+        /// Prints only the longest word (the first alphabetically of the
+        /// longest ones), without collecting or sorting the other matches
+        #[structopt(long, conflicts_with_all = &["tui", "group-by-length", "offset"])]
+        longest: bool,
+
+        /// Fallback dictionary file, searched with the same query: the words
+        /// it finds that the dictionary doesn't are listed after a
+        /// `=== fallback ===` line
+        #[structopt(
+            long,
+            parse(from_os_str),
+            conflicts_with_all = &["tui", "longest", "histogram", "from-source", "max-tier", "tier"]
+        )]
+        fallback: Option<PathBuf>,
+
+        /// Only reports the number of words found in the fallback dictionary,
+        /// on stderr, instead of listing them
+        #[structopt(long, requires = "fallback")]
+        no_fallback_results: bool,
+
+        /// File listing the words already played (see the `used`
+        /// subcommand), which are left out of the results
+        #[structopt(long, parse(from_os_str), conflicts_with_all = &["longest", "histogram"])]
+        used_file: Option<PathBuf>,
+
+        /// Dictionary file whose words are left out of the results, such as
+        /// the words already learned, ignoring case
+        #[structopt(long, parse(from_os_str), conflicts_with_all = &["longest", "histogram"])]
+        exclude_dictionary: Option<PathBuf>,
+
+        /// Sorts the words in runs written to temporary files, bounding
+        /// memory, if the search matches at least this many words (a million
+        /// if no number is given, `--external-sort=N` otherwise, so that the
+        /// rack after it isn't taken for one); only lists words
+        /// alphabetically
+        #[structopt(
+            long,
+            require_equals = true,
+            conflicts_with_all = &[
+                "tui", "longest", "group-by-length", "fallback", "prioritize",
+                "show-remaining", "show-multiplicity", "collapse-variants", "histogram",
+                "report-unusable"
+            ]
+        )]
+        external_sort: Option<Option<usize>>,
+
+        /// Appends the search to this log, a line of JSON with the time, the
+        /// query, the dictionary hash and the number of results, unless the
+        /// WPS_NO_HISTORY environment variable is set
+        #[structopt(
+            long,
+            parse(from_os_str),
+            conflicts_with_all = &["histogram", "longest"]
+        )]
+        history: Option<PathBuf>,
+
+        /// Searches even if the filters contradict each other, such as a
+        /// prefix longer than `--max-length`, only warning about it
+        #[structopt(long)]
+        force: bool,
+
+        /// Searches each rack of this file instead, one per line, skipping
+        /// blank lines: the words of each rack follow a `=== rack ===` line,
+        /// in the order of the file. The racks that can't be searched are
+        /// reported on stderr as lines of JSON with their line number, and
+        /// the others are still searched; the exit code is then 2, unless
+        /// every rack failed
+        #[structopt(
+            long,
+            parse(from_os_str),
+            conflicts_with_all = &[
+                "letters", "counts", "tui", "longest", "histogram", "fallback", "external-sort",
+                "history", "show-remaining", "show-multiplicity", "collapse-variants",
+                "group-by-length", "report-unusable", "print0", "stats"
+            ]
+        )]
+        batch: Option<PathBuf>,
+    },
+    /// Prints information about a dictionary file
+    Info {
+        /// Dictionary file
+        #[structopt(short, long, parse(from_os_str), default_value = DEFAULT_DICTIONARY)]
+        dictionary: PathBuf,
+
+        /// Prints an annotated hex dump of every field of the file instead,
+        /// for debugging the format
+        #[structopt(long)]
+        explain_format: bool,
+    },
+    /// Checks a dictionary file for corruption, reporting every problem found
+    /// with a stable rule id, such as countset_mismatch or duplicate_word
+    Validate {
+        /// Dictionary file
+        #[structopt(short, long, parse(from_os_str), default_value = DEFAULT_DICTIONARY)]
+        dictionary: PathBuf,
+
+        /// Report format: text, a line per finding and then a count of errors
+        /// and warnings, or json, a single object with the findings and the
+        /// number of findings of every rule
+        #[structopt(long, default_value = "text")]
+        format: ReportFormat,
+
+        /// Exits with code 2 if there's a finding of this severity or worse:
+        /// warning or error
+        #[structopt(long, default_value = "error")]
+        fail_on: Severity,
+    },
+    /// Compares how many words two dictionaries find over a set of racks
+    Compare {
+        /// First dictionary file
+        #[structopt(parse(from_os_str))]
+        first: PathBuf,
+
+        /// Second dictionary file
+        #[structopt(parse(from_os_str))]
+        second: PathBuf,
+
+        /// File containing racks separated in lines, instead of random racks
+        #[structopt(short, long, parse(from_os_str))]
+        racks: Option<PathBuf>,
+
+        /// Number of random racks
+        #[structopt(short, long, default_value = "100")]
+        count: usize,
+
+        /// Number of letters in each random rack
+        #[structopt(long, default_value = "7")]
+        rack_size: usize,
+
+        /// Seed for generating the random racks
+        #[structopt(long, default_value = "0")]
+        seed: u64,
+
+        /// Minimum length of the words to be searched, at least 1
+        #[structopt(short, long, default_value = "3")]
+        min_length: usize,
+
+        /// Writes the number of words of each rack to a CSV file, after
+        /// `#` comment lines echoing the query, dictionaries and seed
+        #[structopt(long, parse(from_os_str))]
+        csv: Option<PathBuf>,
+    },
+    /// Lists the words added and removed between two versions of a
+    /// dictionary file
+    Diff {
+        /// Old dictionary file
+        #[structopt(parse(from_os_str))]
+        old: PathBuf,
+
+        /// New dictionary file
+        #[structopt(parse(from_os_str))]
+        new: PathBuf,
+    },
+    /// Exports the words of a dictionary file
+    Export {
+        /// Dictionary file
+        #[structopt(short, long, parse(from_os_str), default_value = DEFAULT_DICTIONARY)]
+        dictionary: PathBuf,
+
+        /// Output format: words, csv-counts for each word's letter counts, or
+        /// anagram-keys for a line per class of anagrams
+        #[structopt(short, long, default_value = "words")]
+        format: ExportFormat,
+    },
+    /// Picks a random word of the given length, to build a puzzle around
+    Pick {
+        /// Dictionary file
+        #[structopt(short, long, parse(from_os_str), default_value = DEFAULT_DICTIONARY)]
+        dictionary: PathBuf,
+
+        /// Length of the word
+        #[structopt(short, long)]
+        length: usize,
+
+        /// Seed for picking the word, to pick the same word again from the
+        /// same dictionary; random if not given
+        #[structopt(long)]
+        seed: Option<u64>,
+    },
+    /// Lists the hooks of a word: the words made by adding a letter at its
+    /// front or back
+    Hooks {
+        /// Dictionary file
+        #[structopt(short, long, parse(from_os_str), default_value = DEFAULT_DICTIONARY)]
+        dictionary: PathBuf,
+
+        /// Word to hook, which doesn't need to be in the dictionary
+        #[structopt(name = "WORD")]
+        word: String,
+    },
+    /// Lists the two- and three-letter words of a dictionary, grouped by
+    /// starting letter, for study
+    Shortlist {
+        /// Dictionary file
+        #[structopt(short, long, parse(from_os_str), default_value = DEFAULT_DICTIONARY)]
+        dictionary: PathBuf,
+
+        /// Only lists the words containing this letter
+        #[structopt(long)]
+        containing: Option<char>,
+
+        /// Only lists the words missing from this older dictionary file
+        #[structopt(long, parse(from_os_str))]
+        new_since: Option<PathBuf>,
+
+        /// Output format: text, or csv for flashcards
+        #[structopt(short, long, default_value = "text")]
+        format: ShortlistFormat,
+    },
+    /// Checks a word list for duplicates and invalid words, without
+    /// generating a dictionary
+    Lint {
+        /// File containing a list of words separated in lines
+        #[structopt(name = "FILE", parse(from_os_str))]
+        file: PathBuf,
+    },
+    /// Adds words to a list of words already played, left out of searches
+    /// with `search --used-file`, or lists them if no word is given
+    Used {
+        /// File listing the words, one per line, created if needed
+        #[structopt(name = "FILE", parse(from_os_str))]
+        file: PathBuf,
+
+        /// Words to add
+        #[structopt(name = "WORD")]
+        words: Vec<String>,
+
+        /// Empties the list
+        #[structopt(long, conflicts_with = "WORD")]
+        clear: bool,
+    },
+    /// Lists the searches logged with `search --history`
+    History {
+        /// Log file
+        #[structopt(name = "FILE", parse(from_os_str))]
+        file: PathBuf,
+
+        /// Only lists the searches made on or after this day, as YYYY-MM-DD
+        /// in UTC
+        #[structopt(long)]
+        since: Option<Date>,
+
+        /// Only lists the searches made on or before this day
+        #[structopt(long)]
+        until: Option<Date>,
+
+        /// Only lists the searches of this rack, whatever the order of its
+        /// letters
+        #[structopt(long)]
+        rack: Option<String>,
+
+        /// Prints the lines of the log as they are, rather than as a table
+        #[structopt(long)]
+        raw: bool,
     },
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let opt = Opt::from_args();
+    let long_version = capabilities::long_version();
+    let (
+        Args {
+            strict_format,
+            deterministic,
+            quiet,
+            verbose,
+            profile_memory,
+            no_default_dictionary,
+            command,
+        },
+        default_dictionary,
+    ) = {
+        let app = Args::clap().long_version(long_version.as_str());
+        match app.clone().get_matches_safe() {
+            Ok(matches) => {
+                // whether the subcommand reads `default.dict` for lack of a
+                // --dictionary
+                let default_dictionary = matches.subcommand().1.is_some_and(|matches| {
+                    matches.is_present("dictionary") && matches.occurrences_of("dictionary") == 0
+                });
+                (Args::from_clap(&matches), default_dictionary)
+            }
+            Err(err) => exit_with_suggestion(&app, err),
+        }
+    };
+    if default_dictionary {
+        if no_default_dictionary {
+            return Err("no dictionary file given: pass one with --dictionary \
+                (--no-default-dictionary leaves out default.dict)"
+                .into());
+        }
+        if !Path::new(DEFAULT_DICTIONARY).exists() {
+            return Err(format!(
+                "no dictionary file given, and there's no {} in the current directory: \
+                 pass one with --dictionary, or generate {0} with `generate`",
+                DEFAULT_DICTIONARY
+            )
+            .into());
+        }
+    }
+    #[cfg(feature = "profile-memory")]
+    // not `then_some`, which would build and drop a report either way
+    let _peak_report = profile_memory.then(|| PeakReport);
+    #[cfg(not(feature = "profile-memory"))]
+    if profile_memory {
+        return Err(
+            "--profile-memory is unavailable: this build has no `profile-memory` feature".into(),
+        );
+    }
+    let load_dictionary = |path: &Path| load_dictionary(path, strict_format).map(|(dict, _)| dict);
+    // informational messages, as opposed to warnings and requested reports
+    macro_rules! info {
+        ($($arg:tt)*) => {
+            if !quiet {
+                eprintln!($($arg)*);
+            }
+        };
+    }
+    let write_dict = |dict: &Dictionary, writer: &mut dyn Write| {
+        if deterministic {
+            write_dict_deterministic(dict, writer)
+        } else {
+            write_dict(dict, writer)
+        }
+    };
 
-    match opt {
-        Opt::Generate { output, file } => {
-            println!(
-                "Generating a dictionary file ({:?}) from {:?}...",
-                output, file
+    match command {
+        Opt::Generate {
+            output,
+            file,
+            source,
+            case_report,
+            fold_case,
+            trim,
+            skip_invalid,
+            keep_blank,
+            sorted_index,
+            default_min_length,
+            expand_inflections,
+            leet,
+            keep_leet_spelling,
+            tier_column,
+            tier_thresholds,
+            format,
+            diff_against,
+            diff_full,
+            diff_output,
+            if_newer,
+            force,
+        } => {
+            let sources = source
+                .iter()
+                .map(|source| match source.split_once(':') {
+                    Some((name, path)) if !name.is_empty() => Ok((name, PathBuf::from(path))),
+                    _ => Err(format!("invalid source {:?}, expected name:path", source)),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            // a default value counts as given for `conflicts_with`
+            if format == ListFormat::AnagramKeys && (tier_column || tier_thresholds.is_some()) {
+                return Err("anagram-keys word lists have no tier column".into());
+            }
+            if default_min_length == Some(0) {
+                return Err("--default-min-length must be at least 1: words have letters".into());
+            }
+            if if_newer && !force {
+                if output == Path::new("-") {
+                    return Err("--if-newer needs an output file".into());
+                }
+                let inputs = file
+                    .iter()
+                    .chain(sources.iter().map(|(_, path)| path))
+                    .map(PathBuf::as_path)
+                    .collect::<Vec<_>>();
+                let index = sorted_index.then(|| sorted_index_path(&output));
+                if up_to_date(&output, &inputs)?
+                    && index
+                        .as_ref()
+                        .map_or(Ok(true), |index| up_to_date(index, &inputs))?
+                {
+                    info!(
+                        "{:?} is up to date, not generating it (regenerate with --force)",
+                        output
+                    );
+                    return Ok(());
+                }
+            }
+
+            let mut builder = DictionaryBuilder::new(BuildOptions {
+                trim,
+                fold_case,
+                skip_invalid,
+                case_report,
+                keep_blank,
+                expand_inflections,
+                leet,
+                keep_leet_spelling,
+                tiers: match tier_thresholds {
+                    Some(thresholds) => Some(TierSource::Frequency(thresholds)),
+                    None => tier_column.then_some(TierSource::Column),
+                },
+                format,
+            });
+            // counting the words first spares the dictionary from growing by
+            // steps, but only files can be read twice
+            let reserve = |builder: &mut DictionaryBuilder,
+                           path: &Path|
+             -> Result<(), Box<dyn Error>> {
+                let prescanned = fs::metadata(path).and_then(|metadata| match metadata.is_file() {
+                    true => prescan(File::open(path)?).map(Some),
+                    false => Ok(None),
+                });
+                if let Some(size) =
+                    prescanned.map_err(|err| format!("{}: {}", path.display(), err))?
+                {
+                    builder.reserve(size.lines, size.bytes);
+                }
+                Ok(())
+            };
+            if let Some(file) = file {
+                info!(
+                    "Generating a dictionary file ({:?}) from {:?}...",
+                    output, file
+                );
+                reserve(&mut builder, &file)?;
+                builder.read_from(BufReader::new(File::open(&file)?))?;
+            }
+            for (name, path) in sources {
+                info!(
+                    "Generating a dictionary file ({:?}) from {:?} as source {:?}...",
+                    output, path, name
+                );
+                builder.set_source(name)?;
+                reserve(&mut builder, &path)?;
+                builder.read_from(BufReader::new(File::open(&path)?))?;
+            }
+            let (mut dict, report) = builder.finish();
+            dict.set_search_defaults(SearchDefaults {
+                min_length: default_min_length,
+            });
+            // read before writing, since it's often the file being replaced
+            let old_dict = match &diff_against {
+                Some(path) => Some(load_dictionary(path)?),
+                None => None,
+            };
+
+            for (line, word, err) in report.skipped.iter() {
+                eprintln!("Skipped {:?} (line {}): {}", word, line, err);
+            }
+
+            if report.blank > 0 {
+                info!("Skipped {} blank lines", report.blank);
+            }
+
+            if case_report {
+                for spellings in report.case_duplicates.iter() {
+                    eprintln!("words differing only by case: {}", spellings.join(", "));
+                }
+                eprintln!(
+                    "{} words have case duplicates{}",
+                    report.case_duplicates.len(),
+                    if fold_case { " (merged)" } else { "" }
+                );
+            }
+
+            info!(
+                "Added {} words from {} lines ({} duplicates, {} skipped)",
+                report.added,
+                report.lines,
+                report.duplicates,
+                report.skipped.len()
             );
-            let file = File::open(&file)?;
-            let file = BufReader::new(file);
-            let mut dict = Dictionary::new();
+            if expand_inflections {
+                info!("Derived {} inflected words", report.derived);
+            }
+            if dict.has_tiers() {
+                info!("Words per tier: {:?}", report.tiers);
+            }
+            if verbose {
+                eprintln!(
+                    "Reserved room for {} words of {} bytes, used by {} words of {} bytes",
+                    report.reserved.0,
+                    report.reserved.1,
+                    dict.len(),
+                    dict.word_string().len()
+                );
+            }
 
-            for line in file.lines() {
-                let line = line?;
-                dict.add(&line)?;
+            // the diff is against the dictionary as it's about to be written
+            if let Some(old_dict) = &old_dict {
+                let diff = diff(old_dict, &dict);
+                let examples = if diff_full { None } else { Some(DIFF_EXAMPLES) };
+                write_diff(&mut io::stderr().lock(), &diff, examples)?;
+                if let Some(path) = &diff_output {
+                    let mut file = BufWriter::new(File::create(path)?);
+                    write_diff(&mut file, &diff, None)?;
+                    file.flush()?;
+                }
+            }
+
+            if output == Path::new("-") {
+                if sorted_index {
+                    return Err("--sorted-index needs an output file".into());
+                }
+
+                let stdout = io::stdout();
+                let mut stdout = BufWriter::new(stdout.lock());
+                write_dict(&dict, &mut stdout)?;
+                stdout.flush()?;
+                return Ok(());
             }
 
-            let mut output_file = OpenOptions::new().create(true).write(true).open(&output)?;
+            write_replacing(&output, |file| Ok(write_dict(&dict, file)?))?;
+            info!("Generated dictionary file {:?}", output);
 
-            write_dict(&dict, &mut output_file)?;
-            println!("Generated dictionary file {:?}", output);
+            if sorted_index {
+                let index_path = sorted_index_path(&output);
+                write_replacing(&index_path, |file| {
+                    Ok(write_sorted_index(&SortedIndex::build(&dict), file)?)
+                })?;
+                info!("Generated sorted index file {:?}", index_path);
+            }
         }
         Opt::Search {
             dictionary,
+            strict,
             letters,
-            min_length,
+            counts,
+            unlimited_repeats,
+            mut min_length,
+            allow_short,
             max_length,
-            separator,
+            mut separator,
+            print0,
+            require,
+            starts_with,
+            letter_index,
+            stats,
+            verify_counts,
+            report_unusable,
+            group_by_length,
+            anagram_summary,
+            min_efficiency,
+            invert,
+            limit,
+            offset,
+            from_source,
+            sort,
+            scorer,
+            score_expr,
+            prioritize,
+            bag_limits,
+            show_remaining,
+            histogram,
+            pattern,
+            exclude_derived,
+            max_tier,
+            tier,
+            max_unique,
+            min_use,
+            multiplicity,
+            show_multiplicity,
+            collapse_variants,
+            show_variants,
+            template,
+            tui,
+            longest,
+            fallback,
+            no_fallback_results,
+            used_file,
+            exclude_dictionary,
+            external_sort,
+            history,
+            force,
+            batch,
         } => {
-            println!("Using dictionary file {:?}...", dictionary);
-            let mut dict_file = File::open(&dictionary)?;
-            let dict = read_dict(&mut dict_file)?;
+            // only the empty word, kept by `generate --keep-blank`, could
+            // match a length of 0
+            for (name, length) in [("--min-length", min_length), ("--max-length", max_length)] {
+                if length == Some(0) {
+                    return Err(format!("{} must be at least 1: words have letters", name).into());
+                }
+            }
+            if deterministic && tui {
+                return Err(
+                    "--deterministic can't be used with --tui, which is interactive".into(),
+                );
+            }
+            if allow_short {
+                min_length = Some(1);
+            }
+            let scorer: Option<&dyn Scorer> = match (sort, &score_expr, &scorer) {
+                (SearchSort::Score, Some(expr), _) => Some(expr),
+                (SearchSort::Score, None, Some(name)) => {
+                    Some(score::scorer(name).ok_or_else(|| {
+                        let names = SCORERS.map(|(name, _)| name);
+                        format!("unknown scorer {} (expected {})", name, names.join(" or "))
+                    })?)
+                }
+                (SearchSort::Score, None, None) => Some(&ScrabbleScorer),
+                (_, None, None) => None,
+                _ => return Err("--scorer and --score-expr only apply to --sort score".into()),
+            };
+            if scorer.is_some() && tui {
+                return Err("--sort score can't be used with --tui".into());
+            }
+            // whether or not the search turns out to need it
+            if external_sort.is_some() && sort != SearchSort::Order(SortOrder::Alpha) {
+                return Err("--external-sort only lists words alphabetically".into());
+            }
+            let sort = match sort {
+                SearchSort::Order(order) => order,
+                // the words are scored once found, in `order`
+                SearchSort::Score => SortOrder::Alpha,
+            };
+            if print0 {
+                separator = "\0".to_owned();
+            }
+            let glob_pattern = glob::is_pattern(&dictionary);
+            if glob_pattern && fallback.is_some() {
+                return Err("--fallback can't be used with a --dictionary pattern, \
+                    whose files are already searched together"
+                    .into());
+            }
+            let (dict, dictionaries) = if glob_pattern {
+                let paths = glob::expand(&dictionary)?;
+                if paths.is_empty() {
+                    return Err(format!("no dictionary file matches {:?}", dictionary).into());
+                }
+                info!(
+                    "Using {} dictionary files matching {:?}...",
+                    paths.len(),
+                    dictionary
+                );
+                load_dictionaries(&paths, strict_format, strict, min_length)?
+            } else {
+                info!("Using dictionary file {:?}...", dictionary);
+                (load_dictionary(&dictionary)?, vec![dictionary.clone()])
+            };
 
-            println!(
-                "Solving for string {:?}, with minimum length of {}{}",
-                letters,
-                min_length,
-                if let Some(max_length) = max_length {
-                    format!(", and maximum length of {}", max_length)
-                } else {
-                    String::new()
+            if verify_counts {
+                let mismatches = dict.count_mismatches();
+                for &(offset, len) in mismatches.iter() {
+                    eprintln!(
+                        "count mismatch: entry at offset {} (length {}) {:?}",
+                        offset,
+                        len,
+                        dict.word_string()
+                            .get(offset..offset.saturating_add(len))
+                            .unwrap_or("")
+                    );
                 }
-            );
 
-            let letter_count = CountSet::from_word(&letters)?;
-            let mut words = dict
-                .par_iter()
-                .filter(|entry| letter_count.contains(entry.count_set))
-                .map(|entry| entry.word)
-                .filter(|word| {
-                    word.len() >= min_length
-                        && (if let Some(max_length) = max_length {
-                            word.len() <= max_length
+                if !mismatches.is_empty() {
+                    return Err(format!(
+                        "{} entries have letter counts that don't match their words",
+                        mismatches.len()
+                    )
+                    .into());
+                }
+            }
+
+            let (min_length, origin) = dict.search_defaults().min_length(min_length);
+            if origin == OptionOrigin::Dictionary {
+                info!(
+                    "Using the dictionary's default minimum length of {}",
+                    min_length
+                );
+            }
+
+            let bag = match bag_limits {
+                Some(Some(bag)) => Some(CountSet::parse_multiset(&bag)?),
+                Some(None) => Some(CountSet::try_from(TILE_DISTRIBUTION)?),
+                None => None,
+            };
+            // the rack as the options change it, for every rack of a batch
+            let adjust_rack = |mut rack: Rack| {
+                if unlimited_repeats {
+                    rack = rack.with_unlimited_repeats();
+                }
+                if let Some(bag) = &bag {
+                    rack = rack.limited_to(bag);
+                }
+                rack
+            };
+            let rack = match (&letters, &counts) {
+                (_, Some(counts)) => Rack::from_counts(counts),
+                (Some(letters), None) => Rack::parse(letters, &RackOptions::default())?,
+                // replaced by the rack of each line
+                (None, None) => Rack::parse("", &RackOptions::default())?,
+            };
+            let rack = adjust_rack(rack);
+            if batch.is_none() {
+                info!(
+                    "Solving for string {:?}, with minimum length of {}{}",
+                    rack.input(),
+                    min_length,
+                    if let Some(max_length) = max_length {
+                        format!(", and maximum length of {}", max_length)
+                    } else {
+                        String::new()
+                    }
+                );
+                if bag.is_some() {
+                    info!("Limited the rack to the bag: {}", rack);
+                }
+            }
+
+            let mut query = SearchQuery::new(rack);
+            query.min_length = min_length;
+            query.max_length = max_length;
+            query.require = CountSet::from_word(&query_letters(&require)?)?;
+            query.starts_with = query_letters(&starts_with)?.into_owned();
+            query.min_efficiency = min_efficiency;
+            query.invert = invert;
+            query.pattern = pattern;
+            query.exclude_derived = exclude_derived;
+            query.max_unique = max_unique;
+            query.min_use = min_use;
+            query.multiplicity = multiplicity;
+            if max_tier.is_some() || tier.is_some() {
+                if !dict.has_tiers() {
+                    return Err(format!(
+                        "{} is unavailable: the dictionary has no tiers \
+                         (generate it with --tier-column or --tier-thresholds)",
+                        if tier.is_some() {
+                            "--tier"
                         } else {
-                            true
-                        })
+                            "--max-tier"
+                        }
+                    )
+                    .into());
+                }
+                query.max_tier = max_tier;
+                query.tier = tier;
+            }
+            if let Some(template) = &template {
+                if template.uses(Placeholder::Tier) && !dict.has_tiers() {
+                    return Err("<tier> is unavailable: the dictionary has no tiers \
+                        (generate it with --tier-column or --tier-thresholds)"
+                        .into());
+                }
+                if template.uses(Placeholder::Sources) && dict.sources().is_empty() {
+                    return Err("<sources> is unavailable: the dictionary has no sources \
+                        (generate it with --source)"
+                        .into());
+                }
+            }
+            if let Some(name) = from_source {
+                let source = dict.source_index(&name).ok_or_else(|| {
+                    format!(
+                        "unknown source {:?}, the dictionary has: {}",
+                        name,
+                        dict.sources().join(", ")
+                    )
+                })?;
+                query.source = Some(source);
+            }
+            let conflicts = match batch {
+                Some(_) => filter_conflicts(&query),
+                None => conflicts(&query),
+            };
+            if force {
+                for conflict in conflicts.iter() {
+                    eprintln!("No word can match: {}", conflict);
+                }
+            } else if !conflicts.is_empty() {
+                let reasons = conflicts
+                    .iter()
+                    .map(Conflict::to_string)
+                    .collect::<Vec<_>>();
+                return Err(format!(
+                    "no word can match: {} (search anyway with --force)",
+                    reasons.join("; ")
+                )
+                .into());
+            }
+
+            if histogram {
+                print_histogram(&dict.count_matches_by_length(&query));
+                return Ok(());
+            }
+
+            if invert && limit.is_none() && !longest && !query.has_filters() {
+                return Err("--invert lists most of the dictionary; \
+                    use it with --limit or another filter such as --max-length"
+                    .into());
+            }
+
+            let load_indexes = |path: &Path, dict: &Dictionary| {
+                let mut indexes = Indexes::default();
+                // only built when searches can use it, once for every rack of
+                // a batch
+                if letter_index && query.require.total() > 0 {
+                    indexes.letters = Some(LetterIndex::build(dict));
+                }
+
+                // the indexes of the files of a pattern don't apply to their
+                // merged entries, which are scanned
+                if !glob_pattern {
+                    indexes.sorted = load_sorted_index(path, dict);
+                }
+                indexes
+            };
+            let indexes = load_indexes(&dictionary, &dict);
+
+            if longest {
+                if let Some(word) = query.longest(&dict, &indexes) {
+                    print!("{}{}", word, separator);
+                }
+                return Ok(());
+            }
+
+            let used = match &used_file {
+                Some(path) => Some(UsedWords::load(path)?),
+                None => None,
+            };
+            let exclude_dict = match &exclude_dictionary {
+                Some(path) => {
+                    info!("Using exclude dictionary file {:?}...", path);
+                    Some(load_dictionary(path)?)
+                }
+                None => None,
+            };
+            // ignoring case, as the used words are
+            let excluded = exclude_dict.as_ref().map(|exclude_dict| {
+                let keys = exclude_dict.word_count().keys();
+                keys.filter_map(|key| exclude_dict.get(key))
+                    .map(|entry| fold_word(entry.word))
+                    .collect::<HashSet<_>>()
+            });
+            // whether a word is neither used nor excluded
+            let kept = |word: &str| {
+                used.as_ref().is_none_or(|used| !used.contains(word))
+                    && excluded
+                        .as_ref()
+                        .is_none_or(|excluded| !excluded.contains(&fold_word(word)))
+            };
+
+            let priority = match &prioritize {
+                Some(letters) => Some(CountSet::from_word(&query_letters(letters)?)?),
+                None => None,
+            };
+            let order = |results: &mut SearchResults, rack: &Rack| match (&priority, scorer) {
+                (Some(priority), _) => results.prioritize(priority),
+                (None, Some(scorer)) => results.sort_by_score(scorer, rack),
+                (None, None) => results.sort(sort),
+            };
+
+            if let Some(path) = &batch {
+                let contents = fs::read(path)?;
+                let search_line = |line: Result<BatchLine, BatchError>| -> Result<_, BatchError> {
+                    let line = line?;
+                    let rack = Rack::parse(line.rack, &RackOptions::default()).map_err(|err| {
+                        BatchError {
+                            line: line.line,
+                            rack: line.rack.to_owned(),
+                            message: err.to_string(),
+                        }
+                    })?;
+                    let mut query = query.clone();
+                    query.rack = adjust_rack(rack);
+                    let mut results = query.search(&dict, &indexes);
+                    results.retain(kept);
+                    order(&mut results, &query.rack);
+                    results.page(offset, limit);
+                    Ok((line.rack.to_owned(), results.words))
+                };
+
+                let (mut searched, mut failed) = (0, 0);
+                let stdout = io::stdout();
+                let mut out = BufWriter::new(stdout.lock());
+                run_ordered(batch_lines(&contents), search_line, |result| match result {
+                    Ok((rack, words)) => {
+                        searched += 1;
+                        writeln!(out, "=== {} ===", rack)?;
+                        for word in words {
+                            write!(out, "{}{}", word, separator)?;
+                        }
+                        if !separator.ends_with('\n') {
+                            writeln!(out)?;
+                        }
+                        // each rack is out as soon as it's done
+                        out.flush()
+                    }
+                    Err(err) => {
+                        failed += 1;
+                        eprintln!("{}", err);
+                        Ok(())
+                    }
+                })?;
+
+                info!("Searched {} racks, {} failed", searched + failed, failed);
+                if failed > 0 {
+                    if searched == 0 {
+                        return Err("every rack of the batch failed".into());
+                    }
+                    // exiting doesn't drop the writer, which would flush it
+                    out.flush()?;
+                    std::process::exit(BATCH_PARTIAL_FAILURE_CODE);
+                }
+                return Ok(());
+            }
+
+            if let Some(min_results) = external_sort {
+                // counting the matches takes little memory, unlike collecting
+                // them
+                let estimate = dict
+                    .count_matches_by_length(&query)
+                    .iter()
+                    .map(|&count| count as usize)
+                    .sum::<usize>();
+                if estimate >= min_results.unwrap_or(EXTERNAL_SORT_MIN_RESULTS) {
+                    info!("Sorting {} words in temporary files...", estimate);
+
+                    let mut sorter = ExternalSorter::new(EXTERNAL_SORT_RUN_LEN, env::temp_dir());
+                    let mut found = 0;
+                    for entry in dict.word_count().keys().filter_map(|key| dict.get(key)) {
+                        if query.matches(&entry) && kept(entry.word) {
+                            sorter.push(entry.word)?;
+                            found += 1;
+                        }
+                    }
+                    if let Some(path) = &history {
+                        log_search(path, &dictionaries, &query, found)?;
+                    }
+
+                    let stdout = io::stdout();
+                    let mut out = BufWriter::new(stdout.lock());
+                    let words = sorter.finish()?.skip(offset);
+                    for word in words.take(limit.unwrap_or(usize::MAX)) {
+                        write!(out, "{}{}", word?, separator)?;
+                    }
+                    out.flush()?;
+                    return Ok(());
+                }
+            }
+
+            // with a limit, only the best words up to the end of the page are
+            // picked, unless more are needed to leave some out, collapse them
+            // or browse them
+            let top = limit
+                .filter(|_| {
+                    priority.is_none()
+                        && scorer.is_none()
+                        && used.is_none()
+                        && excluded.is_none()
+                        && fallback.is_none()
+                        && !collapse_variants
+                        && !tui
                 })
-                .collect::<Vec<_>>();
+                .map(|limit| offset.saturating_add(limit));
+            let (mut results, mut found) = match top {
+                Some(k) => query.search_top(&dict, &indexes, sort, k),
+                None => {
+                    let results = query.search(&dict, &indexes);
+                    let found = results.words.len();
+                    (results, found)
+                }
+            };
+            if let Some(used) = &used {
+                let suppressed = results.retain(|word| !used.contains(word));
+                found -= suppressed;
+                info!("Left out {} used words", suppressed);
+            }
+            if let Some(excluded) = &excluded {
+                let suppressed = results.retain(|word| !excluded.contains(&fold_word(word)));
+                found -= suppressed;
+                info!("Left out {} words of the exclude dictionary", suppressed);
+            }
+            if top.is_none() {
+                order(&mut results, &query.rack);
+            }
 
-            words.par_sort_unstable();
-            words
-                .iter()
-                .for_each(|word| print!("{}{}", word, separator));
+            // the words only found in the fallback dictionary follow those of
+            // the main one, so the pages run on from one to the other
+            let fallback_dict = match &fallback {
+                Some(path) => {
+                    info!("Using fallback dictionary file {:?}...", path);
+                    Some(load_dictionary(path)?)
+                }
+                None => None,
+            };
+            let mut fallback_results = match (&fallback, &fallback_dict) {
+                (Some(path), Some(fallback_dict)) => {
+                    let fallback_indexes = load_indexes(path, fallback_dict);
+                    let mut fallback_results = query.search(fallback_dict, &fallback_indexes);
+                    fallback_results.exclude(&results);
+                    fallback_results.retain(kept);
+                    order(&mut fallback_results, &query.rack);
+                    if no_fallback_results {
+                        eprintln!(
+                            "{} more words in the fallback dictionary",
+                            fallback_results.words.len()
+                        );
+                        None
+                    } else {
+                        Some(fallback_results)
+                    }
+                }
+                _ => None,
+            };
+            // the variants left out, for each word listed, once the fallback
+            // results no longer hold the words of the main ones
+            let mut variants = HashMap::new();
+            if collapse_variants {
+                variants = results.collapse_variants();
+                found = results.words.len();
+                if let Some(fallback_results) = &mut fallback_results {
+                    variants.extend(fallback_results.collapse_variants());
+                }
+            }
+            if let Some(path) = &history {
+                let fallback_found = fallback_results
+                    .as_ref()
+                    .map_or(0, |fallback_results| fallback_results.words.len());
+                log_search(path, &dictionaries, &query, found + fallback_found)?;
+            }
+            results.page(offset, limit);
+            if let Some(fallback_results) = &mut fallback_results {
+                fallback_results.page(
+                    offset.saturating_sub(found),
+                    limit.map(|limit| limit - results.words.len()),
+                );
+            }
+            if stats {
+                eprintln!("{}", results.plan);
+            }
+
+            if tui && io::stdin().is_terminal() && io::stderr().is_terminal() {
+                #[cfg(feature = "tui")]
+                {
+                    let mut browser = Browser::new(&results, sort, 0);
+                    if let Some(word) = browse::run(&mut browser)? {
+                        println!("{}", word);
+                    }
+                    return Ok(());
+                }
+                #[cfg(not(feature = "tui"))]
+                return Err("--tui is unavailable: this build has no `tui` feature".into());
+            }
+
+            // shown in brackets after each word
+            let annotate = |results: &SearchResults| -> Vec<String> {
+                if show_remaining {
+                    let leftovers = results.leftovers(&query.rack);
+                    leftovers.iter().map(CountSet::to_string).collect()
+                } else {
+                    let multiplicities = results.multiplicities(&query.rack);
+                    multiplicities.iter().map(|k| format!("x{}", k)).collect()
+                }
+            };
+            let annotations = if collapse_variants {
+                variants
+                    .into_iter()
+                    .map(|(word, variants)| match show_variants {
+                        true => (word, variants.join(" ")),
+                        false => (word, format!("+{}", variants.len())),
+                    })
+                    .collect()
+            } else if show_remaining || show_multiplicity {
+                results
+                    .words
+                    .iter()
+                    .copied()
+                    .zip(annotate(&results))
+                    .chain(fallback_results.iter().flat_map(|fallback_results| {
+                        fallback_results
+                            .words
+                            .iter()
+                            .copied()
+                            .zip(annotate(fallback_results))
+                    }))
+                    .collect()
+            } else {
+                HashMap::new()
+            };
+            // a single buffered writer, rather than locking stdout for every
+            // word, which is slow with thousands of results
+            let stdout = io::stdout();
+            let mut out = BufWriter::new(stdout.lock());
+            let render = |template: &Template, word: &str| {
+                // the words of the fallback results come from the fallback
+                // dictionary
+                let (entry, sources) = match dict.entry_of(word) {
+                    Some(entry) => (Some(entry), dict.sources()),
+                    None => match &fallback_dict {
+                        Some(fallback_dict) => {
+                            (fallback_dict.entry_of(word), fallback_dict.sources())
+                        }
+                        None => (None, dict.sources()),
+                    },
+                };
+                template.render(|placeholder| match placeholder {
+                    Placeholder::Word => word.to_owned(),
+                    Placeholder::Length => word.len().to_string(),
+                    Placeholder::Score => entry.as_ref().map_or_else(String::new, |entry| {
+                        rack_score(&query.rack, entry.count_set).to_string()
+                    }),
+                    Placeholder::Remaining => entry.as_ref().map_or_else(String::new, |entry| {
+                        let rack = query.rack.letter_counts();
+                        rack.difference(entry.count_set).to_string()
+                    }),
+                    Placeholder::Sources => entry
+                        .as_ref()
+                        .and_then(|entry| entry.source)
+                        .and_then(|source| sources.get(source as usize))
+                        .map_or_else(String::new, String::clone),
+                    Placeholder::Tier => entry
+                        .as_ref()
+                        .and_then(|entry| entry.tier())
+                        .map_or_else(String::new, |tier| TIER_NAMES[tier as usize].to_owned()),
+                })
+            };
+            let write_word =
+                |out: &mut BufWriter<_>, word: &str| match (&template, annotations.get(word)) {
+                    (Some(template), _) => write!(out, "{}{}", render(template, word), separator),
+                    (None, Some(annotation)) => {
+                        write!(out, "{} [{}]{}", word, annotation, separator)
+                    }
+                    (None, None) => write!(out, "{}{}", word, separator),
+                };
+            // whether the words leave the output at the start of a line, for
+            // the headers
+            let line_ended = separator.ends_with('\n')
+                || (separator.is_empty()
+                    && template.as_ref().is_some_and(Template::ends_with_newline));
+            let write_words = |out: &mut BufWriter<_>, results: &SearchResults| -> io::Result<()> {
+                let words = &results.words;
+                if anagram_summary {
+                    for (key, words) in results.anagram_groups() {
+                        writeln!(out, "{} ({}): {}", key, words.len(), words.join(", "))?;
+                    }
+                } else if group_by_length {
+                    let mut words = words.to_vec();
+                    words.sort_by_key(|word| word.len());
+
+                    for group in words.chunk_by(|a, b| a.len() == b.len()) {
+                        writeln!(out, "=== {} letters ===", group[0].len())?;
+                        for word in group.iter() {
+                            write_word(out, word)?;
+                        }
+
+                        // keep the headers on their own lines
+                        if !line_ended {
+                            writeln!(out)?;
+                        }
+                    }
+                } else {
+                    for word in words.iter() {
+                        write_word(out, word)?;
+                    }
+                }
+                Ok(())
+            };
+
+            write_words(&mut out, &results)?;
+            if let Some(fallback_results) = &fallback_results {
+                if !fallback_results.words.is_empty() {
+                    if !group_by_length && !anagram_summary && !line_ended {
+                        writeln!(out)?;
+                    }
+                    writeln!(out, "=== fallback ===")?;
+                    write_words(&mut out, fallback_results)?;
+                }
+            }
+            out.flush()?;
+
+            if report_unusable {
+                if let Some(fallback_results) = &fallback_results {
+                    results.used_letters |= fallback_results.used_letters;
+                }
+                for letter in results.unusable_letters(&query.rack) {
+                    eprintln!("letter {} is unusable with these constraints", letter);
+                }
+            }
+        }
+        Opt::Info {
+            dictionary,
+            explain_format,
+        } => {
+            if explain_format {
+                let stdout = io::stdout();
+                let mut stdout = BufWriter::new(stdout.lock());
+                let explained = read_dictionary_file(&dictionary, |reader| {
+                    Ok(explain_dict(reader, &mut stdout)?)
+                });
+                stdout.flush()?;
+                return explained;
+            }
+
+            let header = read_dictionary_file(&dictionary, |reader| Ok(peek_header(reader)?))?;
+            let (dict, report) = self::load_dictionary(&dictionary, strict_format)?;
+
+            println!("file: {:?}", dictionary);
+            println!("format version: {}", header.version);
+            println!("entries: {}", dict.len());
+            println!("word string: {} bytes", dict.word_string().len());
+            println!("estimated memory: {} bytes", header.estimated_memory());
+            println!("content hash: sha256:{}", to_hex(&dict.content_hash()));
+            println!(
+                "file hash: sha256:{}",
+                to_hex(&read_dictionary_file(&dictionary, |reader| {
+                    Ok(file_hash(reader)?)
+                })?)
+            );
+            if !dict.sources().is_empty() {
+                println!("sources: {}", dict.sources().join(", "));
+            }
+            if !dict.derived().is_empty() {
+                println!("derived words: {}", dict.derived().len());
+            }
+            if dict.has_tiers() {
+                let mut tiers = [0; MAX_TIER as usize + 1];
+                for key in dict.word_count().keys() {
+                    if let Some(tier) = dict.get(key).and_then(|entry| entry.tier()) {
+                        tiers[tier as usize] += 1;
+                    }
+                }
+                println!("words per tier: {:?}", tiers);
+            }
+            if let Some(min_length) = dict.search_defaults().min_length {
+                println!("default minimum length: {}", min_length);
+            }
+            if report.trailing_bytes > 0 {
+                println!("trailing data: {} bytes", report.trailing_bytes);
+            }
+            // both only happen in corrupted or hand-edited files
+            let overlapping = dict.overlapping_entries();
+            if !overlapping.is_empty() {
+                println!("overlapping entry pairs: {}", overlapping.len());
+            }
+            let duplicates = dict.duplicate_entries();
+            if !duplicates.is_empty() {
+                println!(
+                    "duplicate entries: {} words spelled by {} entries",
+                    duplicates.len(),
+                    duplicates.iter().map(Vec::len).sum::<usize>()
+                );
+            }
+
+            for &(extension, name) in capabilities::SIDECARS.iter() {
+                let mut path = dictionary.as_os_str().to_owned();
+                path.push(".");
+                path.push(extension);
+                if Path::new(&path).exists() {
+                    println!("{}: {:?} (supported)", name, path);
+                }
+            }
+        }
+        Opt::Validate {
+            dictionary,
+            format,
+            fail_on,
+        } => {
+            let report = read_dictionary_file(&dictionary, |reader| Ok(validate(reader)?))?;
+            match format {
+                ReportFormat::Text => {
+                    for finding in report.findings.iter() {
+                        println!("{}", finding);
+                    }
+                    println!(
+                        "errors: {}, warnings: {}",
+                        report.count_severity(Severity::Error),
+                        report.count_severity(Severity::Warning)
+                    );
+                }
+                ReportFormat::Json => println!("{}", report.json()),
+            }
+
+            if report.fails(fail_on) {
+                std::process::exit(VALIDATE_FAILURE_CODE);
+            }
+        }
+        Opt::Compare {
+            first,
+            second,
+            racks,
+            count,
+            rack_size,
+            seed,
+            min_length,
+            csv,
+        } => {
+            if min_length == 0 {
+                return Err("--min-length must be at least 1: words have letters".into());
+            }
+            let first_dict = load_dictionary(&first)?;
+            let second_dict = load_dictionary(&second)?;
+            let seed = racks.is_none().then_some(seed);
+            let racks = match racks {
+                Some(racks) => BufReader::new(File::open(racks)?)
+                    .lines()
+                    .collect::<Result<Vec<_>, _>>()?,
+                None => random_racks(seed.unwrap(), count, rack_size),
+            };
+
+            let mut query = SearchQuery::new(Rack::parse("", &RackOptions::default())?);
+            query.min_length = min_length;
+            let comparison = compare(&first_dict, &second_dict, &query, &racks)?;
+
+            if let Some(csv) = csv {
+                let mut echo = QueryEcho::new(query);
+                for (path, dict) in [(&first, &first_dict), (&second, &second_dict)] {
+                    echo.dictionaries.push(DictionaryEcho {
+                        path: path.display().to_string(),
+                        hash: read_dictionary_file(path, |reader| Ok(file_fnv_hash(reader)?))?,
+                        words: Some(dict.content_hash()),
+                    });
+                }
+                echo.seed = seed;
+
+                let mut csv = BufWriter::new(File::create(csv)?);
+                write!(csv, "{}", echo)?;
+                writeln!(csv, "rack,first,second,difference")?;
+                for rack in comparison.racks.iter() {
+                    writeln!(
+                        csv,
+                        "{},{},{},{}",
+                        csv_field(&rack.rack),
+                        rack.first,
+                        rack.second,
+                        rack.difference()
+                    )?;
+                }
+                csv.flush()?;
+            }
+
+            let summary = comparison.summary().ok_or("no racks to compare")?;
+            println!("racks: {}", comparison.racks.len());
+            println!(
+                "mean words per rack: {:.2} ({:?}), {:.2} ({:?})",
+                summary.mean_first, first, summary.mean_second, second
+            );
+            println!("difference (second - first):");
+            println!("  mean: {:+.2}", summary.mean);
+            println!("  median: {:+}", summary.median);
+            println!("  10th percentile: {:+}", summary.p10);
+            println!("  90th percentile: {:+}", summary.p90);
+            println!("  range: {:+} to {:+}", summary.min, summary.max);
+            println!(
+                "only in {:?}: {}",
+                first,
+                comparison
+                    .only_first
+                    .iter()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            println!(
+                "only in {:?}: {}",
+                second,
+                comparison
+                    .only_second
+                    .iter()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+        Opt::Diff { old, new } => {
+            let old_dict = load_dictionary(&old)?;
+            let new_dict = load_dictionary(&new)?;
+            let diff = diff(&old_dict, &new_dict);
+
+            let stdout = io::stdout();
+            let mut out = BufWriter::new(stdout.lock());
+            write_diff(&mut out, &diff, None)?;
+            out.flush()?;
+        }
+        Opt::Hooks { dictionary, word } => {
+            let dict = load_dictionary(&dictionary)?;
+            let sorted = load_sorted_index(&dictionary, &dict);
+            let hooks = hooks(&dict, sorted.as_ref(), &word)
+                .map_err(|err| format!("{:?}: {}", word, err))?;
+
+            let stdout = io::stdout();
+            let mut out = BufWriter::new(stdout.lock());
+            for (name, words) in [("front", &hooks.front), ("back", &hooks.back)] {
+                writeln!(out, "=== {} ({}) ===", name, words.len())?;
+                for word in words.iter() {
+                    writeln!(out, "{}", word)?;
+                }
+            }
+            out.flush()?;
+        }
+        Opt::Export { dictionary, format } => {
+            let dict = load_dictionary(&dictionary)?;
+
+            let stdout = io::stdout();
+            let mut stdout = BufWriter::new(stdout.lock());
+            export(&dict, format, &mut stdout)?;
+            stdout.flush()?;
+        }
+        Opt::Pick {
+            dictionary,
+            length,
+            seed,
+        } => {
+            if deterministic && seed.is_none() {
+                return Err("--deterministic needs --seed: pick is otherwise random".into());
+            }
+            let dict = load_dictionary(&dictionary)?;
+            let mut rng = match seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_entropy(),
+            };
+
+            let word = dict
+                .random_word(length, &mut rng)
+                .ok_or_else(|| format!("no words of length {} in {:?}", length, dictionary))?;
+            println!("{}", word);
+        }
+        Opt::Shortlist {
+            dictionary,
+            containing,
+            new_since,
+            format,
+        } => {
+            if containing.is_some_and(|letter| !letter.is_ascii_alphabetic()) {
+                return Err(CountError::NotAlphabetic.into());
+            }
+
+            let dict = load_dictionary(&dictionary)?;
+            let older = new_since.as_deref().map(load_dictionary).transpose()?;
+            let words = shortlist(
+                &dict,
+                &ShortlistOptions {
+                    containing,
+                    new_since: older.as_ref(),
+                },
+            );
+
+            let stdout = io::stdout();
+            let mut stdout = BufWriter::new(stdout.lock());
+            write_shortlist(&words, format, &mut stdout)?;
+            stdout.flush()?;
+        }
+        Opt::Lint { file } => {
+            let report = lint(BufReader::new(File::open(&file)?))?;
+
+            for (line, word, err) in report.invalid.iter() {
+                eprintln!("Invalid word {:?} (line {}): {}", word, line, err);
+            }
+
+            eprintln!("lines: {}", report.lines);
+            eprintln!("unique words: {}", report.unique);
+            eprintln!("duplicates: {}", report.duplicates);
+            eprintln!("blank lines: {}", report.blank);
+            eprintln!("invalid words: {}", report.invalid.len());
+            for (reason, name) in [
+                (CountError::NotAscii, "non-ASCII"),
+                (CountError::NotAlphabetic, "non-letters"),
+                (CountError::CountOverflow, "over 15 of a letter"),
+            ] {
+                eprintln!("  {}: {}", name, report.invalid_count(reason));
+            }
+        }
+        Opt::Used { file, words, clear } => {
+            // read just before the change, to keep the words added by hand
+            let mut used = UsedWords::load(&file)?;
+            if clear {
+                used.clear();
+                used.save(&file)?;
+            } else if !words.is_empty() {
+                for word in words.iter() {
+                    // only valid words can be found, and so left out
+                    CountSet::from_word(word).map_err(|err| format!("{:?}: {}", word, err))?;
+                    if !used.insert(word) {
+                        info!("{:?} was already used", word);
+                    }
+                }
+                used.save(&file)?;
+            } else {
+                let stdout = io::stdout();
+                let mut stdout = BufWriter::new(stdout.lock());
+                used.write_to(&mut stdout)?;
+                stdout.flush()?;
+            }
+        }
+        Opt::History {
+            file,
+            since,
+            until,
+            rack,
+            raw,
+        } => {
+            let rack = match rack {
+                Some(letters) => Some(Rack::parse(&letters, &RackOptions::default())?.to_string()),
+                None => None,
+            };
+
+            let stdout = io::stdout();
+            let mut out = BufWriter::new(stdout.lock());
+            for (i, line) in BufReader::new(File::open(&file)?).lines().enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let entry = line
+                    .parse::<HistoryEntry>()
+                    .map_err(|err| format!("line {}: {}", i + 1, err))?;
+
+                let date = entry.date();
+                if since.is_some_and(|since| date < since)
+                    || until.is_some_and(|until| date > until)
+                    || rack.as_ref().is_some_and(|rack| entry.rack() != Some(rack))
+                {
+                    continue;
+                }
+                if raw {
+                    writeln!(out, "{}", line)?;
+                } else {
+                    write_entry(&mut out, &entry)?;
+                }
+            }
+            out.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+// appends a search to the log at `path`, unless turned off by the environment.
+// The hash logged is that of the dictionary file, or for several files the
+// hash of their hashes
+fn log_search(
+    path: &Path,
+    dictionaries: &[PathBuf],
+    query: &SearchQuery,
+    results: usize,
+) -> Result<(), Box<dyn Error>> {
+    if env::var_os(NO_HISTORY_VAR).is_some() {
+        return Ok(());
+    }
+
+    let file_hash =
+        |dictionary: &Path| read_dictionary_file(dictionary, |reader| Ok(file_fnv_hash(reader)?));
+    let hash = match dictionaries {
+        [dictionary] => file_hash(dictionary)?,
+        _ => {
+            let mut hashes = Vec::new();
+            for dictionary in dictionaries {
+                hashes.extend(file_hash(dictionary)?.to_le_bytes());
+            }
+            file_fnv_hash(&mut hashes.as_slice())?
+        }
+    };
+    HistoryEntry::now(query.to_string(), hash, results).append_to(path)?;
+    Ok(())
+}
+
+// whether `output` was modified after every input, as make decides: never
+// if it doesn't exist. The errors name the file they're about
+fn up_to_date(output: &Path, inputs: &[&Path]) -> Result<bool, Box<dyn Error>> {
+    let modified = |path: &Path| fs::metadata(path).and_then(|metadata| metadata.modified());
+    let output_modified = match modified(output) {
+        Ok(time) => time,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(err) => return Err(format!("{}: {}", output.display(), err).into()),
+    };
+    for input in inputs {
+        let input_modified =
+            modified(input).map_err(|err| format!("{}: {}", input.display(), err))?;
+        if input_modified > output_modified {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+// reads a dictionary file, or an archive entry such as `bundle.zip:en.dict`
+// when built with archive support
+fn read_dictionary_file<T>(
+    path: &Path,
+    read: impl FnOnce(&mut dyn Read) -> Result<T, Box<dyn Error>>,
+) -> Result<T, Box<dyn Error>> {
+    #[cfg(feature = "archives")]
+    if let Some(path) = ArchivePath::parse(path) {
+        return archive::read_entry(&path, read)?;
+    }
+
+    read(&mut BufReader::new(File::open(path)?))
+}
+
+// the added then removed words, each under a `=== added (N) ===` header,
+// followed by the number of words left out beyond `examples`, if given
+fn write_diff(
+    out: &mut dyn Write,
+    diff: &DictionaryDiff,
+    examples: Option<usize>,
+) -> io::Result<()> {
+    for (name, words) in [("added", &diff.added), ("removed", &diff.removed)] {
+        writeln!(out, "=== {} ({}) ===", name, words.len())?;
+        let shown = examples.unwrap_or(words.len()).min(words.len());
+        for word in words[..shown].iter() {
+            writeln!(out, "{}", word)?;
+        }
+        if shown < words.len() {
+            writeln!(out, "... and {} more", words.len() - shown)?;
         }
     }
 
     Ok(())
 }
+
+// writes a file through a temporary file in the same directory, renamed
+// over it once complete, so that a search reading the file at the same time
+// gets either the old file or the new one, never a mix of both
+fn write_replacing(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> Result<(), Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(format!(".tmp{}", std::process::id()));
+    let temp = PathBuf::from(temp);
+
+    let mut writer = BufWriter::new(File::create(&temp)?);
+    let written = write(&mut writer).and_then(|()| {
+        writer.into_inner()?.sync_all()?;
+        Ok(fs::rename(&temp, path)?)
+    });
+    if written.is_err() {
+        _ = fs::remove_file(&temp);
+    }
+    written
+}
+
+// a file being replaced while it's read looks truncated or inconsistent, so
+// the read is retried once before giving up; a missing file or a denied
+// permission isn't
+fn load_dictionary(path: &Path, strict: bool) -> Result<(Dictionary, ReadReport), Box<dyn Error>> {
+    let read = || read_dictionary_file(path, |reader| Ok(read_dict_report(reader)?));
+
+    let (dict, report) = read().or_else(|err| {
+        if !worth_retrying(err.as_ref()) {
+            return Err(err);
+        }
+        eprintln!("Failed to read {:?} ({}), retrying...", path, err);
+        read()
+    })?;
+
+    if report.trailing_bytes > 0 {
+        if strict {
+            return Err(ReadError::TrailingData(report.trailing_bytes).into());
+        }
+        eprintln!(
+            "Warning: {:?} has {} bytes of trailing data after the dictionary",
+            path, report.trailing_bytes
+        );
+    }
+
+    Ok((dict, report))
+}
+
+// whether the error of reading a dictionary file is of one cut short or
+// inconsistent, as while it's being replaced
+fn worth_retrying(err: &(dyn Error + 'static)) -> bool {
+    let truncated = |err: &io::Error| err.kind() == io::ErrorKind::UnexpectedEof;
+    match err.downcast_ref::<ReadError>() {
+        Some(ReadError::FormatError | ReadError::LengthMismatch { .. }) => true,
+        Some(ReadError::IoError(err)) => truncated(err),
+        Some(ReadError::TrailingData(_)) => false,
+        None => err.downcast_ref::<io::Error>().is_some_and(truncated),
+    }
+}
+
+// merges the dictionary files matching a pattern, tagging their words with
+// the stem of their file, and skipping with a warning the files that can't be
+// read unless `strict`; returns the files read along. The files' search
+// defaults apply if they're all the same; otherwise the search needs the
+// options they differ on, such as `min_length`, from the command line
+fn load_dictionaries(
+    paths: &[PathBuf],
+    strict_format: bool,
+    strict: bool,
+    min_length: Option<usize>,
+) -> Result<(Dictionary, Vec<PathBuf>), Box<dyn Error>> {
+    let mut dict = Dictionary::new();
+    let mut read = Vec::new();
+    let mut defaults = Vec::new();
+    for path in paths {
+        let file_dict = match load_dictionary(path, strict_format) {
+            Ok((file_dict, _)) => file_dict,
+            Err(err) if strict => return Err(format!("{:?}: {}", path, err).into()),
+            Err(err) => {
+                eprintln!("Warning: skipping {:?}: {}", path, err);
+                continue;
+            }
+        };
+        let stem = path.file_stem().unwrap_or(path.as_os_str());
+        let source = dict.add_source(&stem.to_string_lossy())?;
+        dict.extend_from(&file_dict, source);
+        read.push(path.clone());
+        defaults.push(file_dict.search_defaults().clone());
+    }
+
+    if read.is_empty() {
+        return Err(format!("none of the {} dictionary files could be read", paths.len()).into());
+    }
+    if defaults
+        .iter()
+        .all(|file_defaults| *file_defaults == defaults[0])
+    {
+        dict.set_search_defaults(defaults.swap_remove(0));
+    } else if min_length.is_none() {
+        let lengths = read
+            .iter()
+            .zip(defaults.iter())
+            .map(|(path, file_defaults)| match file_defaults.min_length {
+                Some(min_length) => format!("{} {}", path.display(), min_length),
+                None => format!("{} none", path.display()),
+            })
+            .collect::<Vec<_>>();
+        return Err(format!(
+            "the dictionary files have different default minimum lengths ({}): \
+             pass --min-length",
+            lengths.join(", ")
+        )
+        .into());
+    }
+    Ok((dict, read))
+}
+
+// exits with clap's error, suggesting the closest subcommand or flag first
+// for unknown ones
+fn exit_with_suggestion(app: &App, err: clap::Error) -> ! {
+    let token = match (err.kind, err.info.as_ref().and_then(|info| info.first())) {
+        (ErrorKind::UnknownArgument | ErrorKind::UnrecognizedSubcommand, Some(token)) => token,
+        _ => err.exit(),
+    };
+
+    let subcommands = help_entries(&help_text(app, None), "SUBCOMMANDS");
+    let subcommand = std::env::args()
+        .skip(1)
+        .find(|arg| subcommands.contains(arg));
+    let suggestion = if let Some(flag) = token.strip_prefix("--") {
+        let flag = flag.split('=').next().unwrap_or(flag);
+        // the help of a subcommand lists the global flags too
+        let help = help_text(app, subcommand.as_deref());
+        let longs = ["FLAGS", "OPTIONS"]
+            .into_iter()
+            .flat_map(|section| help_entries(&help, section))
+            .filter_map(|entry| entry.strip_prefix("--").map(str::to_owned))
+            .collect::<Vec<_>>();
+        closest(
+            flag,
+            longs.iter().map(String::as_str).chain(["help", "version"]),
+        )
+        .map(|long| ("flag", format!("--{}", long)))
+    } else if subcommand.is_none() && !token.starts_with('-') {
+        let names = subcommands.iter().map(String::as_str);
+        closest(token, names).map(|name| ("subcommand", name.to_owned()))
+    } else {
+        None
+    };
+
+    match suggestion {
+        Some((kind, suggestion)) => {
+            eprintln!(
+                "error: unknown {} '{}', did you mean '{}'?",
+                kind, token, suggestion
+            );
+            // clap's usage, without its own first line
+            for line in err.message.lines().skip(1) {
+                eprintln!("{}", line);
+            }
+            std::process::exit(1);
+        }
+        None => err.exit(),
+    }
+}
+
+// the help of the app, or of one of its subcommands: clap 2 has no public
+// way to list the names it knows but its help
+fn help_text(app: &App, subcommand: Option<&str>) -> String {
+    let args = ["word-puzzle-searcher"]
+        .into_iter()
+        .chain(subcommand)
+        .chain(["--help"]);
+    match app.clone().get_matches_from_safe(args) {
+        Err(err) if err.kind == ErrorKind::HelpDisplayed => err.message,
+        _ => String::new(),
+    }
+}
+
+// the names starting the entries of a section of a help text, such as the
+// subcommands of `SUBCOMMANDS`, or the long flags (`--quiet`) of `FLAGS`: the
+// lines of the descriptions are indented further than those of the entries
+fn help_entries(help: &str, section: &str) -> Vec<String> {
+    let header = format!("{}:", section);
+    help.lines()
+        .skip_while(|line| *line != header)
+        .skip(1)
+        .take_while(|line| line.is_empty() || line.starts_with(' '))
+        .filter(|line| line.len() - line.trim_start().len() <= 8)
+        .flat_map(|line| {
+            // `-q, --quiet`, `--dictionary <dictionary>` or `search`
+            let names = line.trim_start().split("  ").next().unwrap_or("");
+            let names = names.split([',', ' ']).filter(|name| !name.is_empty());
+            match names.clone().find(|name| name.starts_with("--")) {
+                Some(long) => vec![long.to_owned()],
+                None => names.take(1).map(str::to_owned).collect(),
+            }
+        })
+        .collect()
+}
+
+// one line per length with words, with a bar proportional to the count
+fn print_histogram(counts: &[u32]) {
+    const BAR_WIDTH: u32 = 50;
+
+    let max = counts.iter().copied().max().unwrap_or(0);
+    for (length, &count) in counts.iter().enumerate().filter(|(_, &count)| count > 0) {
+        let bar = (count as u64 * BAR_WIDTH as u64).div_ceil(max as u64) as usize;
+        println!("{:>3} {:>7} {}", length, count, "#".repeat(bar));
+    }
+}
+
+// cleans up and validates letters given as a query option, the same way as
+// the letters of the rack
+fn query_letters(s: &str) -> Result<Cow<'_, str>, CountError> {
+    let letters = normalize_letters(
+        s,
+        &NormalizeOptions {
+            separators: RackOptions::default().separators,
+            ..NormalizeOptions::default()
+        },
+    );
+    validate_letters(&letters)?;
+
+    Ok(letters)
+}
+
+// a field of a CSV file, quoted if it has a comma, a quote or a line break,
+// such as a rack with separators (`a,b,c`), its quotes doubled
+fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+// the sorted index, used whenever it was generated along with the
+// dictionary, or `None` to fall back to a scan if it's missing or unusable
+fn load_sorted_index(dictionary: &Path, dict: &Dictionary) -> Option<SortedIndex> {
+    let index_path = sorted_index_path(dictionary);
+    if !index_path.exists() {
+        return None;
+    }
+
+    match File::open(&index_path)
+        .map_err(From::from)
+        .and_then(|mut file| read_sorted_index(&mut file, dict))
+    {
+        Ok(index) => Some(index),
+        Err(err) => {
+            eprintln!("Ignoring sorted index {:?}: {}", index_path, err);
+            None
+        }
+    }
+}
+
+// the sorted index is stored next to its dictionary, e.g. `default.dict.sidx`
+fn sorted_index_path(dictionary: &Path) -> PathBuf {
+    let mut path = dictionary.as_os_str().to_owned();
+    path.push(".sidx");
+    PathBuf::from(path)
+}
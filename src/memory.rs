@@ -0,0 +1,94 @@
+//! Heap allocation tracking, to report the peak memory use of a command
+//!
+//! `TrackingAllocator` wraps the system allocator, keeping count of the
+//! bytes allocated and of their peak. It only counts once installed as the
+//! global allocator, which the binary does with the `profile-memory`
+//! feature:
+//!
+//! ```ignore
+//! #[global_allocator]
+//! static ALLOCATOR: TrackingAllocator = TrackingAllocator;
+//! ```
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// The system allocator, counting the bytes it allocates
+pub struct TrackingAllocator;
+
+impl TrackingAllocator {
+    fn allocated(size: usize) {
+        let current = CURRENT.fetch_add(size, Ordering::Relaxed) + size;
+        PEAK.fetch_max(current, Ordering::Relaxed);
+    }
+
+    fn deallocated(size: usize) {
+        CURRENT.fetch_sub(size, Ordering::Relaxed);
+    }
+}
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            Self::allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            Self::allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        Self::deallocated(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            Self::deallocated(layout.size());
+            Self::allocated(new_size);
+        }
+        new_ptr
+    }
+}
+
+/// Bytes currently allocated through `TrackingAllocator`
+pub fn current_bytes() -> usize {
+    CURRENT.load(Ordering::Relaxed)
+}
+
+/// Most bytes allocated through `TrackingAllocator` at any one time so far
+pub fn peak_bytes() -> usize {
+    PEAK.load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[global_allocator]
+    static ALLOCATOR: TrackingAllocator = TrackingAllocator;
+
+    #[test]
+    fn peak() {
+        // other tests allocate at the same time, so only lower bounds hold
+        let before = current_bytes();
+        let mut buffer = vec![0u8; 1 << 20];
+        assert!(peak_bytes() >= before + (1 << 20));
+
+        buffer.resize(4 << 20, 1);
+        assert!(peak_bytes() >= 4 << 20);
+        assert!(current_bytes() >= 4 << 20);
+        drop(buffer);
+    }
+}
@@ -0,0 +1,159 @@
+//! Parallel iteration, with rayon when the `parallel` feature is enabled and
+//! sequentially otherwise
+//!
+//! Without the feature, the traits below give standard iterators and slices
+//! the methods of rayon used by the crate, so the same code compiles both
+//! ways. Only methods rayon names differently from `Iterator` are needed:
+//! `fold_with` and `reduce_with` rather than `fold` and `reduce`, whose
+//! signatures differ between the two.
+
+#[cfg(feature = "parallel")]
+pub(crate) use rayon::{join, prelude::*};
+
+#[cfg(not(feature = "parallel"))]
+pub(crate) use sequential::*;
+
+#[cfg(not(feature = "parallel"))]
+mod sequential {
+    use std::cmp::Ordering;
+
+    pub(crate) fn join<A, B, RA, RB>(a: A, b: B) -> (RA, RB)
+    where
+        A: FnOnce() -> RA,
+        B: FnOnce() -> RB,
+    {
+        (a(), b())
+    }
+
+    pub(crate) trait ParallelIterator: Iterator + Sized {
+        fn fold_with<T, F>(self, init: T, fold_op: F) -> std::iter::Once<T>
+        where
+            F: FnMut(T, Self::Item) -> T,
+        {
+            std::iter::once(self.fold(init, fold_op))
+        }
+
+        fn reduce_with<F>(self, op: F) -> Option<Self::Item>
+        where
+            F: FnMut(Self::Item, Self::Item) -> Self::Item,
+        {
+            self.reduce(op)
+        }
+
+        fn find_first<P>(mut self, predicate: P) -> Option<Self::Item>
+        where
+            P: FnMut(&Self::Item) -> bool,
+        {
+            self.find(predicate)
+        }
+    }
+
+    impl<I: Iterator> ParallelIterator for I {}
+
+    pub(crate) trait IntoParallelIterator: IntoIterator + Sized {
+        fn into_par_iter(self) -> Self::IntoIter {
+            self.into_iter()
+        }
+    }
+
+    impl<I: IntoIterator> IntoParallelIterator for I {}
+
+    pub(crate) trait IntoParallelRefIterator<'a> {
+        type Iter: Iterator;
+
+        fn par_iter(&'a self) -> Self::Iter;
+    }
+
+    impl<'a, I: 'a + ?Sized> IntoParallelRefIterator<'a> for I
+    where
+        &'a I: IntoIterator,
+    {
+        type Iter = <&'a I as IntoIterator>::IntoIter;
+
+        fn par_iter(&'a self) -> Self::Iter {
+            self.into_iter()
+        }
+    }
+
+    pub(crate) trait ParallelSliceMut<T> {
+        fn par_sort_unstable(&mut self)
+        where
+            T: Ord;
+
+        fn par_sort_unstable_by<F>(&mut self, compare: F)
+        where
+            F: FnMut(&T, &T) -> Ordering;
+
+        fn par_sort_unstable_by_key<K: Ord, F>(&mut self, f: F)
+        where
+            F: FnMut(&T) -> K;
+
+        fn par_sort_by_key<K: Ord, F>(&mut self, f: F)
+        where
+            F: FnMut(&T) -> K;
+    }
+
+    impl<T> ParallelSliceMut<T> for [T] {
+        fn par_sort_unstable(&mut self)
+        where
+            T: Ord,
+        {
+            self.sort_unstable();
+        }
+
+        fn par_sort_unstable_by<F>(&mut self, compare: F)
+        where
+            F: FnMut(&T, &T) -> Ordering,
+        {
+            self.sort_unstable_by(compare);
+        }
+
+        fn par_sort_unstable_by_key<K: Ord, F>(&mut self, f: F)
+        where
+            F: FnMut(&T) -> K,
+        {
+            self.sort_unstable_by_key(f);
+        }
+
+        fn par_sort_by_key<K: Ord, F>(&mut self, f: F)
+        where
+            F: FnMut(&T) -> K,
+        {
+            self.sort_by_key(f);
+        }
+    }
+}
+
+// these run under both configurations, checking that the sequential
+// fallbacks behave like rayon
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn fold_and_reduce() {
+        let sum = (1..=100)
+            .into_par_iter()
+            .fold_with(0, |sum, i| sum + i)
+            .reduce_with(|a, b| a + b);
+        assert_eq!(sum, Some(5050));
+
+        let empty = Vec::<u32>::new();
+        assert_eq!(empty.par_iter().copied().reduce_with(|a, b| a + b), None);
+    }
+
+    #[test]
+    fn iterate_and_sort() {
+        let map = (0..10).map(|i| (i, i * i)).collect::<HashMap<_, _>>();
+        let mut squares = map.par_iter().map(|(_, &v)| v).collect::<Vec<_>>();
+        squares.par_sort_unstable_by_key(|&v| std::cmp::Reverse(v));
+        assert_eq!(squares[..3], [81, 64, 49]);
+
+        let first = squares.par_iter().find_first(|&&v| v < 50);
+        assert_eq!(first, Some(&49));
+
+        let (a, b) = join(|| 1, || 2);
+        assert_eq!((a, b), (1, 2));
+    }
+}
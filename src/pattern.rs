@@ -0,0 +1,228 @@
+//! Letter patterns with placeholders
+//!
+//! In a pattern, a lowercase letter stands for itself, `_` for any letter and
+//! `*` for any number of letters. An uppercase letter is a placeholder for an
+//! unknown letter, which must be the same everywhere the placeholder appears:
+//! `*XX*` matches the words with a double letter.
+
+use crate::count::CountError;
+use crate::fold::fold_case;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Token {
+    Letter(u8),
+    Placeholder(u8),
+    AnyLetter,
+    AnyLetters,
+}
+
+// the letter bound to each placeholder so far, from A to Z
+type Bindings = [Option<u8>; 26];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    tokens: Vec<Token>,
+    // whether a placeholder follows a `*`, which the letters the `*` takes
+    // can bind otherwise
+    bound_after_star: bool,
+}
+
+impl Pattern {
+    pub fn parse(s: &str) -> Result<Self, CountError> {
+        if !s.is_ascii() {
+            return Err(CountError::NotAscii);
+        }
+
+        let tokens = s
+            .bytes()
+            .map(|b| match b {
+                b'a'..=b'z' => Ok(Token::Letter(b)),
+                b'A'..=b'Z' => Ok(Token::Placeholder(b - b'A')),
+                b'_' => Ok(Token::AnyLetter),
+                b'*' => Ok(Token::AnyLetters),
+                _ => Err(CountError::NotAlphabetic),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let bound_after_star = tokens
+            .iter()
+            .skip_while(|&&token| token != Token::AnyLetters)
+            .any(|token| matches!(token, Token::Placeholder(_)));
+
+        Ok(Self {
+            tokens,
+            bound_after_star,
+        })
+    }
+
+    /// The shortest and longest lengths of the words the pattern matches,
+    /// the longest being `None` if unbounded (with a `*`)
+    pub fn length_bounds(&self) -> (usize, Option<usize>) {
+        let fixed = self
+            .tokens
+            .iter()
+            .filter(|&&token| token != Token::AnyLetters)
+            .count();
+        let unbounded = self.tokens.contains(&Token::AnyLetters);
+
+        (fixed, (!unbounded).then_some(fixed))
+    }
+
+    /// Whether the whole word matches the pattern, ignoring case
+    pub fn matches(&self, word: &str) -> bool {
+        if self.bound_after_star {
+            match_bound(&self.tokens, word.as_bytes(), [None; 26])
+        } else {
+            match_tokens(&self.tokens, word.as_bytes())
+        }
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = CountError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// Displays the pattern as parsed, e.g. `*XX*`.
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for token in self.tokens.iter() {
+            let c = match *token {
+                Token::Letter(letter) => letter,
+                Token::Placeholder(p) => b'A' + p,
+                Token::AnyLetter => b'_',
+                Token::AnyLetters => b'*',
+            };
+            write!(f, "{}", c as char)?;
+        }
+
+        Ok(())
+    }
+}
+
+// matches the patterns without a placeholder after a `*`, by giving the last
+// `*` one more letter on a mismatch. The placeholders are bound before the
+// first `*`, so letting a `*` take more never changes their bindings; the
+// other patterns go through `match_bound`, which can undo them
+fn match_tokens(tokens: &[Token], word: &[u8]) -> bool {
+    let mut bindings: Bindings = [None; 26];
+    let (mut t, mut w) = (0, 0);
+    // the tokens after the last `*`, and where in the word to try them next
+    let mut star = None;
+    while w < word.len() {
+        let c = fold_case(word[w]);
+        let matched = match tokens.get(t) {
+            Some(Token::AnyLetters) => {
+                t += 1;
+                star = Some((t, w));
+                continue;
+            }
+            Some(&Token::Letter(letter)) => letter == c,
+            Some(&Token::Placeholder(p)) => *bindings[p as usize].get_or_insert(c) == c,
+            Some(Token::AnyLetter) => true,
+            None => false,
+        };
+        if matched {
+            t += 1;
+            w += 1;
+            continue;
+        }
+
+        match star {
+            // the `*` takes one more letter
+            Some((star_t, star_w)) => {
+                star = Some((star_t, star_w + 1));
+                (t, w) = (star_t, star_w + 1);
+            }
+            None => return false,
+        }
+    }
+
+    tokens[t..].iter().all(|&token| token == Token::AnyLetters)
+}
+
+// binds the placeholders as it goes, backtracking on every `*`: a letter an
+// earlier `*` takes may bind a placeholder after it otherwise
+fn match_bound(tokens: &[Token], word: &[u8], mut bindings: Bindings) -> bool {
+    let (token, tokens) = match tokens.split_first() {
+        Some(split) => split,
+        None => return word.is_empty(),
+    };
+
+    if *token == Token::AnyLetters {
+        return (0..=word.len()).any(|skip| match_bound(tokens, &word[skip..], bindings));
+    }
+
+    let (c, word) = match word.split_first() {
+        Some((&c, word)) => (fold_case(c), word),
+        None => return false,
+    };
+    let matched = match *token {
+        Token::Letter(letter) => letter == c,
+        Token::Placeholder(p) => *bindings[p as usize].get_or_insert(c) == c,
+        Token::AnyLetter => true,
+        Token::AnyLetters => unreachable!(),
+    };
+
+    matched && match_bound(tokens, word, bindings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, word: &str) -> bool {
+        Pattern::parse(pattern).unwrap().matches(word)
+    }
+
+    #[test]
+    fn placeholders() {
+        assert!(matches("*XX*", "committee"));
+        assert!(matches("__XX_YYZZ", "committee"));
+        assert!(matches("c*XX", "committee"));
+        assert!(!matches("__XX_XXZZ", "committee"));
+        assert!(!matches("*XYX*", "committee"));
+        assert!(matches("*XYX*", "banana"));
+        // different placeholders may stand for the same letter
+        assert!(matches("XY", "ee"));
+        // a placeholder after a `*` binds to a letter an earlier `*` skips
+        assert!(matches("*X*X", "abcb"));
+        assert!(matches("XY*X", "abxa"));
+    }
+
+    #[test]
+    fn wildcards() {
+        assert!(matches("c_t", "cat"));
+        assert!(matches("c_t", "CAT"));
+        assert!(!matches("c_t", "cart"));
+        assert!(matches("c*t", "cart"));
+        assert!(matches("*", ""));
+        assert!(!matches("_", ""));
+        assert!(matches("c*t*", "cattle"));
+        assert!(!matches("a*a*a*a*a*a*a*a*b", &"a".repeat(60)));
+    }
+
+    #[test]
+    fn length_bounds() {
+        let bounds = |pattern| Pattern::parse(pattern).unwrap().length_bounds();
+        assert_eq!(bounds("c_tXX"), (5, Some(5)));
+        assert_eq!(bounds("c*t*"), (2, None));
+        assert_eq!(bounds("*"), (0, None));
+    }
+
+    #[test]
+    fn display() {
+        let pattern = Pattern::parse("c_*XX").unwrap();
+        assert_eq!(pattern.to_string(), "c_*XX");
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Pattern::parse("c?t"), Err(CountError::NotAlphabetic));
+        assert_eq!(Pattern::parse("ça"), Err(CountError::NotAscii));
+    }
+}
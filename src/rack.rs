@@ -0,0 +1,326 @@
+//! Rack (available letters) parsing and matching
+
+use crate::count::{
+    letter_index, normalize_letters, validate_letters, CountError, CountSet, NormalizeOptions,
+};
+use std::fmt;
+
+/// Options controlling how a rack is parsed from user input.
+#[derive(Clone, Debug)]
+pub struct RackOptions {
+    /// Characters standing for a blank tile, which can be any letter
+    pub wildcards: Vec<char>,
+    /// Characters that are ignored, allowing inputs like `a, b, c`
+    pub separators: Vec<char>,
+}
+
+impl Default for RackOptions {
+    fn default() -> Self {
+        Self {
+            wildcards: vec!['?', '*'],
+            separators: vec![' ', ',', '-'],
+        }
+    }
+}
+
+/// The letters available in a word puzzle.
+///
+/// Unlike `CountSet`, a rack may contain any number of instances of a letter,
+/// as well as blank tiles. Letters are case-insensitive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rack {
+    letters: [u16; 26],
+    blanks: u16,
+    // bit mask of the letters the rack has, as `CountSet::presence_mask`
+    presence: u32,
+    // `letter_counts`, computed once rather than for every entry searched
+    counts: CountSet,
+    input: String,
+}
+
+impl Rack {
+    fn new(letters: [u16; 26], blanks: u16, input: String) -> Self {
+        let presence = letters
+            .iter()
+            .enumerate()
+            .fold(0, |mask, (i, &have)| mask | ((have != 0) as u32) << i);
+        let counts = letters.map(|have| have.min(15) as u8).try_into().unwrap();
+        Self {
+            letters,
+            blanks,
+            presence,
+            counts,
+            input,
+        }
+    }
+
+    pub fn parse(input: &str, options: &RackOptions) -> Result<Self, CountError> {
+        let normalized = normalize_letters(
+            input,
+            &NormalizeOptions {
+                separators: options.separators.clone(),
+                ..NormalizeOptions::default()
+            },
+        );
+        let (wildcards, letters) = normalized
+            .chars()
+            .partition::<String, _>(|c| options.wildcards.contains(c));
+        validate_letters(&letters)?;
+
+        let mut counts = [0u16; 26];
+        for b in letters.bytes() {
+            let count = &mut counts[letter_index(b)];
+            *count = count.checked_add(1).ok_or(CountError::CountOverflow)?;
+        }
+        let blanks = u16::try_from(wildcards.len()).map_err(|_| CountError::CountOverflow)?;
+        let letters = counts;
+
+        Ok(Self::new(letters, blanks, input.to_owned()))
+    }
+
+    /// A rack of exactly these letters, without blanks, as when the rack is
+    /// given as letter counts rather than parsed from a string
+    pub fn from_counts(counts: &CountSet) -> Self {
+        let mut letters = [0u16; 26];
+        for (have, count) in letters.iter_mut().zip(counts.iter()) {
+            *have = count as u16;
+        }
+
+        let mut rack = Self::new(letters, 0, String::new());
+        rack.input = rack.to_string();
+
+        rack
+    }
+
+    /// Count of each letter, from A to Z
+    #[inline]
+    pub fn letters(&self) -> &[u16; 26] {
+        &self.letters
+    }
+
+    /// The letters of the rack as a `CountSet`, without the blanks and with
+    /// at most 15 of each letter
+    pub fn letter_counts(&self) -> CountSet {
+        self.counts.clone()
+    }
+
+    // `letter_counts` without a copy, for testing every entry of a search
+    #[inline]
+    pub(crate) fn counts(&self) -> &CountSet {
+        &self.counts
+    }
+
+    #[inline]
+    pub fn blanks(&self) -> u16 {
+        self.blanks
+    }
+
+    /// The text this rack was parsed from
+    #[inline]
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// Whether a word with the given letter counts can be made from this rack,
+    /// using blanks for any missing letters.
+    pub fn contains_word(&self, word: &CountSet) -> bool {
+        // each letter the rack lacks takes a blank at least, so without
+        // blanks one AND rejects the words with any of them
+        let lacking = word.presence_mask() & !self.presence;
+        if lacking != 0 && lacking.count_ones() > self.blanks as u32 {
+            return false;
+        }
+
+        let mut missing = 0;
+        for (&have, need) in self.letters.iter().zip(word.iter()) {
+            missing += (need as u16).saturating_sub(have);
+            if missing > self.blanks {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Number of letters of the rack a word uses, not counting the letters
+    /// that have to be made with blanks.
+    pub fn letters_used(&self, word: &CountSet) -> usize {
+        self.letters
+            .iter()
+            .zip(word.iter())
+            .map(|(&have, need)| have.min(need as u16) as usize)
+            .sum()
+    }
+
+    /// This rack with each letter capped at its count in `bag`, e.g. the
+    /// tiles of a game; blanks are kept.
+    pub fn limited_to(&self, bag: &CountSet) -> Self {
+        let mut letters = self.letters;
+        for (have, limit) in letters.iter_mut().zip(bag.iter()) {
+            *have = (*have).min(limit as u16);
+        }
+
+        let mut rack = Self::new(letters, self.blanks, String::new());
+        rack.input = rack.to_string();
+
+        rack
+    }
+
+    /// This rack with every letter it has available any number of times, so
+    /// that it only limits which letters a word uses, not how many of each.
+    /// A word holds at most 15 of a letter, so that many stand for any
+    /// number; blanks still make one letter each.
+    pub fn with_unlimited_repeats(&self) -> Self {
+        let mut letters = self.letters;
+        for have in letters.iter_mut().filter(|have| **have > 0) {
+            *have = (*have).max(15);
+        }
+
+        Self::new(letters, self.blanks, self.input.clone())
+    }
+
+    /// The rack left over after making a word, or `None` if the word cannot
+    /// be made from this rack.
+    pub fn remaining_after(&self, word: &CountSet) -> Option<Self> {
+        let mut letters = self.letters;
+        let mut blanks = self.blanks;
+        for (have, need) in letters.iter_mut().zip(word.iter()) {
+            let need = need as u16;
+            if need > *have {
+                blanks = blanks.checked_sub(need - *have)?;
+                *have = 0;
+            } else {
+                *have -= need;
+            }
+        }
+
+        let mut rack = Self::new(letters, blanks, String::new());
+        rack.input = rack.to_string();
+
+        Some(rack)
+    }
+}
+
+/// Displays the letters in alphabetical order, followed by a `?` per blank.
+impl fmt::Display for Rack {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, &count) in self.letters.iter().enumerate() {
+            for _ in 0..count {
+                write!(f, "{}", (b'a' + i as u8) as char)?;
+            }
+        }
+
+        for _ in 0..self.blanks {
+            write!(f, "?")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rack(input: &str) -> Rack {
+        Rack::parse(input, &RackOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        let parsed = rack("Hello, W?rld");
+        assert_eq!(parsed.blanks(), 1);
+        assert_eq!(parsed.letters()[(b'l' - b'a') as usize], 3);
+        assert_eq!(parsed.input(), "Hello, W?rld");
+        assert_eq!(parsed.to_string(), "dehlllorw?");
+
+        // more than 15 of a letter is fine for a rack
+        assert_eq!(rack(&"a".repeat(20)).letters()[0], 20);
+
+        match Rack::parse("abc1", &RackOptions::default()).unwrap_err() {
+            CountError::NotAlphabetic => {}
+            err => panic!("Wrong 'not_alphabetic' error! {:?}", err),
+        }
+    }
+
+    #[test]
+    fn from_counts() {
+        let counts = "e2,a1,r1,t1".parse::<CountSet>().unwrap();
+        let counted = Rack::from_counts(&counts);
+        assert_eq!(counted.letters(), rack("treea").letters());
+        assert_eq!(counted.blanks(), 0);
+        assert_eq!(counted.input(), "aeert");
+        assert_eq!(counted.letter_counts(), counts);
+    }
+
+    #[test]
+    fn contains_word() {
+        let word = |w| CountSet::from_word(w).unwrap();
+
+        assert!(rack("retains").contains_word(&word("stainer")));
+        assert!(!rack("retains").contains_word(&word("strainer")));
+        assert!(rack("retains?").contains_word(&word("strainer")));
+        assert!(!rack("?").contains_word(&word("ab")));
+        assert!(rack("??").contains_word(&word("ab")));
+
+        // letters missing from the rack, each taking a blank
+        assert!(!rack("ab").contains_word(&word("abz")));
+        assert!(rack("ab?").contains_word(&word("abz")));
+        assert!(!rack("ab?").contains_word(&word("abzy")));
+        assert!(rack("ab??").contains_word(&word("abzy")));
+        // a missing letter and a missing copy of another
+        assert!(!rack("ab?").contains_word(&word("abbz")));
+        // letters the bag has none of are missing from the rack
+        let bag = CountSet::parse_multiset("a:1").unwrap();
+        assert!(!rack("ab").limited_to(&bag).contains_word(&word("b")));
+    }
+
+    #[test]
+    fn letters_used() {
+        let word = |w| CountSet::from_word(w).unwrap();
+
+        assert_eq!(rack("retains").letters_used(&word("stainer")), 7);
+        // the second r is a blank
+        assert_eq!(rack("retains?").letters_used(&word("strainer")), 7);
+        assert_eq!(rack("ab??").letters_used(&word("abba")), 2);
+        assert_eq!(rack("xyz").letters_used(&word("abc")), 0);
+    }
+
+    #[test]
+    fn limited_to() {
+        let bag = CountSet::parse_multiset("a:2, b:1, z:1").unwrap();
+
+        assert_eq!(rack("aaabbz?").limited_to(&bag).to_string(), "aabz?");
+        assert_eq!(rack("cab").limited_to(&bag).to_string(), "ab");
+    }
+
+    #[test]
+    fn with_unlimited_repeats() {
+        let word = |w| CountSet::from_word(w).unwrap();
+
+        let unlimited = rack("tes").with_unlimited_repeats();
+        assert!(unlimited.contains_word(&word("sestets")));
+        assert!(!rack("tes").contains_word(&word("sestets")));
+        assert!(!unlimited.contains_word(&word("taste")));
+        assert_eq!(unlimited.input(), "tes");
+
+        // a blank still makes a single letter
+        let unlimited = rack("tes?").with_unlimited_repeats();
+        assert!(unlimited.contains_word(&word("taste")));
+        assert!(!unlimited.contains_word(&word("tastier")));
+        assert_eq!(unlimited.blanks(), 1);
+    }
+
+    #[test]
+    fn remaining_after() {
+        let word = |w| CountSet::from_word(w).unwrap();
+
+        let remaining = rack("retains?").remaining_after(&word("tin")).unwrap();
+        assert_eq!(remaining.to_string(), "aers?");
+
+        let remaining = rack("ab?").remaining_after(&word("abc")).unwrap();
+        assert_eq!(remaining.to_string(), "");
+
+        assert!(rack("ab").remaining_after(&word("abc")).is_none());
+    }
+}
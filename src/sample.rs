@@ -0,0 +1,37 @@
+//! A small English dictionary for tests and examples
+//!
+//! The word list, `tests/data/words.txt`, is a few thousand common words
+//! compiled for this project and under the same license.
+
+use crate::dict::Dictionary;
+use crate::format::write_dict;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// The sample word list, one lowercase word per line
+pub const WORDS: &str = include_str!("../tests/data/words.txt");
+
+/// The sample word list as a dictionary
+pub fn dictionary() -> Dictionary {
+    WORDS.lines().collect()
+}
+
+/// Writes the sample dictionary file in the current format, e.g. for running
+/// the command line on it.
+pub fn write_dictionary(path: &Path) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    write_dict(&dictionary(), &mut file)?;
+    file.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn words_are_valid() {
+        assert_eq!(dictionary().len(), WORDS.lines().count());
+        assert!(dictionary().len() > 2000);
+    }
+}
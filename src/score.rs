@@ -0,0 +1,116 @@
+//! Word scoring
+
+use crate::count::CountSet;
+use crate::rack::Rack;
+
+/// Points of each letter in English Scrabble, from A to Z
+pub const LETTER_SCORES: [u8; 26] = [
+    1, 3, 3, 2, 1, 4, 2, 4, 1, 8, 5, 1, 3, 1, 1, 3, 10, 1, 1, 1, 1, 4, 4, 8, 4, 10,
+];
+
+/// Scrabble score of a word with the given letter counts
+pub fn score(word: &CountSet) -> u32 {
+    word.iter()
+        .zip(LETTER_SCORES.iter())
+        .map(|(count, &points)| count as u32 * points as u32)
+        .sum()
+}
+
+/// Scrabble score of a word made from a rack, where letters missing from the
+/// rack are made with blanks, which are worth nothing.
+pub fn rack_score(rack: &Rack, word: &CountSet) -> u32 {
+    word.iter()
+        .zip(rack.letters().iter())
+        .zip(LETTER_SCORES.iter())
+        .map(|((need, &have), &points)| (need as u16).min(have) as u32 * points as u32)
+        .sum()
+}
+
+/// Rarity of each letter, from A to Z: 12 divided by its number of tiles in
+/// an English Scrabble set (`compare::TILE_DISTRIBUTION`), so E is 1 and the
+/// letters with a single tile 12.
+pub const LETTER_RARITY: [u8; 26] = [
+    1, 6, 6, 3, 1, 6, 4, 6, 1, 12, 12, 3, 6, 2, 1, 6, 12, 2, 3, 2, 3, 6, 6, 12, 6, 12,
+];
+
+/// Rarity of a word with the given letter counts, the sum of the rarity of
+/// its letters
+pub fn rarity(word: &CountSet) -> u32 {
+    word.iter()
+        .zip(LETTER_RARITY.iter())
+        .map(|(count, &rarity)| count as u32 * rarity as u32)
+        .sum()
+}
+
+/// A way of scoring the words found from a rack, for `--sort score`.
+///
+/// Implement it for the rules of games this crate doesn't know; `counts` are
+/// the letter counts of `word`.
+pub trait Scorer: Sync {
+    fn score(&self, word: &str, counts: &CountSet, rack: &Rack) -> i64;
+}
+
+/// Scores words with `rack_score`: their Scrabble score, blanks scoring 0
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ScrabbleScorer;
+
+impl Scorer for ScrabbleScorer {
+    fn score(&self, _: &str, counts: &CountSet, rack: &Rack) -> i64 {
+        rack_score(rack, counts) as i64
+    }
+}
+
+/// Scores words with `rarity`, whatever the rack
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RarityScorer;
+
+impl Scorer for RarityScorer {
+    fn score(&self, _: &str, counts: &CountSet, _: &Rack) -> i64 {
+        rarity(counts) as i64
+    }
+}
+
+/// The built-in scorers, by name
+pub const SCORERS: [(&str, &dyn Scorer); 2] =
+    [("scrabble", &ScrabbleScorer), ("rarity", &RarityScorer)];
+
+/// The built-in scorer with this name
+pub fn scorer(name: &str) -> Option<&'static dyn Scorer> {
+    SCORERS
+        .iter()
+        .find(|&&(scorer, _)| scorer == name)
+        .map(|&(_, scorer)| scorer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rack::RackOptions;
+
+    #[test]
+    fn scores() {
+        let word = |w| CountSet::from_word(w).unwrap();
+        assert_eq!(score(&word("quiz")), 22);
+        assert_eq!(score(&word("")), 0);
+
+        let rack = Rack::parse("qui?", &RackOptions::default()).unwrap();
+        assert_eq!(rack_score(&rack, &word("quiz")), 12);
+    }
+
+    #[test]
+    fn scorers() {
+        let counts = CountSet::from_word("quiz").unwrap();
+        let rack = Rack::parse("qui?", &RackOptions::default()).unwrap();
+        let score = |name| scorer(name).unwrap().score("quiz", &counts, &rack);
+        assert_eq!(score("scrabble"), 12);
+        assert_eq!(score("rarity"), 12 + 3 + 1 + 12);
+        assert!(scorer("Scrabble").is_none());
+    }
+
+    #[test]
+    fn letter_rarity() {
+        for (rarity, tiles) in LETTER_RARITY.iter().zip(crate::compare::TILE_DISTRIBUTION) {
+            assert_eq!(*rarity, 12 / tiles);
+        }
+    }
+}
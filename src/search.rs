@@ -0,0 +1,1410 @@
+//! Word searching
+
+use crate::count::CountSet;
+use crate::dict::{Dictionary, DictionaryEntry, OffsetLength};
+use crate::fold::{fold_word, starts_with_folded};
+use crate::index::Indexes;
+use crate::par::*;
+use crate::pattern::Pattern;
+use crate::rack::{Rack, RackOptions};
+use crate::score::{rack_score, Scorer};
+use crate::stem::variant_groups;
+use crate::trace::{event, span};
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+/// Length of the longest possible word, with 15 of every letter
+pub const MAX_WORD_LENGTH: usize = 26 * 15;
+
+/// Minimum word length of command line searches when neither the command
+/// line nor the dictionary sets one
+pub const DEFAULT_MIN_LENGTH: usize = 3;
+
+/// Suggested search options stored in a dictionary file, used when the
+/// command line doesn't set them
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchDefaults {
+    pub min_length: Option<usize>,
+}
+
+/// Where the value of a search option came from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OptionOrigin {
+    CommandLine,
+    Dictionary,
+    BuiltIn,
+}
+
+impl SearchDefaults {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The minimum length to search with: the command line value, else the
+    /// dictionary default, else `DEFAULT_MIN_LENGTH`. A dictionary default of
+    /// 0, which `generate` refuses but a file can hold, counts as 1, so that
+    /// an empty word kept by `generate --keep-blank` never matches.
+    pub fn min_length(&self, command_line: Option<usize>) -> (usize, OptionOrigin) {
+        match (command_line, self.min_length) {
+            (Some(min_length), _) => (min_length, OptionOrigin::CommandLine),
+            (None, Some(min_length)) => (min_length.max(1), OptionOrigin::Dictionary),
+            (None, None) => (DEFAULT_MIN_LENGTH, OptionOrigin::BuiltIn),
+        }
+    }
+}
+
+/// A search for words that can be made from a rack.
+#[derive(Clone, Debug)]
+pub struct SearchQuery {
+    pub rack: Rack,
+    /// Minimum length of the words
+    pub min_length: usize,
+    /// Maximum length of the words
+    pub max_length: Option<usize>,
+    /// Letters that every word must contain
+    pub require: CountSet,
+    /// Text that every word must start with, as spelled in the dictionary
+    /// (see `Dictionary::add_spelling`)
+    pub starts_with: String,
+    /// Minimum score per letter of the words
+    pub min_efficiency: Option<f64>,
+    /// Index of the word list the words must come from
+    pub source: Option<u16>,
+    /// Search for the words that can *not* be made from the rack instead,
+    /// keeping every other filter
+    pub invert: bool,
+    /// Pattern that every word must match as spelled in the dictionary,
+    /// checked after the rack
+    pub pattern: Option<Pattern>,
+    /// Leave out the words derived from other words, such as inflections
+    pub exclude_derived: bool,
+    /// Highest difficulty tier of the words; no word matches in
+    /// dictionaries without tiers
+    pub max_tier: Option<u8>,
+    /// Exact difficulty tier of the words; no word matches in dictionaries
+    /// without tiers
+    pub tier: Option<u8>,
+    /// Maximum number of different letters in the words
+    pub max_unique: Option<usize>,
+    /// Minimum number of tiles of the rack the words use, blanks included
+    pub min_use: Option<usize>,
+    /// Number of times over the words must be makeable from the letters of
+    /// the rack at once, blanks aside (see `CountSet::scaled_fits`)
+    pub multiplicity: Option<u8>,
+}
+
+/// How a search went through the dictionary.
+#[derive(Clone, Debug)]
+pub enum SearchPlan {
+    /// Every entry was tested
+    Scan { entries: usize },
+    /// Only the entries containing every required letter were tested
+    LetterIndex {
+        letters: CountSet,
+        candidates: usize,
+    },
+    /// Only the entries starting with the prefix were tested
+    SortedIndex { prefix: String, candidates: usize },
+}
+
+/// Orders in which the results of a search can be listed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Alphabetical order, in bytes: uppercase letters before lowercase
+    /// ones (see `fold`)
+    Alpha,
+    /// Most rack letters used first, then alphabetical order
+    Consumed,
+}
+
+impl FromStr for SortOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "alpha" => Ok(SortOrder::Alpha),
+            "consumed" => Ok(SortOrder::Consumed),
+            _ => Err(format!(
+                "unknown sort order {:?} (expected alpha or consumed)",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for SortOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SortOrder::Alpha => "alpha",
+            SortOrder::Consumed => "consumed",
+        })
+    }
+}
+
+/// Displays every field of the query as `name=value` pairs separated by `;`,
+/// with empty values for unset options, e.g.
+/// `rack=aest?;min_length=3;max_length=;...`; parsed back by `FromStr`.
+impl fmt::Display for SearchQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn optional<T: ToString>(value: &Option<T>) -> String {
+            value.as_ref().map_or_else(String::new, T::to_string)
+        }
+
+        write!(
+            f,
+            "rack={};min_length={};max_length={};require={};starts_with={};\
+             min_efficiency={};source={};invert={};pattern={};exclude_derived={};\
+             max_tier={};tier={};max_unique={};min_use={};multiplicity={}",
+            self.rack,
+            self.min_length,
+            optional(&self.max_length),
+            self.require,
+            self.starts_with,
+            optional(&self.min_efficiency),
+            optional(&self.source),
+            self.invert,
+            optional(&self.pattern),
+            self.exclude_derived,
+            optional(&self.max_tier),
+            optional(&self.tier),
+            optional(&self.max_unique),
+            optional(&self.min_use),
+            optional(&self.multiplicity)
+        )
+    }
+}
+
+/// Parses the form written by `Display`; missing fields keep the values of
+/// `SearchQuery::new`.
+impl FromStr for SearchQuery {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        fn value<T: FromStr>(name: &str, value: &str) -> Result<T, String> {
+            value
+                .parse()
+                .map_err(|_| format!("invalid query field {}={:?}", name, value))
+        }
+        fn optional<T: FromStr>(name: &str, v: &str) -> Result<Option<T>, String> {
+            if v.is_empty() {
+                Ok(None)
+            } else {
+                value(name, v).map(Some)
+            }
+        }
+
+        let mut query = SearchQuery::new(Rack::parse("", &RackOptions::default()).unwrap());
+        for field in s.split(';').filter(|field| !field.is_empty()) {
+            let (name, v) = field
+                .split_once('=')
+                .ok_or_else(|| format!("invalid query field {:?}, expected name=value", field))?;
+
+            match name {
+                "rack" => {
+                    query.rack = Rack::parse(v, &RackOptions::default())
+                        .map_err(|err| format!("invalid query field rack={:?}: {}", v, err))?
+                }
+                "min_length" => query.min_length = value(name, v)?,
+                "max_length" => query.max_length = optional(name, v)?,
+                "require" => {
+                    query.require = CountSet::from_word(v)
+                        .map_err(|err| format!("invalid query field require={:?}: {}", v, err))?
+                }
+                "starts_with" => query.starts_with = v.to_owned(),
+                "min_efficiency" => query.min_efficiency = optional(name, v)?,
+                "source" => query.source = optional(name, v)?,
+                "invert" => query.invert = value(name, v)?,
+                "pattern" => query.pattern = optional(name, v)?,
+                "exclude_derived" => query.exclude_derived = value(name, v)?,
+                "max_tier" => query.max_tier = optional(name, v)?,
+                "tier" => query.tier = optional(name, v)?,
+                "max_unique" => query.max_unique = optional(name, v)?,
+                "min_use" => query.min_use = optional(name, v)?,
+                "multiplicity" => query.multiplicity = optional(name, v)?,
+                _ => return Err(format!("unknown query field {:?}", name)),
+            }
+        }
+
+        Ok(query)
+    }
+}
+
+pub struct SearchResults<'a> {
+    /// The matching words, sorted alphabetically unless re-sorted with `sort`
+    pub words: Vec<&'a str>,
+    /// Number of rack letters used by each word, blanks excluded, in the
+    /// order of `words`
+    pub letters_used: Vec<usize>,
+    /// Letter counts of each word as stored in the dictionary, in the order
+    /// of `words`: a word kept in another spelling, such as `h3llo` (see
+    /// `Dictionary::add_spelling`), has the counts of its letters
+    pub count_sets: Vec<&'a CountSet>,
+    pub plan: SearchPlan,
+    /// Bit mask of the letters used by at least one match (bit 0 for A)
+    pub used_letters: u32,
+}
+
+impl SearchQuery {
+    pub fn new(rack: Rack) -> Self {
+        Self {
+            rack,
+            min_length: 1,
+            max_length: None,
+            require: CountSet::default(),
+            starts_with: String::new(),
+            min_efficiency: None,
+            source: None,
+            invert: false,
+            pattern: None,
+            exclude_derived: false,
+            max_tier: None,
+            tier: None,
+            max_unique: None,
+            min_use: None,
+            multiplicity: None,
+        }
+    }
+
+    /// Whether the query narrows down the words other than with the rack
+    pub fn has_filters(&self) -> bool {
+        self.max_length.is_some()
+            || self.require != CountSet::default()
+            || !self.starts_with.is_empty()
+            || self.min_efficiency.is_some()
+            || self.source.is_some()
+            || self.pattern.is_some()
+            || self.exclude_derived
+            || self.max_tier.is_some()
+            || self.tier.is_some()
+            || self.max_unique.is_some()
+            || self.min_use.is_some()
+            || self.multiplicity.is_some()
+    }
+
+    pub fn matches(&self, entry: &DictionaryEntry) -> bool {
+        self.matches_filters(entry) && self.matches_rack(&self.rack, entry)
+    }
+
+    // the conditions that don't depend on the rack, tested once per entry
+    // for every rack of `search_batch`
+    fn matches_filters(&self, entry: &DictionaryEntry) -> bool {
+        (self.source.is_none() || entry.source == self.source)
+            && !(self.exclude_derived && entry.derived)
+            && self
+                .max_tier
+                .is_none_or(|max| entry.tier().is_some_and(|tier| tier <= max))
+            && self.tier.is_none_or(|tier| entry.tier() == Some(tier))
+            && entry.len() >= self.min_length
+            && self.max_length.is_none_or(|max| entry.len() <= max)
+            && self
+                .max_unique
+                .is_none_or(|max| entry.count_set.distinct() <= max)
+            // a word made from the rack uses one tile per letter
+            && self
+                .min_use
+                .is_none_or(|min| entry.count_set.total() >= min)
+            && starts_with_folded(entry.word, &self.starts_with)
+            && entry.count_set.contains(&self.require)
+            && self
+                .pattern
+                .as_ref()
+                .is_none_or(|pattern| pattern.matches(entry.word))
+    }
+
+    fn matches_rack(&self, rack: &Rack, entry: &DictionaryEntry) -> bool {
+        self.multiplicity
+            .is_none_or(|k| entry.count_set.scaled_fits(k, rack.counts()))
+            && rack.contains_word(entry.count_set) != self.invert
+            && self.min_efficiency.is_none_or(|min| {
+                rack_score(rack, entry.count_set) as f64 / entry.len() as f64 >= min
+            })
+    }
+
+    /// Searches the dictionary like `search`, pairing each word with the
+    /// letters of the rack it leaves unused (blanks aside), which can be
+    /// searched in turn.
+    pub fn search_with_leftovers<'a>(
+        &self,
+        dict: &'a Dictionary,
+        indexes: &Indexes,
+    ) -> Vec<(&'a str, CountSet)> {
+        let results = self.search(dict, indexes);
+        let leftovers = results.leftovers(&self.rack);
+        results.words.into_iter().zip(leftovers).collect()
+    }
+
+    /// Searches the dictionary, using the available indexes to narrow down
+    /// the candidates: the sorted index if the query has a prefix, otherwise
+    /// the letter index if the query requires some letters.
+    pub fn search<'a>(&self, dict: &'a Dictionary, indexes: &Indexes) -> SearchResults<'a> {
+        span!("search", rack = %self.rack);
+        let (matches, plan) = match self.candidates(dict, indexes) {
+            Some((candidates, plan)) => (
+                collect_matches(
+                    &self.rack,
+                    candidates
+                        .par_iter()
+                        .filter_map(|key| dict.get(key))
+                        .filter(|entry| self.matches(entry)),
+                ),
+                plan,
+            ),
+            None => (
+                collect_matches(
+                    &self.rack,
+                    dict.par_iter().filter(|entry| self.matches(entry)),
+                ),
+                SearchPlan::Scan {
+                    entries: dict.len(),
+                },
+            ),
+        };
+
+        let (mut matches, used_letters) = matches;
+        matches.par_sort_unstable();
+        let mut results = SearchResults {
+            words: Vec::new(),
+            letters_used: Vec::new(),
+            count_sets: Vec::new(),
+            plan,
+            used_letters,
+        };
+        results.set_matches(matches);
+        event!(matches = results.words.len(), plan = %results.plan, "search done");
+        results
+    }
+
+    /// The first `k` words of the search in `order`, as with `search` then
+    /// `sort` and `page(0, Some(k))`, along with the number of matches, but
+    /// without collecting and sorting every match: the matches go through
+    /// heaps of the best `k`, one per thread, which are then merged.
+    pub fn search_top<'a>(
+        &self,
+        dict: &'a Dictionary,
+        indexes: &Indexes,
+        order: SortOrder,
+        k: usize,
+    ) -> (SearchResults<'a>, usize) {
+        span!("search_top", rack = %self.rack, k);
+        let (top, plan) = match self.candidates(dict, indexes) {
+            Some((candidates, plan)) => (
+                select_top(
+                    &self.rack,
+                    order,
+                    k,
+                    candidates
+                        .par_iter()
+                        .filter_map(|key| dict.get(key))
+                        .filter(|entry| self.matches(entry)),
+                ),
+                plan,
+            ),
+            None => (
+                select_top(
+                    &self.rack,
+                    order,
+                    k,
+                    dict.par_iter().filter(|entry| self.matches(entry)),
+                ),
+                SearchPlan::Scan {
+                    entries: dict.len(),
+                },
+            ),
+        };
+
+        let (heap, used_letters, found) = top;
+        let mut results = SearchResults {
+            words: Vec::new(),
+            letters_used: Vec::new(),
+            count_sets: Vec::new(),
+            plan,
+            used_letters,
+        };
+        results.set_matches(heap.into_sorted_vec().into_iter().map(|(_, m)| m));
+        event!(matches = found, plan = %results.plan, "search done");
+        (results, found)
+    }
+
+    /// The words of each rack, alphabetically, as if searching each in turn
+    /// with this query (whose own rack is ignored), but in a single pass over
+    /// the dictionary: the filters that don't depend on the rack are tested
+    /// once per entry rather than once per rack. Faster than a loop of
+    /// `search` for many racks (see `benches/count_set.rs`).
+    pub fn search_batch<'a>(&self, dict: &'a Dictionary, racks: &[CountSet]) -> Vec<Vec<&'a str>> {
+        let racks = racks.iter().map(Rack::from_counts).collect::<Vec<_>>();
+
+        let mut words = dict
+            .par_iter()
+            .filter(|entry| self.matches_filters(entry))
+            .fold_with(vec![Vec::new(); racks.len()], |mut words, entry| {
+                for (words, rack) in words.iter_mut().zip(racks.iter()) {
+                    if self.matches_rack(rack, &entry) {
+                        words.push(entry.word);
+                    }
+                }
+                words
+            })
+            .reduce_with(|mut words, other| {
+                for (words, other) in words.iter_mut().zip(other) {
+                    words.extend(other);
+                }
+                words
+            })
+            .unwrap_or_else(|| vec![Vec::new(); racks.len()]);
+
+        for words in words.iter_mut() {
+            words.par_sort_unstable();
+        }
+        words
+    }
+
+    /// The longest matching word, the first alphabetically of the longest
+    /// ones, found without collecting and sorting every match.
+    pub fn longest<'a>(&self, dict: &'a Dictionary, indexes: &Indexes) -> Option<&'a str> {
+        // words are unique, so the key picks a single word whatever the order
+        let key = |word: &&'a str| (word.len(), std::cmp::Reverse(*word));
+
+        match self.candidates(dict, indexes) {
+            Some((candidates, _)) => candidates
+                .par_iter()
+                .filter_map(|key| dict.get(key))
+                .filter(|entry| self.matches(entry))
+                .map(|entry| entry.word)
+                .max_by_key(key),
+            None => dict
+                .par_iter()
+                .filter(|entry| self.matches(entry))
+                .map(|entry| entry.word)
+                .max_by_key(key),
+        }
+    }
+
+    // the entries to test, from the sorted index if the query has a prefix,
+    // otherwise from the letter index if the query requires some letters, or
+    // `None` to test every entry
+    fn candidates<'i>(
+        &self,
+        dict: &Dictionary,
+        indexes: &'i Indexes,
+    ) -> Option<(Cow<'i, [OffsetLength]>, SearchPlan)> {
+        let prefix_candidates = indexes
+            .sorted
+            .as_ref()
+            .filter(|_| !self.starts_with.is_empty())
+            .map(|index| {
+                let candidates = index.prefix_range(dict, &self.starts_with);
+                let plan = SearchPlan::SortedIndex {
+                    prefix: self.starts_with.clone(),
+                    candidates: candidates.len(),
+                };
+                (Cow::Borrowed(candidates), plan)
+            });
+        let candidates = prefix_candidates.or_else(|| {
+            let candidates = indexes.letters.as_ref()?.candidates(&self.require)?;
+            let plan = SearchPlan::LetterIndex {
+                letters: self.require.clone(),
+                candidates: candidates.len(),
+            };
+            Some((Cow::Owned(candidates), plan))
+        });
+
+        candidates
+    }
+}
+
+impl<'a> SearchResults<'a> {
+    /// Reorders the words, keeping `letters_used` and `count_sets` in step.
+    pub fn sort(&mut self, order: SortOrder) {
+        if order == SortOrder::Alpha {
+            return;
+        }
+
+        // the words are already alphabetical, which a stable sort keeps for ties
+        let mut matches = self.take_matches();
+        matches.par_sort_by_key(|m| Reverse(m.used));
+        self.set_matches(matches);
+    }
+
+    /// Reorders the words by how many of the `priority` letters they have,
+    /// most first (each occurrence counts), then alphabetically.
+    pub fn prioritize(&mut self, priority: &CountSet) {
+        let mut matches = self
+            .take_matches()
+            .into_iter()
+            .map(|m| {
+                let key = m
+                    .count_set
+                    .iter()
+                    .zip(priority.iter())
+                    .filter(|&(_, p)| p > 0)
+                    .map(|(c, _)| c as usize)
+                    .sum::<usize>();
+                (key, m)
+            })
+            .collect::<Vec<_>>();
+        matches.par_sort_unstable_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+        self.set_matches(matches.into_iter().map(|(_, m)| m));
+    }
+
+    /// Reorders the words by their score with `scorer`, highest first, then
+    /// alphabetically.
+    pub fn sort_by_score(&mut self, scorer: &dyn Scorer, rack: &Rack) {
+        let mut matches = self
+            .take_matches()
+            .into_par_iter()
+            .map(|m| (scorer.score(m.word, m.count_set, rack), m))
+            .collect::<Vec<_>>();
+        matches.par_sort_unstable_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+        self.set_matches(matches.into_iter().map(|(_, m)| m));
+    }
+
+    /// Keeps the page of `limit` words (or every word if `None`) starting
+    /// at `offset`.
+    ///
+    /// Pages are only meaningful in a fixed order, so call `sort` first:
+    /// both orders break ties alphabetically, making the pages of repeated
+    /// searches consistent.
+    pub fn page(&mut self, offset: usize, limit: Option<usize>) {
+        let start = offset.min(self.words.len());
+        let end = limit.map_or(self.words.len(), |limit| {
+            start.saturating_add(limit).min(self.words.len())
+        });
+
+        self.words.truncate(end);
+        self.words.drain(..start);
+        self.letters_used.truncate(end);
+        self.letters_used.drain(..start);
+        self.count_sets.truncate(end);
+        self.count_sets.drain(..start);
+    }
+
+    /// Removes the words also found in `other`, ignoring case, such as the
+    /// words of a fallback dictionary already found in the primary one.
+    pub fn exclude(&mut self, other: &SearchResults) {
+        let found = other
+            .words
+            .iter()
+            .map(|word| fold_word(word))
+            .collect::<HashSet<_>>();
+        self.retain(|word| !found.contains(&fold_word(word)));
+    }
+
+    /// Keeps a single word of each group of variants sharing a stem (see
+    /// `stem::stem`), its shortest, in place of the first word of the group.
+    /// Returns the other variants of the words kept that have any, in the
+    /// order of `words`. `used_letters` still counts the variants.
+    pub fn collapse_variants(&mut self) -> HashMap<&'a str, Vec<&'a str>> {
+        let words = self.words.clone();
+        let mut matches = self
+            .take_matches()
+            .into_iter()
+            .map(|m| (m.word, m))
+            .collect::<HashMap<_, _>>();
+        let mut variants = HashMap::new();
+        let mut kept_matches = Vec::new();
+        for group in variant_groups(&words) {
+            // the first of the shortest words
+            let kept = *group.iter().min_by_key(|word| word.len()).unwrap();
+            kept_matches.push(matches.remove(kept).unwrap());
+            let others = group
+                .into_iter()
+                .filter(|&word| word != kept)
+                .collect::<Vec<_>>();
+            if !others.is_empty() {
+                variants.insert(kept, others);
+            }
+        }
+
+        self.set_matches(kept_matches);
+        variants
+    }
+
+    /// Keeps only the words for which `f` returns `true`, returning the
+    /// number of words removed.
+    pub fn retain<F: FnMut(&str) -> bool>(&mut self, mut f: F) -> usize {
+        let len = self.words.len();
+        let matches = self.take_matches();
+        self.set_matches(matches.into_iter().filter(|m| f(m.word)));
+        self.used_letters = self
+            .count_sets
+            .iter()
+            .fold(0, |mask, count_set| mask | count_set.presence_mask());
+
+        len - self.words.len()
+    }
+
+    /// Letters of the rack left unused by each word (blanks aside), in the
+    /// order of `words`
+    pub fn leftovers(&self, rack: &Rack) -> Vec<CountSet> {
+        let rack = rack.letter_counts();
+        self.count_sets
+            .par_iter()
+            .map(|count_set| rack.difference(count_set))
+            .collect()
+    }
+
+    /// How many times over each word can be made from the letters of the
+    /// rack at once, blanks aside (see `CountSet::max_multiplicity`)
+    pub fn multiplicities(&self, rack: &Rack) -> Vec<u8> {
+        self.count_sets
+            .par_iter()
+            .map(|count_set| count_set.max_multiplicity(rack.counts()))
+            .collect()
+    }
+
+    /// Groups the words by their letters, the anagrams of each other,
+    /// keyed by their letter counts in the dictionary. The groups and their
+    /// words keep the order of `words`, by their first word.
+    pub fn anagram_groups(&self) -> Vec<(&'a CountSet, Vec<&'a str>)> {
+        let mut groups: Vec<(&CountSet, Vec<&str>)> = Vec::new();
+        let mut positions: HashMap<&CountSet, usize> = HashMap::new();
+        for (&word, &key) in self.words.iter().zip(self.count_sets.iter()) {
+            match positions.get(key) {
+                Some(&i) => groups[i].1.push(word),
+                None => {
+                    positions.insert(key, groups.len());
+                    groups.push((key, vec![word]));
+                }
+            }
+        }
+        groups
+    }
+
+    /// Letters in the rack that don't appear in any of the matches
+    pub fn unusable_letters(&self, rack: &Rack) -> Vec<char> {
+        (0..26)
+            .filter(|&i| rack.letters()[i] > 0 && self.used_letters & (1 << i) == 0)
+            .map(|i| (b'a' + i as u8) as char)
+            .collect()
+    }
+
+    // the matches, leaving the results empty until `set_matches`
+    fn take_matches(&mut self) -> Vec<Match<'a>> {
+        self.words
+            .drain(..)
+            .zip(self.count_sets.drain(..))
+            .zip(self.letters_used.drain(..))
+            .map(|((word, count_set), used)| Match {
+                word,
+                count_set,
+                used,
+            })
+            .collect()
+    }
+
+    fn set_matches<I: IntoIterator<Item = Match<'a>>>(&mut self, matches: I) {
+        for m in matches {
+            self.words.push(m.word);
+            self.count_sets.push(m.count_set);
+            self.letters_used.push(m.used);
+        }
+    }
+}
+
+// a matching entry with the number of rack letters it uses, ordered by its
+// word alone, the words of a dictionary being unique
+#[derive(Clone)]
+struct Match<'a> {
+    word: &'a str,
+    count_set: &'a CountSet,
+    used: usize,
+}
+
+impl PartialEq for Match<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.word == other.word
+    }
+}
+
+impl Eq for Match<'_> {}
+
+impl PartialOrd for Match<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Match<'_> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.word.cmp(other.word)
+    }
+}
+
+type Matches<'a> = Vec<Match<'a>>;
+
+// collects the words of the entries with the number of rack letters they use,
+// folding the union of their letters along the way so that it doesn't take a
+// second pass
+fn collect_matches<'a, I>(rack: &Rack, entries: I) -> (Matches<'a>, u32)
+where
+    I: ParallelIterator<Item = DictionaryEntry<'a>>,
+{
+    entries
+        .fold_with((Vec::new(), 0), |(mut words, mask), entry| {
+            words.push(Match {
+                word: entry.word,
+                count_set: entry.count_set,
+                used: rack.letters_used(entry.count_set),
+            });
+            (words, mask | entry.count_set.presence_mask())
+        })
+        .reduce_with(|(mut words, mask), (other, other_mask)| {
+            words.extend(other);
+            (words, mask | other_mask)
+        })
+        .unwrap_or_default()
+}
+
+// a match keyed by its place in a sort order, then its word, which alone
+// decides between equal keys
+type Ranked<'a> = (Reverse<usize>, Match<'a>);
+
+// the best `k` matches in `order`, in a heap with the worst of them on top,
+// with the union of the letters of every match and the number of matches
+fn select_top<'a, I>(
+    rack: &Rack,
+    order: SortOrder,
+    k: usize,
+    entries: I,
+) -> (BinaryHeap<Ranked<'a>>, u32, usize)
+where
+    I: ParallelIterator<Item = DictionaryEntry<'a>>,
+{
+    let keep = |heap: &mut BinaryHeap<Ranked<'a>>, ranked: Ranked<'a>| {
+        if heap.len() < k {
+            heap.push(ranked);
+        } else if heap.peek().is_some_and(|worst| ranked < *worst) {
+            heap.pop();
+            heap.push(ranked);
+        }
+    };
+
+    entries
+        .fold_with(
+            (BinaryHeap::new(), 0, 0),
+            |(mut heap, mask, found), entry| {
+                let used = rack.letters_used(entry.count_set);
+                let rank = match order {
+                    SortOrder::Alpha => 0,
+                    SortOrder::Consumed => used,
+                };
+                let m = Match {
+                    word: entry.word,
+                    count_set: entry.count_set,
+                    used,
+                };
+                keep(&mut heap, (Reverse(rank), m));
+                (heap, mask | entry.count_set.presence_mask(), found + 1)
+            },
+        )
+        .reduce_with(
+            |(mut heap, mask, found), (other, other_mask, other_found)| {
+                for ranked in other {
+                    keep(&mut heap, ranked);
+                }
+                (heap, mask | other_mask, found + other_found)
+            },
+        )
+        .unwrap_or_default()
+}
+
+impl fmt::Display for SearchPlan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SearchPlan::Scan { entries } => {
+                write!(f, "scanned all {} entries", thousands(*entries))
+            }
+            SearchPlan::LetterIndex {
+                letters,
+                candidates,
+            } => {
+                write!(f, "used letter index: ")?;
+                for (i, c) in letters.iter().enumerate() {
+                    for _ in 0..c {
+                        write!(f, "{}", (b'a' + i as u8) as char)?;
+                    }
+                }
+                write!(f, " \u{2192} {} candidates", thousands(*candidates))
+            }
+            SearchPlan::SortedIndex { prefix, candidates } => write!(
+                f,
+                "used sorted index: {} \u{2192} {} candidates",
+                prefix,
+                thousands(*candidates)
+            ),
+        }
+    }
+}
+
+// formats a number with comma thousands separators, e.g. `2,113`
+fn thousands(n: usize) -> String {
+    let digits = n.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::{LetterIndex, SortedIndex};
+    use crate::rack::RackOptions;
+
+    #[test]
+    fn search() {
+        let mut dict = Dictionary::new();
+        for word in ["tan", "ant", "ants", "stain", "satin", "train", "quit"] {
+            dict.add(word).unwrap();
+        }
+        let indexes = Indexes {
+            letters: Some(LetterIndex::build(&dict)),
+            sorted: Some(SortedIndex::build(&dict)),
+        };
+
+        let rack = Rack::parse("satin?", &RackOptions::default()).unwrap();
+        let mut query = SearchQuery::new(rack);
+        query.min_length = 4;
+        query.require = CountSet::from_word("s").unwrap();
+
+        let scanned = query.search(&dict, &Indexes::default());
+        let indexed = query.search(&dict, &indexes);
+        assert_eq!(scanned.words, ["ants", "satin", "stain"]);
+        assert_eq!(indexed.words, scanned.words);
+        assert_eq!(scanned.plan.to_string(), "scanned all 7 entries");
+        assert_eq!(
+            indexed.plan.to_string(),
+            "used letter index: s \u{2192} 3 candidates"
+        );
+
+        query.starts_with = String::from("sta");
+        let scanned = query.search(&dict, &Indexes::default());
+        let indexed = query.search(&dict, &indexes);
+        assert_eq!(scanned.words, ["stain"]);
+        assert_eq!(indexed.words, scanned.words);
+        assert_eq!(
+            indexed.plan.to_string(),
+            "used sorted index: sta \u{2192} 1 candidates"
+        );
+    }
+
+    #[test]
+    fn pattern() {
+        let dict = ["committee", "commit", "comet", "tee"]
+            .iter()
+            .collect::<Dictionary>();
+        let rack = Rack::parse("committee", &RackOptions::default()).unwrap();
+        let mut query = SearchQuery::new(rack);
+        query.pattern = Some(Pattern::parse("*XX*").unwrap());
+
+        assert_eq!(
+            query.search(&dict, &Indexes::default()).words,
+            ["commit", "committee", "tee"]
+        );
+    }
+
+    #[test]
+    fn min_efficiency() {
+        let dict = ["quiz", "quit", "zit", "it"].iter().collect::<Dictionary>();
+        let rack = Rack::parse("quitz", &RackOptions::default()).unwrap();
+        let mut query = SearchQuery::new(rack);
+
+        // quiz 22/4, quit 13/4, zit 12/3, it 2/2
+        query.min_efficiency = Some(4.0);
+        assert_eq!(
+            query.search(&dict, &Indexes::default()).words,
+            ["quiz", "zit"]
+        );
+        query.min_efficiency = Some(3.25);
+        assert_eq!(
+            query.search(&dict, &Indexes::default()).words,
+            ["quit", "quiz", "zit"]
+        );
+    }
+
+    #[test]
+    fn invert() {
+        let dict = [
+            "tan", "ant", "ants", "stain", "satin", "train", "quit", "at",
+        ]
+        .iter()
+        .collect::<Dictionary>();
+        let rack = Rack::parse("satin", &RackOptions::default()).unwrap();
+        let mut query = SearchQuery::new(rack);
+        query.min_length = 3;
+        query.max_length = Some(4);
+
+        let normal = query.search(&dict, &Indexes::default()).words;
+        query.invert = true;
+        let inverted = query.search(&dict, &Indexes::default()).words;
+        assert_eq!(inverted, ["quit"]);
+
+        let mut union = normal
+            .iter()
+            .chain(inverted.iter())
+            .copied()
+            .collect::<Vec<_>>();
+        union.sort_unstable();
+        assert_eq!(union, ["ant", "ants", "quit", "tan"]);
+    }
+
+    #[test]
+    fn sort_consumed() {
+        let dict = ["abba", "ab", "bad", "cab", "a"]
+            .iter()
+            .collect::<Dictionary>();
+        let rack = Rack::parse("abc??", &RackOptions::default()).unwrap();
+        let mut results = SearchQuery::new(rack).search(&dict, &Indexes::default());
+        assert_eq!(results.words, ["a", "ab", "abba", "bad", "cab"]);
+        assert_eq!(results.letters_used, [1, 2, 2, 2, 3]);
+
+        results.sort(SortOrder::Consumed);
+        assert_eq!(results.words, ["cab", "ab", "abba", "bad", "a"]);
+        assert_eq!(results.letters_used, [3, 2, 2, 2, 1]);
+    }
+
+    #[test]
+    fn query_round_trip() {
+        let mut query =
+            SearchQuery::new(Rack::parse("t, e, s, ?", &RackOptions::default()).unwrap());
+        query.max_length = Some(5);
+        query.require = CountSet::from_word("E").unwrap();
+        query.min_efficiency = Some(1.25);
+        query.pattern = Some(Pattern::parse("*XX*").unwrap());
+        query.exclude_derived = true;
+
+        let echo = query.to_string();
+        assert_eq!(
+            echo,
+            "rack=est?;min_length=1;max_length=5;require=e;starts_with=;\
+             min_efficiency=1.25;source=;invert=false;pattern=*XX*;exclude_derived=true;\
+             max_tier=;tier=;max_unique=;min_use=;multiplicity="
+        );
+        assert_eq!(echo.parse::<SearchQuery>().unwrap().to_string(), echo);
+
+        let query = "min_length=4".parse::<SearchQuery>().unwrap();
+        assert_eq!(query.min_length, 4);
+        assert!(query.pattern.is_none());
+        assert!("colour=red".parse::<SearchQuery>().is_err());
+        assert!("min_length=x".parse::<SearchQuery>().is_err());
+    }
+
+    #[test]
+    fn longest() {
+        let dict = ["tan", "ant", "ants", "stain", "satin", "train", "quit"]
+            .iter()
+            .collect::<Dictionary>();
+        let indexes = Indexes {
+            letters: Some(LetterIndex::build(&dict)),
+            sorted: Some(SortedIndex::build(&dict)),
+        };
+        let rack = Rack::parse("satin?", &RackOptions::default()).unwrap();
+        let mut query = SearchQuery::new(rack);
+
+        // ties are broken alphabetically
+        assert_eq!(query.longest(&dict, &Indexes::default()), Some("satin"));
+        query.require = CountSet::from_word("r").unwrap();
+        assert_eq!(query.longest(&dict, &indexes), Some("train"));
+        query.starts_with = String::from("q");
+        assert_eq!(query.longest(&dict, &indexes), None);
+    }
+
+    #[test]
+    fn max_unique() {
+        let dict = ["banana", "bandana", "nab", "band"]
+            .iter()
+            .collect::<Dictionary>();
+        let mut query =
+            SearchQuery::new(Rack::parse("??????????", &RackOptions::default()).unwrap());
+        query.max_unique = Some(3);
+
+        // banana has exactly 3 different letters, bandana 4
+        let results = query.search(&dict, &Indexes::default());
+        assert_eq!(results.words, ["banana", "nab"]);
+    }
+
+    #[test]
+    fn min_use() {
+        let dict = ["tan", "ants", "stain"].iter().collect::<Dictionary>();
+        let mut query = SearchQuery::new(Rack::parse("sat?n", &RackOptions::default()).unwrap());
+        query.min_use = Some(4);
+        let results = query.search(&dict, &Indexes::default());
+        assert_eq!(results.words, ["ants", "stain"]);
+
+        query.min_use = Some(5);
+        let results = query.search(&dict, &Indexes::default());
+        assert_eq!(results.words, ["stain"]);
+
+        query.min_use = Some(6);
+        assert!(query.search(&dict, &Indexes::default()).words.is_empty());
+    }
+
+    #[test]
+    fn multiplicity() {
+        let dict = ["ant", "tan", "at", "ta", "taa", "stain", "nan"]
+            .iter()
+            .collect::<Dictionary>();
+        let mut query =
+            SearchQuery::new(Rack::parse("aaattnns??", &RackOptions::default()).unwrap());
+        query.multiplicity = Some(2);
+        let results = query.search(&dict, &Indexes::default());
+        assert_eq!(results.words, ["ant", "at", "ta", "tan"]);
+
+        // the blanks don't count
+        query.multiplicity = Some(3);
+        assert!(query.search(&dict, &Indexes::default()).words.is_empty());
+        query.rack = Rack::parse("aaaaaattt", &RackOptions::default()).unwrap();
+        let results = query.search(&dict, &Indexes::default());
+        assert_eq!(results.words, ["at", "ta", "taa"]);
+    }
+
+    #[test]
+    fn page() {
+        let dict = ["abba", "ab", "bad", "cab", "a"]
+            .iter()
+            .collect::<Dictionary>();
+        let rack = Rack::parse("abc??", &RackOptions::default()).unwrap();
+        let search = || SearchQuery::new(rack.clone()).search(&dict, &Indexes::default());
+
+        let mut results = search();
+        results.sort(SortOrder::Consumed);
+        results.page(1, Some(2));
+        assert_eq!(results.words, ["ab", "abba"]);
+        assert_eq!(results.letters_used, [2, 2]);
+
+        let mut results = search();
+        results.page(3, None);
+        assert_eq!(results.words, ["bad", "cab"]);
+
+        let mut results = search();
+        results.page(4, Some(10));
+        assert_eq!(results.words, ["cab"]);
+
+        let mut results = search();
+        results.page(10, Some(2));
+        assert!(results.words.is_empty());
+        assert!(results.letters_used.is_empty());
+    }
+
+    #[test]
+    fn search_top() {
+        let dict = crate::sample::dictionary();
+        let indexes = Indexes {
+            letters: Some(LetterIndex::build(&dict)),
+            sorted: None,
+        };
+
+        for (rack, require) in [("aeinrst??", ""), ("retains", ""), ("zq??", "e"), ("x", "")] {
+            let mut query = SearchQuery::new(Rack::parse(rack, &RackOptions::default()).unwrap());
+            query.require = CountSet::from_word(require).unwrap();
+
+            for order in [SortOrder::Alpha, SortOrder::Consumed] {
+                for k in [0, 1, 5, 20, 1000, usize::MAX] {
+                    let mut expected = query.search(&dict, &indexes);
+                    let matches = expected.words.len();
+                    expected.sort(order);
+                    expected.page(0, Some(k));
+
+                    let (top, found) = query.search_top(&dict, &indexes, order, k);
+                    assert_eq!(top.words, expected.words, "{} {:?} {}", rack, order, k);
+                    assert_eq!(top.letters_used, expected.letters_used);
+                    assert_eq!(top.count_sets, expected.count_sets);
+                    assert_eq!(top.used_letters, expected.used_letters);
+                    assert_eq!(found, matches);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn exclude() {
+        let rack = Rack::parse("satin", &RackOptions::default()).unwrap();
+        let query = SearchQuery::new(rack);
+        let primary = ["stain", "ant"].iter().collect::<Dictionary>();
+        let fallback = ["STAIN", "ant", "tan"].iter().collect::<Dictionary>();
+
+        let found = query.search(&primary, &Indexes::default());
+        let mut results = query.search(&fallback, &Indexes::default());
+        results.exclude(&found);
+        assert_eq!(results.words, ["tan"]);
+        assert_eq!(results.letters_used, [3]);
+        assert_eq!(results.count_sets.len(), 1);
+        assert_eq!(results.unusable_letters(&query.rack), ['i', 's']);
+    }
+
+    #[test]
+    fn prioritize() {
+        let dict = ["stain", "satin", "ant", "tan", "ants", "sass"]
+            .iter()
+            .collect::<Dictionary>();
+        let rack = Rack::parse("satins??", &RackOptions::default()).unwrap();
+        let mut results = SearchQuery::new(rack).search(&dict, &Indexes::default());
+        results.prioritize(&CountSet::from_word("sn").unwrap());
+        assert_eq!(
+            results.words,
+            ["sass", "ants", "satin", "stain", "ant", "tan"]
+        );
+        assert_eq!(results.letters_used, [3, 4, 5, 5, 3, 3]);
+
+        // by the stored counts of words kept in another spelling
+        let mut dict = ["cat"].iter().collect::<Dictionary>();
+        dict.add_spelling("h3llo", "hello", None).unwrap();
+        let rack = Rack::parse("hellocat", &RackOptions::default()).unwrap();
+        let mut results = SearchQuery::new(rack).search(&dict, &Indexes::default());
+        results.prioritize(&CountSet::from_word("l").unwrap());
+        assert_eq!(results.words, ["h3llo", "cat"]);
+    }
+
+    #[test]
+    fn sort_by_score() {
+        let dict = ["stain", "satin", "ant", "tan", "quit"]
+            .iter()
+            .collect::<Dictionary>();
+        let rack = Rack::parse("satinqu", &RackOptions::default()).unwrap();
+        let mut results = SearchQuery::new(rack.clone()).search(&dict, &Indexes::default());
+        results.sort_by_score(&crate::score::ScrabbleScorer, &rack);
+        assert_eq!(results.words, ["quit", "satin", "stain", "ant", "tan"]);
+        assert_eq!(results.letters_used, [4, 5, 5, 3, 3]);
+
+        // scored by the stored counts of words kept in another spelling
+        let mut dict = ["cat"].iter().collect::<Dictionary>();
+        dict.add_spelling("h3llo", "hello", None).unwrap();
+        let rack = Rack::parse("hellocat", &RackOptions::default()).unwrap();
+        let mut results = SearchQuery::new(rack.clone()).search(&dict, &Indexes::default());
+        results.sort_by_score(&crate::score::ScrabbleScorer, &rack);
+        assert_eq!(results.words, ["h3llo", "cat"]);
+    }
+
+    #[test]
+    fn retain() {
+        let dict = ["stain", "satin", "ant"].iter().collect::<Dictionary>();
+        let rack = Rack::parse("satin", &RackOptions::default()).unwrap();
+        let mut results = SearchQuery::new(rack).search(&dict, &Indexes::default());
+        assert_eq!(results.retain(|word| word.len() < 5), 2);
+        assert_eq!(results.words, ["ant"]);
+        assert_eq!(results.letters_used, [3]);
+        assert_eq!(results.count_sets, [&CountSet::from_word("ant").unwrap()]);
+        assert_eq!(
+            results.used_letters,
+            CountSet::from_word("ant").unwrap().presence_mask()
+        );
+        assert_eq!(results.retain(|_| true), 0);
+    }
+
+    #[test]
+    fn collapse_variants() {
+        let dict = ["stain", "stains", "stained", "tan", "tans", "ant"]
+            .iter()
+            .collect::<Dictionary>();
+        let rack = Rack::parse("stainsed", &RackOptions::default()).unwrap();
+        let mut results = SearchQuery::new(rack).search(&dict, &Indexes::default());
+        let used_letters = results.used_letters;
+        let variants = results.collapse_variants();
+        assert_eq!(results.words, ["ant", "stain", "tan"]);
+        assert_eq!(results.letters_used, [3, 5, 3]);
+        assert_eq!(
+            results.count_sets[1],
+            &CountSet::from_word("stain").unwrap()
+        );
+        assert_eq!(results.used_letters, used_letters);
+        assert_eq!(variants.len(), 2);
+        assert_eq!(variants["stain"], ["stained", "stains"]);
+        assert_eq!(variants["tan"], ["tans"]);
+    }
+
+    #[test]
+    fn anagram_groups() {
+        let dict = ["stain", "satin", "ant", "tan", "tin", "quit"]
+            .iter()
+            .collect::<Dictionary>();
+        let rack = Rack::parse("satin", &RackOptions::default()).unwrap();
+        let results = SearchQuery::new(rack).search(&dict, &Indexes::default());
+        let groups = results
+            .anagram_groups()
+            .into_iter()
+            .map(|(key, words)| (key.to_string(), words))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            groups,
+            [
+                ("ant".to_owned(), vec!["ant", "tan"]),
+                ("ainst".to_owned(), vec!["satin", "stain"]),
+                ("int".to_owned(), vec!["tin"]),
+            ]
+        );
+
+        // a word kept in another spelling joins the anagrams of its letters
+        let mut dict = ["hello", "cat"].iter().collect::<Dictionary>();
+        dict.add_spelling("h3llo", "hello", None).unwrap();
+        let rack = Rack::parse("hellocat", &RackOptions::default()).unwrap();
+        let results = SearchQuery::new(rack).search(&dict, &Indexes::default());
+        let groups = results.anagram_groups();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[1].0.to_string(), "ehllo");
+        assert_eq!(groups[1].1, ["h3llo", "hello"]);
+    }
+
+    #[test]
+    fn search_with_leftovers() {
+        let dict = ["tin", "tins", "nit", "quit"]
+            .iter()
+            .collect::<Dictionary>();
+        let rack = Rack::parse("retains?", &RackOptions::default()).unwrap();
+        let query = SearchQuery::new(rack);
+
+        let results = query.search_with_leftovers(&dict, &Indexes::default());
+        let results = results
+            .iter()
+            .map(|(word, leftover)| (*word, leftover.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            results,
+            [
+                ("nit", String::from("aers")),
+                ("tin", String::from("aers")),
+                ("tins", String::from("aer")),
+            ]
+        );
+    }
+
+    #[test]
+    fn leftovers_of_spellings() {
+        // `h3llo` is stored with the counts of `hello`, which it doesn't spell
+        let mut dict = Dictionary::new();
+        dict.add_spelling("h3llo", "hello", None).unwrap();
+        dict.add("cat").unwrap();
+        let rack = Rack::parse("hellocat", &RackOptions::default()).unwrap();
+        let mut results = SearchQuery::new(rack.clone()).search(&dict, &Indexes::default());
+        results.sort(SortOrder::Consumed);
+        assert_eq!(results.words, ["h3llo", "cat"]);
+        let leftovers = results.leftovers(&rack);
+        assert_eq!(
+            leftovers
+                .iter()
+                .map(CountSet::to_string)
+                .collect::<Vec<_>>(),
+            ["act", "ehllo"]
+        );
+        assert_eq!(results.multiplicities(&rack), [1, 1]);
+    }
+
+    #[test]
+    fn unusable_letters() {
+        let dict = ["jab", "bat", "tab", "zoo"].iter().collect::<Dictionary>();
+
+        let rack = Rack::parse("jabtz", &RackOptions::default()).unwrap();
+        let mut query = SearchQuery::new(rack);
+        assert_eq!(
+            query
+                .search(&dict, &Indexes::default())
+                .unusable_letters(&query.rack),
+            ['z']
+        );
+
+        query.max_length = Some(2);
+        assert_eq!(
+            query
+                .search(&dict, &Indexes::default())
+                .unusable_letters(&query.rack),
+            ['a', 'b', 'j', 't', 'z']
+        );
+    }
+
+    #[test]
+    fn search_batch() {
+        let mut dict = Dictionary::new();
+        for word in [
+            "tan", "ant", "ants", "stain", "satin", "train", "quit", "tat", "tatt", "it",
+        ] {
+            dict.add(word).unwrap();
+        }
+        let racks = ["satin", "tatt", "quirt", "", "tannins"]
+            .map(|letters| CountSet::from_word(letters).unwrap());
+
+        let mut queries = vec![SearchQuery::new(Rack::from_counts(&CountSet::default()))];
+        let mut query = queries[0].clone();
+        query.require = CountSet::from_word("t").unwrap();
+        query.max_length = Some(4);
+        queries.push(query);
+        let mut query = queries[0].clone();
+        query.invert = true;
+        queries.push(query);
+        let mut query = queries[0].clone();
+        query.multiplicity = Some(2);
+        query.min_length = 2;
+        queries.push(query);
+        let mut query = queries[0].clone();
+        query.min_efficiency = Some(1.5);
+        queries.push(query);
+
+        // the same words as searching each rack in turn
+        for query in queries.iter() {
+            let expected = racks
+                .iter()
+                .map(|counts| {
+                    let mut query = query.clone();
+                    query.rack = Rack::from_counts(counts);
+                    query.search(&dict, &Indexes::default()).words
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(query.search_batch(&dict, &racks), expected, "{}", query);
+        }
+        assert_eq!(
+            queries[0].search_batch(&dict, &racks)[0],
+            ["ant", "ants", "it", "satin", "stain", "tan"]
+        );
+        assert!(queries[0].search_batch(&dict, &[]).is_empty());
+        assert_eq!(
+            queries[0].search_batch(&Dictionary::new(), &racks[..2]),
+            [Vec::<&str>::new(), Vec::new()]
+        );
+    }
+
+    #[test]
+    fn defaults_precedence() {
+        let stored = SearchDefaults {
+            min_length: Some(4),
+        };
+        let cases = [
+            (Some(5), &stored, (5, OptionOrigin::CommandLine)),
+            (
+                Some(2),
+                &SearchDefaults::default(),
+                (2, OptionOrigin::CommandLine),
+            ),
+            (None, &stored, (4, OptionOrigin::Dictionary)),
+            (
+                None,
+                &SearchDefaults {
+                    min_length: Some(0),
+                },
+                (1, OptionOrigin::Dictionary),
+            ),
+            (
+                None,
+                &SearchDefaults::default(),
+                (DEFAULT_MIN_LENGTH, OptionOrigin::BuiltIn),
+            ),
+        ];
+        for (command_line, defaults, expected) in cases {
+            assert_eq!(defaults.min_length(command_line), expected);
+        }
+    }
+
+    #[test]
+    fn thousands() {
+        assert_eq!(super::thousands(0), "0");
+        assert_eq!(super::thousands(999), "999");
+        assert_eq!(super::thousands(2113), "2,113");
+        assert_eq!(super::thousands(1234567), "1,234,567");
+    }
+}
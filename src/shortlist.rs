@@ -0,0 +1,152 @@
+//! Two- and three-letter word lists, as studied by Scrabble players
+
+use crate::dict::Dictionary;
+use crate::fold::{eq_folded, fold_case};
+use crate::par::*;
+use std::collections::HashSet;
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+/// Lengths of the words of a shortlist
+pub const LENGTHS: [usize; 2] = [2, 3];
+
+/// Layouts a shortlist can be written in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShortlistFormat {
+    /// A section per length, with a line of words per starting letter
+    Text,
+    /// A `length,letter,word` header, then a line per word, for flashcards
+    Csv,
+}
+
+impl FromStr for ShortlistFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(ShortlistFormat::Text),
+            "csv" => Ok(ShortlistFormat::Csv),
+            _ => Err(format!(
+                "unknown shortlist format {:?} (expected text or csv)",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ShortlistFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ShortlistFormat::Text => "text",
+            ShortlistFormat::Csv => "csv",
+        })
+    }
+}
+
+/// Which words of the dictionary make it to the shortlist
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ShortlistOptions<'a> {
+    /// Only the words containing this letter
+    pub containing: Option<char>,
+    /// Only the words missing from this (older) dictionary
+    pub new_since: Option<&'a Dictionary>,
+}
+
+/// The two- and three-letter words of the dictionary, sorted by length then
+/// alphabetically.
+pub fn shortlist<'a>(dict: &'a Dictionary, options: &ShortlistOptions) -> Vec<&'a str> {
+    let older = options.new_since.map(|older| {
+        older
+            .par_iter()
+            .filter(|entry| LENGTHS.contains(&entry.len()))
+            .map(|entry| entry.word)
+            .collect::<HashSet<_>>()
+    });
+
+    let mut words = dict
+        .par_iter()
+        .filter(|entry| LENGTHS.contains(&entry.len()))
+        .map(|entry| entry.word)
+        .filter(|word| {
+            options
+                .containing
+                .is_none_or(|letter| word.chars().any(|c| eq_folded(c, letter)))
+        })
+        .filter(|word| older.as_ref().is_none_or(|older| !older.contains(word)))
+        .collect::<Vec<_>>();
+    words.par_sort_unstable_by_key(|word| (word.len(), *word));
+
+    words
+}
+
+/// Writes a shortlist, as returned by `shortlist`.
+pub fn write_shortlist<W: Write>(
+    words: &[&str],
+    format: ShortlistFormat,
+    writer: &mut W,
+) -> io::Result<()> {
+    let first_letter = |word: &str| fold_case(word.as_bytes()[0]) as char;
+
+    match format {
+        ShortlistFormat::Text => {
+            for (i, length) in words.chunk_by(|a, b| a.len() == b.len()).enumerate() {
+                if i > 0 {
+                    writeln!(writer)?;
+                }
+                writeln!(writer, "=== {}-letter words ===", length[0].len())?;
+
+                for letter in length.chunk_by(|a, b| first_letter(a) == first_letter(b)) {
+                    writeln!(writer, "{}: {}", first_letter(letter[0]), letter.join(" "))?;
+                }
+            }
+        }
+        ShortlistFormat::Csv => {
+            writeln!(writer, "length,letter,word")?;
+            for word in words.iter() {
+                writeln!(writer, "{},{},{}", word.len(), first_letter(word), word)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shortlist() {
+        let dict = ["qi", "za", "aa", "zax", "cat", "ace", "word", "q"]
+            .iter()
+            .collect::<Dictionary>();
+        let older = ["qi", "aa", "cat"].iter().collect::<Dictionary>();
+
+        let options = ShortlistOptions::default();
+        let words = super::shortlist(&dict, &options);
+        assert_eq!(words, ["aa", "qi", "za", "ace", "cat", "zax"]);
+
+        let mut out = Vec::new();
+        write_shortlist(&words, ShortlistFormat::Text, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "=== 2-letter words ===\na: aa\nq: qi\nz: za\n\n\
+             === 3-letter words ===\na: ace\nc: cat\nz: zax\n"
+        );
+
+        let options = ShortlistOptions {
+            containing: Some('A'),
+            new_since: Some(&older),
+        };
+        let words = super::shortlist(&dict, &options);
+        assert_eq!(words, ["za", "ace", "zax"]);
+
+        let mut out = Vec::new();
+        write_shortlist(&words, ShortlistFormat::Csv, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "length,letter,word\n2,z,za\n3,a,ace\n3,z,zax\n"
+        );
+    }
+}
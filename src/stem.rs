@@ -0,0 +1,156 @@
+//! A light rule-based stemmer, to tell apart the regular variants of a word
+//!
+//! Stripping `s`, `es`, `ed` and `ing` with the usual spelling adjustments
+//! is enough for `walk, walks, walked, walking` to share a stem, without any
+//! word data. Irregular forms keep their own stems (`ran`, `mice`), and
+//! unrelated words can share one (`hop`, `hope`): stems only group variants,
+//! they aren't words.
+
+use crate::fold::fold_word;
+use std::collections::HashMap;
+
+/// The stem of a word, lowercase: `walks`, `walked` and `walking` give
+/// `walk`, `tries` and `tried` give `try`, `baking` and `bake` give `bak`.
+pub fn stem(word: &str) -> String {
+    let mut stem = fold_word(word);
+
+    if let Some(base) = (stem.ends_with("ies") || stem.ends_with("ied"))
+        .then(|| &stem[..(stem.len() - 3)])
+        .filter(|base| base.len() >= 2)
+    {
+        stem = format!("{}y", base);
+    } else if let Some(base) = strip(&stem, "ing").or_else(|| strip(&stem, "ed")) {
+        // `agreed` is `agree` and a `d`
+        stem = match base.ends_with('e') {
+            true if stem.ends_with("eed") => format!("{}e", base),
+            _ => undouble(base).to_owned(),
+        };
+    } else if let Some(base) = strip(&stem, "es").filter(|base| takes_es(base)) {
+        stem = base.to_owned();
+    } else if let Some(base) = strip(&stem, "s").filter(|base| !takes_es(base)) {
+        if !base.ends_with('u') && !base.ends_with('i') {
+            stem = base.to_owned();
+        }
+    }
+
+    // a silent e is dropped before `ing` and `ed`, so from every stem
+    if stem.len() > 3 && stem.ends_with('e') {
+        stem.pop();
+    }
+    stem
+}
+
+/// The words grouped by stem, each group in order of `words` and the groups
+/// in order of their first word.
+pub fn variant_groups<'a>(words: &[&'a str]) -> Vec<Vec<&'a str>> {
+    let mut groups: Vec<Vec<&str>> = Vec::new();
+    let mut group_of_stem = HashMap::new();
+    for &word in words {
+        let group = *group_of_stem.entry(stem(word)).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[group].push(word);
+    }
+
+    groups
+}
+
+// the word without the suffix, if what's left can be a stem: at least 3
+// letters, with a vowel
+fn strip<'w>(word: &'w str, suffix: &str) -> Option<&'w str> {
+    word.strip_suffix(suffix).filter(|base| {
+        base.len() >= 3
+            && base
+                .bytes()
+                .any(|c| matches!(c, b'a' | b'e' | b'i' | b'o' | b'u' | b'y'))
+    })
+}
+
+// whether the plural of a word ending so takes `es` (`buses`, `boxes`)
+fn takes_es(base: &str) -> bool {
+    ["s", "x", "z", "ch", "sh"]
+        .iter()
+        .any(|ending| base.ends_with(ending))
+}
+
+// `running → run`, but `falling → fall`, `passed → pass`, `buzzed → buzz`
+fn undouble(base: &str) -> &str {
+    match *base.as_bytes() {
+        [.., a, b] if a == b && !matches!(b, b'l' | b's' | b'z' | b'e' | b'o') => {
+            &base[..(base.len() - 1)]
+        }
+        _ => base,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn same_stem(words: &[&str]) -> bool {
+        words.iter().all(|word| stem(word) == stem(words[0]))
+    }
+
+    #[test]
+    fn regular() {
+        for variants in [
+            &["walk", "walks", "walked", "walking"][..],
+            &["bake", "bakes", "baked", "baking"],
+            &["stop", "stops", "stopped", "stopping"],
+            &["try", "tries", "tried"],
+            &["box", "boxes", "boxed", "boxing"],
+            &["wish", "wishes", "wished"],
+            &["fall", "falls", "falling"],
+            &["pass", "passes", "passed"],
+            &["agree", "agrees", "agreed", "agreeing"],
+            &["see", "sees", "seeing"],
+            &["Walk", "WALKED"],
+        ] {
+            assert!(same_stem(variants), "{:?}", variants);
+        }
+        assert_eq!(stem("walking"), "walk");
+        assert_eq!(stem("tried"), "try");
+        assert_eq!(stem("running"), "run");
+    }
+
+    #[test]
+    fn left_alone() {
+        // too short for a suffix to be one, or no vowel left
+        for word in [
+            "sing", "ring", "bring", "string", "bed", "need", "gas", "yes",
+        ] {
+            assert_eq!(stem(word), word);
+        }
+        // not plurals
+        assert_eq!(stem("bus"), "bus");
+        assert_eq!(stem("glass"), "glass");
+        assert_eq!(stem("this"), "this");
+    }
+
+    // the cases the rules intentionally don't handle, documented
+    #[test]
+    fn not_handled() {
+        // irregular forms keep their own stems
+        assert!(!same_stem(&["run", "ran"]));
+        assert!(!same_stem(&["mouse", "mice"]));
+        assert!(!same_stem(&["go", "went", "gone"]));
+        // `dy` is too short to be the stem of `dying`
+        assert!(!same_stem(&["die", "dying"]));
+        // unrelated words can share a stem
+        assert!(same_stem(&["hop", "hope", "hoping", "hopped"]));
+        // words merely ending like a suffix are cut short
+        assert_eq!(stem("hundred"), "hundr");
+        assert_eq!(stem("news"), "new");
+    }
+
+    #[test]
+    fn variant_groups() {
+        let words = ["walks", "ant", "walk", "ants", "walked", "tan"];
+        assert_eq!(
+            super::variant_groups(&words),
+            [&["walks", "walk", "walked"][..], &["ant", "ants"], &["tan"]]
+        );
+        assert!(super::variant_groups(&[]).is_empty());
+    }
+}
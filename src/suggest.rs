@@ -0,0 +1,64 @@
+//! "Did you mean" suggestions for mistyped names
+
+/// Levenshtein distance between two strings: the number of single-character
+/// insertions, deletions and substitutions turning one into the other.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    // distances from the prefix of `a` so far to every prefix of `b`
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + (ca != cb) as usize;
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+/// The candidate closest to `name`, if close enough to be a typo: at most
+/// one edit per three characters, rounded up, so that swapping two letters
+/// of a five-letter name (two edits) still counts. Ties go to the first
+/// candidate.
+pub fn closest<'a, I>(name: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let max_distance = name.chars().count().div_ceil(3);
+
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|&(distance, _)| distance <= max_distance)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn edit_distance() {
+        assert_eq!(super::edit_distance("kitten", "sitting"), 3);
+        assert_eq!(super::edit_distance("serach", "search"), 2);
+        assert_eq!(super::edit_distance("", "abc"), 3);
+        assert_eq!(super::edit_distance("abc", "abc"), 0);
+    }
+
+    #[test]
+    fn closest() {
+        let names = ["generate", "search", "info", "export"];
+
+        assert_eq!(super::closest("serach", names), Some("search"));
+        assert_eq!(super::closest("seach", names), Some("search"));
+        assert_eq!(super::closest("inf", names), Some("info"));
+        assert_eq!(super::closest("xyzzy", names), None);
+        assert_eq!(super::closest("stast", ["stats", "sort"]), Some("stats"));
+        // too short for two edits
+        assert_eq!(super::closest("ifo", ["info", "ab"]), Some("info"));
+        assert_eq!(super::closest("in", ["info"]), None);
+    }
+}
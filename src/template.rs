@@ -0,0 +1,266 @@
+//! Templates for the lines of search results
+//!
+//! A template is text with placeholders in angle brackets, replaced for each
+//! word: `<word>\t<length>\t<score>`. A backslash escapes the next character:
+//! `\t` is a tab, `\n` a newline, and `\\`, `\<` and `\>` the character
+//! itself, so templates can be typed in a shell without its own escapes.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Data about a word that a template can show
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Placeholder {
+    /// The word
+    Word,
+    /// Number of letters of the word
+    Length,
+    /// Scrabble score of the word made from the rack, blanks scoring 0
+    Score,
+    /// Letters of the rack the word leaves unused, blanks aside
+    Remaining,
+    /// Name of the word list the word came from, empty if untagged
+    Sources,
+    /// Difficulty tier of the word, by name
+    Tier,
+}
+
+impl Placeholder {
+    pub const ALL: [Placeholder; 6] = [
+        Placeholder::Word,
+        Placeholder::Length,
+        Placeholder::Score,
+        Placeholder::Remaining,
+        Placeholder::Sources,
+        Placeholder::Tier,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Placeholder::Word => "word",
+            Placeholder::Length => "length",
+            Placeholder::Score => "score",
+            Placeholder::Remaining => "remaining",
+            Placeholder::Sources => "sources",
+            Placeholder::Tier => "tier",
+        }
+    }
+}
+
+impl FromStr for Placeholder {
+    type Err = TemplateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Placeholder::ALL
+            .into_iter()
+            .find(|placeholder| placeholder.name() == s)
+            .ok_or_else(|| TemplateError::UnknownPlaceholder(s.to_owned()))
+    }
+}
+
+/// Displays the placeholder as written in templates, e.g. `<word>`.
+impl fmt::Display for Placeholder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{}>", self.name())
+    }
+}
+
+#[derive(Debug)]
+pub enum TemplateError {
+    /// A placeholder name that isn't one of `Placeholder::ALL`
+    UnknownPlaceholder(String),
+    /// A `<` without its `>`
+    Unclosed,
+    /// A backslash followed by a character it doesn't escape
+    UnknownEscape(char),
+    /// A backslash ending the template
+    TrailingBackslash,
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use TemplateError::*;
+
+        match self {
+            UnknownPlaceholder(name) => {
+                let valid = Placeholder::ALL.map(|placeholder| placeholder.to_string());
+                write!(
+                    f,
+                    "unknown placeholder <{}> (expected one of {})",
+                    name,
+                    valid.join(", ")
+                )
+            }
+            Unclosed => f.write_str("a placeholder isn't closed with >"),
+            UnknownEscape(c) => write!(
+                f,
+                "unknown escape \\{} (expected \\t, \\n, \\\\, \\< or \\>)",
+                c
+            ),
+            TrailingBackslash => f.write_str("the template ends with a lone backslash"),
+        }
+    }
+}
+
+impl Error for TemplateError {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Piece {
+    Text(String),
+    Placeholder(Placeholder),
+}
+
+/// A parsed template, checked to only have known placeholders
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Template {
+    pieces: Vec<Piece>,
+}
+
+impl Template {
+    pub fn parse(s: &str) -> Result<Self, TemplateError> {
+        let mut pieces = Vec::new();
+        let mut text = String::new();
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => text.push(match chars.next() {
+                    Some('t') => '\t',
+                    Some('n') => '\n',
+                    Some(c @ ('\\' | '<' | '>')) => c,
+                    Some(c) => return Err(TemplateError::UnknownEscape(c)),
+                    None => return Err(TemplateError::TrailingBackslash),
+                }),
+                '<' => {
+                    let rest = chars.as_str();
+                    let end = rest.find('>').ok_or(TemplateError::Unclosed)?;
+                    let placeholder = rest[..end].parse()?;
+                    chars = rest[(end + 1)..].chars();
+
+                    if !text.is_empty() {
+                        pieces.push(Piece::Text(std::mem::take(&mut text)));
+                    }
+                    pieces.push(Piece::Placeholder(placeholder));
+                }
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            pieces.push(Piece::Text(text));
+        }
+
+        Ok(Self { pieces })
+    }
+
+    /// Whether the template has this placeholder, for the data that can't
+    /// always be shown
+    pub fn uses(&self, placeholder: Placeholder) -> bool {
+        self.pieces.contains(&Piece::Placeholder(placeholder))
+    }
+
+    /// Whether the template ends a line by itself, with a final `\n`
+    pub fn ends_with_newline(&self) -> bool {
+        matches!(self.pieces.last(), Some(Piece::Text(text)) if text.ends_with('\n'))
+    }
+
+    /// The text of a word, with each placeholder replaced by `value`
+    pub fn render<F>(&self, mut value: F) -> String
+    where
+        F: FnMut(Placeholder) -> String,
+    {
+        let mut rendered = String::new();
+        for piece in self.pieces.iter() {
+            match piece {
+                Piece::Text(text) => rendered.push_str(text),
+                Piece::Placeholder(placeholder) => rendered.push_str(&value(*placeholder)),
+            }
+        }
+        rendered
+    }
+}
+
+impl FromStr for Template {
+    type Err = TemplateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(template: &str) -> String {
+        Template::parse(template)
+            .unwrap()
+            .render(|placeholder| match placeholder {
+                Placeholder::Word => String::from("cat"),
+                Placeholder::Length => String::from("3"),
+                other => other.to_string().to_uppercase(),
+            })
+    }
+
+    #[test]
+    fn placeholders() {
+        assert_eq!(render("<word>"), "cat");
+        assert_eq!(render(r"<word>\t<length>\t<score>"), "cat\t3\t<SCORE>");
+        assert_eq!(render("<word> (<length>)<word>"), "cat (3)cat");
+        assert_eq!(render("word"), "word");
+        assert_eq!(render(""), "");
+        // a `>` outside a placeholder is plain text
+        assert_eq!(render("-> <word>"), "-> cat");
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(render(r"<word>\n"), "cat\n");
+        assert_eq!(render(r"\<word\> \\ <word>"), "<word> \\ cat");
+        assert!(matches!(
+            Template::parse(r"<word>\x"),
+            Err(TemplateError::UnknownEscape('x'))
+        ));
+        assert!(matches!(
+            Template::parse(r"<word>\"),
+            Err(TemplateError::TrailingBackslash)
+        ));
+    }
+
+    #[test]
+    fn errors() {
+        let err = Template::parse("<word>\t<len>").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown placeholder <len> (expected one of <word>, <length>, <score>, \
+             <remaining>, <sources>, <tier>)"
+        );
+        // names are exact
+        assert!(Template::parse("<Word>").is_err());
+        assert!(Template::parse("< word>").is_err());
+        assert!(Template::parse("<>").is_err());
+        assert!(matches!(
+            Template::parse("<word"),
+            Err(TemplateError::Unclosed)
+        ));
+    }
+
+    #[test]
+    fn uses() {
+        let template = Template::parse(r"<word>\t<tier>").unwrap();
+        assert!(template.uses(Placeholder::Tier));
+        assert!(!template.uses(Placeholder::Sources));
+        // escaped brackets aren't placeholders
+        assert!(!Template::parse(r"\<tier\>")
+            .unwrap()
+            .uses(Placeholder::Tier));
+    }
+
+    #[test]
+    fn ends_with_newline() {
+        assert!(Template::parse(r"<word>\n").unwrap().ends_with_newline());
+        assert!(!Template::parse(r"<word>\n<length>")
+            .unwrap()
+            .ends_with_newline());
+        assert!(!Template::parse("<word>").unwrap().ends_with_newline());
+    }
+}
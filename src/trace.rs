@@ -0,0 +1,114 @@
+//! Events and spans for applications instrumented with `tracing`
+//!
+//! With the `tracing` feature, `event!` and `span!` forward to `tracing`'s
+//! `info!` and `info_span!`, with the path of their module as target;
+//! without it, they expand to nothing and their arguments aren't evaluated.
+//! Spans wrap the reading of dictionaries and searches, so a subscriber
+//! timing spans reports how long they took.
+
+/// An event at the info level, with the syntax of `tracing::info!`
+macro_rules! event {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::info!($($arg)*);
+    };
+}
+
+/// A span at the info level, entered until the end of the enclosing block,
+/// with the syntax of `tracing::info_span!`
+macro_rules! span {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!($($arg)*).entered();
+    };
+}
+
+pub(crate) use {event, span};
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use crate::builder::{BuildOptions, DictionaryBuilder};
+    use crate::format::{read_dict, write_dict};
+    use crate::index::Indexes;
+    use crate::rack::{Rack, RackOptions};
+    use crate::search::SearchQuery;
+    use std::fmt::Debug;
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    // records the spans and events as `name field=value ...` lines
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    struct Fields(String);
+
+    impl Visit for Fields {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            match field.name() {
+                "message" => self.0 += &format!(" {:?}", value),
+                name => self.0 += &format!(" {}={:?}", name, value),
+            }
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut fields = Fields(format!("span {}", span.metadata().name()));
+            span.record(&mut fields);
+            let mut lines = self.0.lock().unwrap();
+            lines.push(fields.0);
+            Id::from_u64(lines.len() as u64)
+        }
+
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut fields = Fields(String::from("event"));
+            event.record(&mut fields);
+            self.0.lock().unwrap().push(fields.0);
+        }
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    #[test]
+    fn events() {
+        let recorder = Recorder::default();
+        tracing::subscriber::with_default(recorder.clone(), || {
+            let mut builder = DictionaryBuilder::new(BuildOptions::default());
+            for word in ["cat", "act", "dog"] {
+                builder.add_line(word).unwrap();
+            }
+            let (dict, _) = builder.finish();
+
+            let mut file = Vec::new();
+            write_dict(&dict, &mut file).unwrap();
+            let dict = read_dict(&mut &file[..]).unwrap();
+
+            let rack = Rack::parse("tac", &RackOptions::default()).unwrap();
+            SearchQuery::new(rack).search(&dict, &Indexes::default());
+        });
+
+        let lines = recorder.0.lock().unwrap();
+        assert_eq!(
+            lines[..],
+            [
+                "event dictionary built lines=3 added=3 duplicates=0 skipped=0",
+                "span read_dict",
+                "event dictionary read words=3 word_bytes=9 version=2 trailing_bytes=0",
+                "span search rack=act",
+                "event search done matches=2 plan=scanned all 3 entries",
+            ]
+        );
+    }
+}
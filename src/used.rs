@@ -0,0 +1,141 @@
+//! Words already played in a game, excluded from later searches
+//!
+//! They're kept in a plain word list, one word per line, which may be edited
+//! by hand between changes: it's read again before every change, then
+//! rewritten whole through a temporary file, so that readers never see it
+//! half-written.
+
+use crate::fold::fold_word;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// A set of words, compared ignoring case
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct UsedWords {
+    words: HashSet<String>,
+}
+
+impl UsedWords {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads a word list, one word per line, skipping blank lines
+    pub fn read_from<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut used = Self::new();
+        for line in reader.lines() {
+            let line = line?;
+            let word = line.trim();
+            if !word.is_empty() {
+                used.insert(word);
+            }
+        }
+
+        Ok(used)
+    }
+
+    /// Reads the word list at `path`, which is empty if it doesn't exist yet
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match File::open(path) {
+            Ok(file) => Self::read_from(BufReader::new(file)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::new()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Writes the words, sorted, one per line
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        for word in self.sorted() {
+            writeln!(writer, "{}", word)?;
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the word list at `path`, by renaming a temporary file next to
+    /// it over it
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut temp = PathBuf::from(path.as_os_str().to_owned());
+        temp.set_extension(format!("tmp{}", std::process::id()));
+
+        let mut writer = BufWriter::new(File::create(&temp)?);
+        self.write_to(&mut writer)?;
+        writer.into_inner()?.sync_all()?;
+        fs::rename(&temp, path)
+    }
+
+    /// Adds a word, returning whether it wasn't used yet
+    pub fn insert(&mut self, word: &str) -> bool {
+        self.words.insert(fold_word(word))
+    }
+
+    pub fn clear(&mut self) {
+        self.words.clear();
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.words.contains(&fold_word(word))
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// The words, in alphabetical order
+    pub fn sorted(&self) -> Vec<&str> {
+        let mut words = self.words.iter().map(String::as_str).collect::<Vec<_>>();
+        words.sort_unstable();
+        words
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_and_write() {
+        let used = UsedWords::read_from("Stain\n\n  satin \nSTAIN\n".as_bytes()).unwrap();
+        assert_eq!(used.len(), 2);
+        assert!(used.contains("stain"));
+        assert!(used.contains("SATIN"));
+        assert!(!used.contains("ant"));
+
+        let mut written = Vec::new();
+        used.write_to(&mut written).unwrap();
+        assert_eq!(written, b"satin\nstain\n");
+    }
+
+    #[test]
+    fn load_and_save() {
+        let path = std::env::temp_dir().join(format!(
+            "word-puzzle-searcher-used-{}.txt",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        assert!(UsedWords::load(&path).unwrap().is_empty());
+
+        let mut used = UsedWords::new();
+        assert!(used.insert("ant"));
+        assert!(!used.insert("ANT"));
+        used.save(&path).unwrap();
+
+        // a word added by hand in the meantime is kept by the next change
+        fs::write(&path, "ant\ntan\n").unwrap();
+        let mut used = UsedWords::load(&path).unwrap();
+        used.insert("stain");
+        used.save(&path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "ant\nstain\ntan\n");
+
+        used.clear();
+        used.save(&path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+        fs::remove_file(&path).unwrap();
+    }
+}
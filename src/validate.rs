@@ -0,0 +1,558 @@
+//! Checks of dictionary files, for release pipelines
+//!
+//! `validate` reads a dictionary file without stopping at the problems
+//! `read_dict` rejects, and reports each one as a `Finding` of a rule of
+//! `RULES`. The rule ids and the JSON form of a report (`Report::json`) are
+//! stable, so that scripts can rely on them: rules and fields may be added,
+//! but never renamed or removed.
+
+use crate::count::CountSet;
+use crate::dict::OffsetLength;
+use crate::format::{read_raw, ReadError};
+use crate::history::write_json_string;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Read};
+use std::str::FromStr;
+
+/// Version of the JSON form of reports, increased if it ever has to change
+/// incompatibly
+pub const REPORT_VERSION: u32 = 1;
+
+/// How serious a finding is, from the least serious
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Something a correct writer doesn't produce, but that reads fine
+    Warning,
+    /// Something `read_dict` rejects, or that makes searches wrong
+    Error,
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "warning" => Ok(Severity::Warning),
+            "error" => Ok(Severity::Error),
+            _ => Err(format!(
+                "unknown severity {:?} (expected warning or error)",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+/// Forms a report can be written in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// A line per finding, then the number of errors and warnings
+    Text,
+    /// `Report::json`
+    Json,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(ReportFormat::Text),
+            "json" => Ok(ReportFormat::Json),
+            _ => Err(format!(
+                "unknown report format {:?} (expected text or json)",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ReportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ReportFormat::Text => "text",
+            ReportFormat::Json => "json",
+        })
+    }
+}
+
+/// A check of dictionary files
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rule {
+    /// Stable identifier, in snake case
+    pub id: &'static str,
+    pub severity: Severity,
+    pub description: &'static str,
+}
+
+/// Every rule, in the order findings of the same entry are reported
+pub const RULES: [Rule; 6] = [
+    Rule {
+        id: "unreadable",
+        severity: Severity::Error,
+        description: "the file isn't a dictionary file, or is cut short",
+    },
+    Rule {
+        id: "out_of_bounds_entry",
+        severity: Severity::Error,
+        description: "an entry isn't a range of the word string",
+    },
+    Rule {
+        id: "countset_mismatch",
+        severity: Severity::Error,
+        description: "the letter counts of an entry don't match its word",
+    },
+    Rule {
+        id: "duplicate_word",
+        severity: Severity::Warning,
+        description: "a word is spelled by more than one entry",
+    },
+    Rule {
+        id: "overlapping_entry",
+        severity: Severity::Warning,
+        description: "an entry shares bytes of the word string with another",
+    },
+    Rule {
+        id: "trailing_bytes",
+        severity: Severity::Warning,
+        description: "data follows the end of the dictionary",
+    },
+];
+
+/// The rule with this id
+pub fn rule(id: &str) -> Option<&'static Rule> {
+    RULES.iter().find(|rule| rule.id == id)
+}
+
+/// A problem found in a dictionary file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Finding {
+    pub rule: &'static Rule,
+    /// Index of the entry in the entry table of the file, if the finding is
+    /// about an entry
+    pub entry: Option<usize>,
+    /// Offset of the entry in the word string
+    pub offset: Option<usize>,
+    pub message: String,
+}
+
+impl Finding {
+    fn new(id: &str, entry: Option<(usize, OffsetLength)>, message: String) -> Self {
+        Self {
+            rule: rule(id).unwrap(),
+            entry: entry.map(|(index, _)| index),
+            offset: entry.map(|(_, (offset, _))| offset),
+            message,
+        }
+    }
+}
+
+/// Writes the finding as a line of text, e.g.
+/// `error[countset_mismatch] entry 3, offset 12: ...`.
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]", self.rule.severity, self.rule.id)?;
+        match (self.entry, self.offset) {
+            (Some(entry), Some(offset)) => write!(f, " entry {}, offset {}", entry, offset)?,
+            (Some(entry), None) => write!(f, " entry {}", entry)?,
+            _ => {}
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// The findings of `validate`, in order of entry, the findings about the
+/// whole file last
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    /// Format version of the file, if it could be read
+    pub format_version: Option<u32>,
+    pub findings: Vec<Finding>,
+}
+
+impl Report {
+    /// Number of findings of the rule with this id
+    pub fn count(&self, id: &str) -> usize {
+        self.findings
+            .iter()
+            .filter(|finding| finding.rule.id == id)
+            .count()
+    }
+
+    /// Number of findings of this severity
+    pub fn count_severity(&self, severity: Severity) -> usize {
+        self.findings
+            .iter()
+            .filter(|finding| finding.rule.severity == severity)
+            .count()
+    }
+
+    /// Whether any finding is at least as serious as `threshold`
+    pub fn fails(&self, threshold: Severity) -> bool {
+        self.findings
+            .iter()
+            .any(|finding| finding.rule.severity >= threshold)
+    }
+
+    /// The report in JSON, on one line:
+    ///
+    /// ```json
+    /// {"version":1,"format_version":2,"findings":[{"rule_id":"duplicate_word",
+    /// "severity":"warning","entry":4,"offset":8,"message":"..."}],"summary":
+    /// {"errors":0,"warnings":1,"rules":{"unreadable":0,...}}}
+    /// ```
+    ///
+    /// `entry` and `offset` are `null` for the findings about the whole file,
+    /// and `format_version` for unreadable files. The summary counts the
+    /// findings of every rule of `RULES`, none included.
+    pub fn json(&self) -> impl fmt::Display + '_ {
+        JsonReport(self)
+    }
+}
+
+struct JsonReport<'a>(&'a Report);
+
+impl fmt::Display for JsonReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let report = self.0;
+        let null = |value: Option<usize>| value.map_or(String::from("null"), |v| v.to_string());
+
+        write!(
+            f,
+            "{{\"version\":{},\"format_version\":{},\"findings\":[",
+            REPORT_VERSION,
+            null(report.format_version.map(|version| version as usize))
+        )?;
+        for (i, finding) in report.findings.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(
+                f,
+                "{{\"rule_id\":\"{}\",\"severity\":\"{}\",\"entry\":{},\"offset\":{},\"message\":",
+                finding.rule.id,
+                finding.rule.severity,
+                null(finding.entry),
+                null(finding.offset)
+            )?;
+            write_json_string(f, &finding.message)?;
+            f.write_str("}")?;
+        }
+        write!(
+            f,
+            "],\"summary\":{{\"errors\":{},\"warnings\":{},\"rules\":{{",
+            report.count_severity(Severity::Error),
+            report.count_severity(Severity::Warning)
+        )?;
+        for (i, rule) in RULES.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "\"{}\":{}", rule.id, report.count(rule.id))?;
+        }
+        f.write_str("}}}")
+    }
+}
+
+/// Checks a dictionary file against every rule of `RULES`. Only I/O errors
+/// other than a file cut short are errors; everything else is a finding.
+pub fn validate<R: Read + ?Sized>(reader: &mut R) -> io::Result<Report> {
+    let raw = match read_raw(reader) {
+        Ok(raw) => raw,
+        Err(ReadError::IoError(err)) if err.kind() != io::ErrorKind::UnexpectedEof => {
+            return Err(err)
+        }
+        Err(err) => {
+            let message = match err {
+                ReadError::IoError(_) => String::from("the file ends early"),
+                err => err.to_string(),
+            };
+            return Ok(Report {
+                format_version: None,
+                findings: vec![Finding::new("unreadable", None, message)],
+            });
+        }
+    };
+
+    let mut findings = Vec::new();
+    let mut indexes = HashMap::new();
+    let mut in_bounds = Vec::new();
+    for (index, &(key, ref set)) in raw.entries.iter().enumerate() {
+        let (offset, len) = key;
+        let entry = Some((index, key));
+        if !raw.in_bounds(key) {
+            findings.push(Finding::new(
+                "out_of_bounds_entry",
+                entry,
+                format!(
+                    "the {} bytes at offset {} aren't within the {}-byte word string, \
+                     or split a character",
+                    len,
+                    offset,
+                    raw.word_string.len()
+                ),
+            ));
+            continue;
+        }
+
+        let word = &raw.word_string[offset..(offset + len)];
+        if CountSet::from_bytes_checked(set.clone().into(), word).is_err() {
+            findings.push(Finding::new(
+                "countset_mismatch",
+                entry,
+                format!("letter counts {} don't match the word {:?}", set, word),
+            ));
+        }
+        match indexes.get(&key) {
+            Some(first) => findings.push(Finding::new(
+                "duplicate_word",
+                entry,
+                format!("the word {:?} repeats entry {}", word, first),
+            )),
+            None => {
+                indexes.insert(key, index);
+                in_bounds.push((key, set.clone()));
+            }
+        }
+    }
+
+    let (format_version, trailing_bytes) = (raw.version, raw.trailing_bytes);
+    let mut raw = raw;
+    raw.entries = in_bounds;
+    // the entries out of bounds were left out
+    let dict = unsafe { raw.into_dict() };
+    let word = |(offset, len): OffsetLength| &dict.word_string()[offset..(offset + len)];
+
+    for group in dict.duplicate_entries() {
+        let mut group = group
+            .into_iter()
+            .map(|key| (indexes[&key], key))
+            .collect::<Vec<_>>();
+        group.sort_unstable();
+        let (first, _) = group[0];
+        for &(index, key) in group[1..].iter() {
+            findings.push(Finding::new(
+                "duplicate_word",
+                Some((index, key)),
+                format!("the word {:?} repeats entry {}", word(key), first),
+            ));
+        }
+    }
+    // reported on the entry starting inside the other
+    for (outer, inner) in dict.overlapping_entries() {
+        findings.push(Finding::new(
+            "overlapping_entry",
+            Some((indexes[&inner], inner)),
+            format!(
+                "the word {:?} overlaps {:?} of entry {}",
+                word(inner),
+                word(outer),
+                indexes[&outer]
+            ),
+        ));
+    }
+
+    let position = |finding: &Finding| {
+        let rule = RULES.iter().position(|rule| rule == finding.rule);
+        (finding.entry.unwrap_or(usize::MAX), rule)
+    };
+    findings.sort_by_key(position);
+
+    if trailing_bytes > 0 {
+        findings.push(Finding::new(
+            "trailing_bytes",
+            None,
+            format!("{} bytes follow the end of the dictionary", trailing_bytes),
+        ));
+    }
+
+    Ok(Report {
+        format_version: Some(format_version),
+        findings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dict::Dictionary;
+    use crate::format::write_dict_deterministic;
+
+    const USIZE: usize = std::mem::size_of::<usize>();
+    // magic, version, entry count and word string length
+    const HEADER: usize = 8 + 2 * USIZE;
+
+    // a dictionary file of these words, with the entry table in word string
+    // order, then this tampering
+    fn dict_file<F: FnOnce(&mut Vec<u8>)>(words: &[&str], tamper: F) -> Vec<u8> {
+        let dict = words.iter().collect::<Dictionary>();
+        let mut file = Vec::new();
+        write_dict_deterministic(&dict, &mut file).unwrap();
+        tamper(&mut file);
+        file
+    }
+
+    fn set_entry(file: &mut [u8], index: usize, offset: usize, len: usize) {
+        let words = usize::from_le_bytes(file[(8 + USIZE)..HEADER].try_into().unwrap());
+        let start = HEADER + words + index * (2 * USIZE + 13);
+        file[start..(start + USIZE)].copy_from_slice(&offset.to_le_bytes());
+        file[(start + USIZE)..(start + 2 * USIZE)].copy_from_slice(&len.to_le_bytes());
+    }
+
+    fn ids(report: &Report) -> Vec<(&str, Option<usize>)> {
+        report
+            .findings
+            .iter()
+            .map(|finding| (finding.rule.id, finding.entry))
+            .collect()
+    }
+
+    #[test]
+    fn clean() {
+        let report = validate(&mut &dict_file(&["cat", "dog"], |_| {})[..]).unwrap();
+        assert_eq!(report.format_version, Some(2));
+        assert!(report.findings.is_empty());
+        assert!(!report.fails(Severity::Warning));
+    }
+
+    #[test]
+    fn findings() {
+        // "catactstank": "cat" points past the words, and "act" at the "tan"
+        // of "stank"
+        let file = dict_file(&["cat", "act", "stank"], |file| {
+            set_entry(file, 0, 100, 3);
+            set_entry(file, 1, 7, 3);
+            file.extend_from_slice(b"junk");
+        });
+        let report = validate(&mut &file[..]).unwrap();
+
+        assert_eq!(
+            ids(&report),
+            [
+                ("out_of_bounds_entry", Some(0)),
+                ("countset_mismatch", Some(1)),
+                ("overlapping_entry", Some(1)),
+                ("trailing_bytes", None),
+            ]
+        );
+        let messages = report
+            .findings
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            [
+                "error[out_of_bounds_entry] entry 0, offset 100: the 3 bytes at offset 100 \
+                 aren't within the 11-byte word string, or split a character",
+                "error[countset_mismatch] entry 1, offset 7: letter counts act don't match \
+                 the word \"tan\"",
+                "warning[overlapping_entry] entry 1, offset 7: the word \"tan\" overlaps \
+                 \"stank\" of entry 2",
+                "warning[trailing_bytes]: 4 bytes follow the end of the dictionary",
+            ]
+        );
+        assert_eq!(report.count_severity(Severity::Error), 2);
+        assert_eq!(report.count_severity(Severity::Warning), 2);
+        assert!(report.fails(Severity::Error));
+    }
+
+    #[test]
+    fn duplicate_word() {
+        // "catdogtac", with the entry of "tac" spelling "cat" instead
+        let file = dict_file(&["cat", "dog", "tac"], |file| {
+            set_entry(file, 2, 0, 3);
+        });
+        let report = validate(&mut &file[..]).unwrap();
+        assert_eq!(ids(&report), [("duplicate_word", Some(2))]);
+        assert_eq!(
+            report.findings[0].message,
+            "the word \"cat\" repeats entry 0"
+        );
+        assert!(!report.fails(Severity::Error));
+        assert!(report.fails(Severity::Warning));
+
+        // the same word at two offsets
+        let file = dict_file(&["cat", "dog", "tac"], |file| {
+            file[(HEADER + 6)..(HEADER + 9)].copy_from_slice(b"cat");
+        });
+        let report = validate(&mut &file[..]).unwrap();
+        assert_eq!(ids(&report), [("duplicate_word", Some(2))]);
+    }
+
+    #[test]
+    fn unreadable() {
+        let mut file = dict_file(&["cat"], |_| {});
+        file.truncate(file.len() - 1);
+        let report = validate(&mut &file[..]).unwrap();
+        assert_eq!(report.format_version, None);
+        assert_eq!(ids(&report), [("unreadable", None)]);
+        assert_eq!(report.findings[0].message, "the file ends early");
+
+        let report = validate(&mut &b"not a dictionary"[..]).unwrap();
+        assert_eq!(report.findings[0].message, "wrong format");
+    }
+
+    // the JSON form is stable: this test changing means a new
+    // `REPORT_VERSION`, unless only adding fields or rules
+    #[test]
+    fn json_schema() {
+        let report = Report {
+            format_version: Some(2),
+            findings: vec![
+                Finding::new(
+                    "countset_mismatch",
+                    Some((3, (12, 4))),
+                    String::from("letter counts \"a\" don't match"),
+                ),
+                Finding::new("trailing_bytes", None, String::from("4 bytes")),
+            ],
+        };
+        assert_eq!(
+            report.json().to_string(),
+            "{\"version\":1,\"format_version\":2,\"findings\":[\
+             {\"rule_id\":\"countset_mismatch\",\"severity\":\"error\",\"entry\":3,\
+             \"offset\":12,\"message\":\"letter counts \\\"a\\\" don't match\"},\
+             {\"rule_id\":\"trailing_bytes\",\"severity\":\"warning\",\"entry\":null,\
+             \"offset\":null,\"message\":\"4 bytes\"}],\
+             \"summary\":{\"errors\":1,\"warnings\":1,\"rules\":{\"unreadable\":0,\
+             \"out_of_bounds_entry\":0,\"countset_mismatch\":1,\"duplicate_word\":0,\
+             \"overlapping_entry\":0,\"trailing_bytes\":1}}}"
+        );
+        assert_eq!(
+            Report::default().json().to_string(),
+            "{\"version\":1,\"format_version\":null,\"findings\":[],\
+             \"summary\":{\"errors\":0,\"warnings\":0,\"rules\":{\"unreadable\":0,\
+             \"out_of_bounds_entry\":0,\"countset_mismatch\":0,\"duplicate_word\":0,\
+             \"overlapping_entry\":0,\"trailing_bytes\":0}}}"
+        );
+    }
+
+    #[test]
+    fn rules() {
+        let ids = RULES.map(|rule| rule.id);
+        assert_eq!(
+            ids,
+            [
+                "unreadable",
+                "out_of_bounds_entry",
+                "countset_mismatch",
+                "duplicate_word",
+                "overlapping_entry",
+                "trailing_bytes"
+            ]
+        );
+        assert!(Severity::Warning < Severity::Error);
+        assert_eq!("warning".parse(), Ok(Severity::Warning));
+        assert!("fatal".parse::<Severity>().is_err());
+    }
+}
@@ -0,0 +1,231 @@
+//! Guards the public API against accidental changes
+//!
+//! The signature of every public item of `src/` is listed in
+//! `tests/data/api.txt`; after a deliberate change, regenerate the listing
+//! with `UPDATE_API_SNAPSHOT=1 cargo test --test api` and review its diff.
+
+use std::fs;
+use std::path::Path;
+
+use word_puzzle_searcher::prelude::*;
+
+// Lists the public items of a source file, one per line, each prefixed with
+// its module, its enclosing item (impl, struct, enum or module) if any, and
+// its `cfg` attribute if any. Signatures are cut before their body and
+// their whitespace is collapsed, so only changes to them show up.
+fn public_items(module: &str, source: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    // the top-level block being read, if any: its header, whether it's a
+    // public enum (whose variants are public), and its `cfg` attribute
+    let mut block: Option<(String, bool, Option<String>)> = None;
+    let mut cfg: Option<String> = None;
+    let mut lines = source.lines();
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+        let top_level = !line.starts_with(' ');
+
+        // tests aren't part of the API
+        if top_level && trimmed == "#[cfg(test)]" {
+            break;
+        }
+        if trimmed.starts_with("#[cfg(") {
+            cfg = Some(trimmed.to_owned());
+            continue;
+        }
+        if trimmed.starts_with("#[") || trimmed.starts_with("//") || trimmed.is_empty() {
+            continue;
+        }
+        if top_level && trimmed == "}" {
+            block = None;
+            continue;
+        }
+
+        // items may span several lines, up to their body or semicolon,
+        // while fields and variants take one line
+        let mut signature = trimmed.to_owned();
+        let in_items = block
+            .as_ref()
+            .is_some_and(|(header, _, _)| header.starts_with("impl") || header.contains("mod "));
+        let is_item =
+            ["pub ", "impl"].iter().any(|p| signature.starts_with(p)) && (top_level || in_items);
+        while is_item && !signature.contains('{') && !signature.ends_with(';') {
+            match lines.next() {
+                Some(next) => {
+                    signature.push(' ');
+                    signature.push_str(next.trim());
+                }
+                None => break,
+            }
+        }
+        let signature = signature
+            .split(" {")
+            .next()
+            .unwrap()
+            .trim_end_matches([';', ',', '{'])
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let in_block = block.as_ref().filter(|_| !top_level);
+        let variant = in_block.is_some_and(|&(_, is_enum, _)| is_enum)
+            && line.starts_with("    ")
+            && line[4..].starts_with(|c: char| c.is_ascii_uppercase());
+        if signature.starts_with("pub ") || variant {
+            let mut item = format!("{}: ", module);
+            if let Some(cfg) = cfg
+                .as_ref()
+                .or(in_block.and_then(|(_, _, cfg)| cfg.as_ref()))
+            {
+                item.push_str(cfg);
+                item.push(' ');
+            }
+            if let Some((header, _, _)) = in_block {
+                item.push_str(header);
+                item.push_str(" :: ");
+            }
+            item.push_str(&signature);
+            items.push(item);
+        }
+
+        if top_level && trimmed.ends_with('{') {
+            let is_enum = signature.starts_with("pub enum");
+            block = Some((signature, is_enum, cfg.take()));
+        }
+        cfg = None;
+    }
+
+    items
+}
+
+fn api_listing() -> String {
+    let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+    let mut files = fs::read_dir(&src)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "rs"))
+        .filter(|path| path.file_stem().is_some_and(|stem| stem != "main"))
+        .collect::<Vec<_>>();
+    files.sort();
+
+    let mut listing = String::new();
+    for path in files {
+        let module = match path.file_stem().unwrap().to_str().unwrap() {
+            "lib" => "crate",
+            stem => stem,
+        };
+        for item in public_items(module, &fs::read_to_string(&path).unwrap()) {
+            listing.push_str(&item);
+            listing.push('\n');
+        }
+    }
+
+    listing
+}
+
+// the stable core is enough to build, save, load and search a dictionary
+#[test]
+fn prelude() {
+    let mut builder = DictionaryBuilder::new(BuildOptions::default());
+    for line in ["stain", "satin", "ant"] {
+        builder.add_line(line).unwrap();
+    }
+    let (dict, _) = builder.finish();
+
+    let mut bytes = Vec::new();
+    write_dict(&dict, &mut bytes).unwrap();
+    let dict: Dictionary = read_dict(&mut bytes.as_slice()).unwrap();
+
+    let rack = Rack::parse("satin", &RackOptions::default()).unwrap();
+    let mut query = SearchQuery::new(rack);
+    query.pattern = Some(Pattern::parse("s*").unwrap());
+    let indexes = Indexes {
+        letters: Some(LetterIndex::build(&dict)),
+        sorted: Some(SortedIndex::build(&dict)),
+    };
+    let mut results: SearchResults = query.search(&dict, &indexes);
+    results.sort(SortOrder::Alpha);
+    assert_eq!(results.words, ["satin", "stain"]);
+}
+
+#[test]
+fn api_snapshot() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/api.txt");
+    let listing = api_listing();
+
+    if std::env::var_os("UPDATE_API_SNAPSHOT").is_some() {
+        fs::write(&path, &listing).unwrap();
+        return;
+    }
+
+    let snapshot = fs::read_to_string(&path).unwrap_or_default();
+    let removed = snapshot
+        .lines()
+        .filter(|line| !listing.lines().any(|item| item == *line))
+        .collect::<Vec<_>>();
+    let added = listing
+        .lines()
+        .filter(|line| !snapshot.lines().any(|item| item == *line))
+        .collect::<Vec<_>>();
+    assert!(
+        removed.is_empty() && added.is_empty(),
+        "the public API changed (rerun with UPDATE_API_SNAPSHOT=1 if deliberate)\n\
+         removed:\n  {}\nadded:\n  {}",
+        removed.join("\n  "),
+        added.join("\n  ")
+    );
+}
+
+#[test]
+fn public_items_listing() {
+    let source = "\
+pub struct Rack {
+    pub blanks: u16,
+    letters: [u16; 26],
+}
+
+pub enum Key {
+    /// a variant
+    Up,
+    Char(char),
+}
+
+impl Rack {
+    pub fn parse(
+        input: &str,
+    ) -> Result<Self, CountError> {
+        todo!()
+    }
+
+    #[cfg(feature = \"unstable\")]
+    pub fn blanks(&self) -> u16 {
+        self.blanks
+    }
+
+    fn private(&self) {}
+}
+
+#[cfg(feature = \"tui\")]
+pub fn run() {}
+
+pub(crate) fn internal() {}
+
+#[cfg(test)]
+mod tests {
+    pub fn helper() {}
+}
+";
+    assert_eq!(
+        public_items("rack", source),
+        [
+            "rack: pub struct Rack",
+            "rack: pub struct Rack :: pub blanks: u16",
+            "rack: pub enum Key",
+            "rack: pub enum Key :: Up",
+            "rack: pub enum Key :: Char(char)",
+            "rack: impl Rack :: pub fn parse( input: &str, ) -> Result<Self, CountError>",
+            "rack: #[cfg(feature = \"unstable\")] impl Rack :: pub fn blanks(&self) -> u16",
+            "rack: #[cfg(feature = \"tui\")] pub fn run()",
+        ]
+    );
+}
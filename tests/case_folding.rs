@@ -0,0 +1,114 @@
+//! The same logical query must find the same words whatever the case of the
+//! rack, of the dictionary and of the filters, with or without indexes.
+
+use word_puzzle_searcher::count::CountSet;
+use word_puzzle_searcher::dict::Dictionary;
+use word_puzzle_searcher::index::{Indexes, LetterIndex, SortedIndex};
+use word_puzzle_searcher::pattern::Pattern;
+use word_puzzle_searcher::rack::{Rack, RackOptions};
+use word_puzzle_searcher::search::SearchQuery;
+
+const WORDS: &[&str] = &["stain", "satin", "ant", "tan", "ants", "saint", "quit"];
+
+fn lower(s: &str) -> String {
+    s.to_ascii_lowercase()
+}
+
+fn upper(s: &str) -> String {
+    s.to_ascii_uppercase()
+}
+
+// alternating case, e.g. `SaTiN`
+fn mixed(s: &str) -> String {
+    s.chars()
+        .enumerate()
+        .map(|(i, c)| {
+            if i % 2 == 0 {
+                c.to_ascii_uppercase()
+            } else {
+                c.to_ascii_lowercase()
+            }
+        })
+        .collect()
+}
+
+type Case = fn(&str) -> String;
+type Filter = Box<dyn Fn(&mut SearchQuery, Case)>;
+
+const CASES: &[(&str, Case)] = &[("lower", lower), ("upper", upper), ("mixed", mixed)];
+
+// the filters under test, applied to a query with the letters in `case`
+fn filters() -> Vec<(&'static str, Filter)> {
+    vec![
+        ("count", Box::new(|_, _| {})),
+        (
+            "require",
+            Box::new(|query, case| query.require = CountSet::from_word(&case("s")).unwrap()),
+        ),
+        (
+            "prefix",
+            Box::new(|query, case| query.starts_with = case("sa")),
+        ),
+        // uppercase letters are placeholders in patterns, so only the
+        // dictionary and the rack vary here
+        (
+            "pattern",
+            Box::new(|query, _| query.pattern = Some(Pattern::parse("s*n").unwrap())),
+        ),
+    ]
+}
+
+fn search(dict: &Dictionary, indexes: &Indexes, query: &SearchQuery) -> Vec<String> {
+    let mut words = query
+        .search(dict, indexes)
+        .words
+        .iter()
+        .map(|word| word.to_ascii_lowercase())
+        .collect::<Vec<_>>();
+    words.sort_unstable();
+    words
+}
+
+#[test]
+fn case_matrix() {
+    let reference = WORDS.iter().collect::<Dictionary>();
+
+    for (filter_name, filter) in filters() {
+        let mut expected_query =
+            SearchQuery::new(Rack::parse("satin?", &RackOptions::default()).unwrap());
+        filter(&mut expected_query, lower);
+        let expected = search(&reference, &Indexes::default(), &expected_query);
+        assert!(!expected.is_empty(), "{} finds nothing", filter_name);
+
+        for &(dict_name, dict_case) in CASES {
+            let mut dict = Dictionary::new();
+            for word in WORDS {
+                dict.add(&dict_case(word)).unwrap();
+            }
+            let indexed = Indexes {
+                letters: Some(LetterIndex::build(&dict)),
+                sorted: Some(SortedIndex::build(&dict)),
+            };
+
+            for &(query_name, query_case) in CASES {
+                let rack = Rack::parse(&query_case("satin?"), &RackOptions::default()).unwrap();
+                let mut query = SearchQuery::new(rack);
+                filter(&mut query, query_case);
+
+                for (indexes_name, indexes) in
+                    [("scan", &Indexes::default()), ("indexed", &indexed)]
+                {
+                    assert_eq!(
+                        search(&dict, indexes, &query),
+                        expected,
+                        "{} filter, {} dictionary, {} query, {}",
+                        filter_name,
+                        dict_name,
+                        query_name,
+                        indexes_name
+                    );
+                }
+            }
+        }
+    }
+}
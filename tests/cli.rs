@@ -0,0 +1,1806 @@
+//! End-to-end tests of the command line interface
+//!
+//! Every subcommand must write only its data to the standard output, so that
+//! it can be piped safely; everything else goes to the standard error.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use word_puzzle_searcher::count::CountSet;
+use word_puzzle_searcher::dict::Dictionary;
+use word_puzzle_searcher::echo::QueryEcho;
+use word_puzzle_searcher::format;
+use word_puzzle_searcher::hash;
+use word_puzzle_searcher::index::Indexes;
+use word_puzzle_searcher::rack::{Rack, RackOptions};
+use word_puzzle_searcher::sample;
+use word_puzzle_searcher::search::SearchQuery;
+
+const WORDS: &str = "stain\nsatin\nant\ntan\nants\nquit\nzoo\n";
+
+// a fresh directory for each test, so tests can run in parallel
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "word-puzzle-searcher-{}-{}",
+        name,
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn run(args: &[&str]) -> Output {
+    let output = Command::new(env!("CARGO_BIN_EXE_word-puzzle-searcher"))
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output
+}
+
+fn run_failing(args: &[&str]) -> Output {
+    let output = Command::new(env!("CARGO_BIN_EXE_word-puzzle-searcher"))
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(!output.status.success(), "{:?} succeeded", args);
+    output
+}
+
+fn stdout(output: &Output) -> &str {
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+// generates the test dictionary, returning its path
+fn generate(dir: &Path) -> String {
+    let words = dir.join("words.txt");
+    fs::write(&words, WORDS).unwrap();
+    let dict = dir.join("test.dict");
+
+    let output = run(&[
+        "generate",
+        "-o",
+        dict.to_str().unwrap(),
+        words.to_str().unwrap(),
+    ]);
+    assert_eq!(stdout(&output), "");
+    assert!(!output.stderr.is_empty());
+
+    dict.to_str().unwrap().to_owned()
+}
+
+#[test]
+fn generate_to_stdout() {
+    let dir = temp_dir("generate-stdout");
+    let dict = generate(&dir);
+
+    let words = dir.join("words.txt");
+    let output = run(&["generate", "-o", "-", words.to_str().unwrap()]);
+    assert!(output.stdout.starts_with(b"DICT"));
+
+    // the dictionary written to the standard output is a valid dictionary
+    let piped = dir.join("piped.dict");
+    fs::write(&piped, &output.stdout).unwrap();
+    let output = run(&["search", "-d", piped.to_str().unwrap(), "satin"]);
+    let expected = run(&["search", "-d", &dict, "satin"]);
+    assert_eq!(output.stdout, expected.stdout);
+}
+
+#[test]
+fn deterministic() {
+    let dir = temp_dir("deterministic");
+    generate(&dir);
+    let words = dir.join("words.txt");
+
+    let generate = || {
+        run(&[
+            "generate",
+            "--deterministic",
+            "-o",
+            "-",
+            words.to_str().unwrap(),
+        ])
+        .stdout
+    };
+    let first = generate();
+    assert!((0..3).all(|_| generate() == first));
+}
+
+// the same queries give the same bytes whatever the number of threads
+#[test]
+fn deterministic_threads() {
+    let dir = temp_dir("deterministic-threads");
+    // enough words for the work to be split between threads, with spellings
+    // differing by case
+    let mut seed = 1u32;
+    let mut words = String::from("at\nCat\ncat\nCAT\ncats\nrat\nRat\n");
+    for _ in 0..5000 {
+        seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+        let len = 2 + (seed >> 16) as usize % 6;
+        for i in 0..len {
+            words.push(b"aeinrstlo"[(seed >> i) as usize % 9] as char);
+        }
+        words.push('\n');
+    }
+    let (list, fallback_list) = (dir.join("words.txt"), dir.join("fallback.txt"));
+    fs::write(&list, &words[..(words.len() / 2)]).unwrap();
+    fs::write(&fallback_list, &words).unwrap();
+    let racks = dir.join("racks.txt");
+    fs::write(&racks, "retains\nslot?\nat\n").unwrap();
+    let (dict, fallback) = (dir.join("words.dict"), dir.join("fallback.dict"));
+    let (dict, fallback) = (dict.to_str().unwrap(), fallback.to_str().unwrap());
+    run(&["generate", "-q", "-o", dict, list.to_str().unwrap()]);
+    run(&[
+        "generate",
+        "-q",
+        "-o",
+        fallback,
+        fallback_list.to_str().unwrap(),
+    ]);
+
+    let list = list.to_str().unwrap();
+    fn search<'a>(dict: &'a str, extra: &[&'a str]) -> Vec<&'a str> {
+        [&["search", "-d", dict, "retains?"][..], extra].concat()
+    }
+    let battery = [
+        vec!["generate", "-o", "-", list],
+        search(dict, &[]),
+        search(dict, &["--sort", "consumed"]),
+        search(
+            dict,
+            &["--sort", "consumed", "--limit", "20", "--offset", "5"],
+        ),
+        search(dict, &["--prioritize", "st"]),
+        search(dict, &["--group-by-length", "--show-remaining"]),
+        search(dict, &["--collapse-variants", "--show-variants"]),
+        search(dict, &["--anagram-summary", "--sort", "consumed"]),
+        search(dict, &["--fallback", fallback, "--sort", "consumed"]),
+        search(dict, &["--template", r"<word>\t<score>\t<remaining>"]),
+        search(dict, &["--invert", "--limit", "50"]),
+        vec!["search", "-d", dict, "--batch", racks.to_str().unwrap()],
+        vec!["hooks", "-d", dict, "at"],
+        vec!["export", "-d", dict, "-f", "anagram-keys"],
+        vec!["shortlist", "-d", dict],
+        vec!["pick", "-d", dict, "-l", "5", "--seed", "7"],
+        vec!["compare", dict, fallback, "-c", "20", "--seed", "3"],
+    ];
+    for args in battery {
+        let args = [&["--deterministic", "-q"][..], &args].concat();
+        let outputs = ["1", "2", "8"].map(|threads| {
+            let output = Command::new(env!("CARGO_BIN_EXE_word-puzzle-searcher"))
+                .args(&args)
+                .env("RAYON_NUM_THREADS", threads)
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "{:?} failed", args);
+            output.stdout
+        });
+        assert!(!outputs[0].is_empty(), "{:?}", args);
+        assert!(
+            outputs.iter().all(|output| *output == outputs[0]),
+            "{:?} isn't deterministic",
+            args
+        );
+    }
+
+    // the commands that can't keep the guarantee refuse it
+    for (args, reason) in [
+        (&["pick", "-d", dict, "-l", "5"][..], "needs --seed"),
+        (&["search", "-d", dict, "at", "--tui"], "interactive"),
+    ] {
+        let args = [&["--deterministic"][..], args].concat();
+        let stderr = String::from_utf8(run_failing(&args).stderr).unwrap();
+        assert!(stderr.contains(reason), "{}", stderr);
+    }
+}
+
+#[test]
+fn if_newer() {
+    let dir = temp_dir("if-newer");
+    let words = dir.join("words.txt");
+    fs::write(&words, WORDS).unwrap();
+    let (dict, index) = (dir.join("words.dict"), dir.join("words.dict.sidx"));
+    let generate = |extra: &[&str]| {
+        let args = [
+            &["generate", "-o", dict.to_str().unwrap(), "--if-newer"][..],
+            extra,
+            &[words.to_str().unwrap()],
+        ]
+        .concat();
+        String::from_utf8(run(&args).stderr).unwrap()
+    };
+    let modified = |path: &Path| fs::metadata(path).unwrap().modified().unwrap();
+    let set_modified = |path: &Path, time| {
+        let file = fs::OpenOptions::new().write(true).open(path).unwrap();
+        file.set_modified(time).unwrap();
+    };
+    let an_hour = std::time::Duration::from_secs(3600);
+
+    // missing, then up to date
+    assert!(generate(&[]).contains("Added 7 words"));
+    let generated = modified(&dict);
+    assert!(generate(&[]).contains("is up to date"));
+    assert_eq!(modified(&dict), generated);
+
+    // the word list changed since
+    fs::write(&words, "ant\ntan\n").unwrap();
+    set_modified(&dict, modified(&words) - an_hour);
+    assert!(generate(&[]).contains("Added 2 words"));
+    assert!(generate(&[]).contains("is up to date"));
+
+    // a missing or older sorted index counts too
+    assert!(generate(&["--sorted-index"]).contains("Added 2 words"));
+    assert!(generate(&["--sorted-index"]).contains("is up to date"));
+    set_modified(&index, modified(&words) - an_hour);
+    assert!(generate(&["--sorted-index"]).contains("Added 2 words"));
+
+    assert!(generate(&["--force"]).contains("Added 2 words"));
+    let output = run_failing(&["generate", "-o", "-", "--if-newer", words.to_str().unwrap()]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--if-newer needs an output file"));
+
+    // the error names the missing input
+    let missing = dir.join("missing.txt");
+    let output = run_failing(&[
+        "generate",
+        "-o",
+        dict.to_str().unwrap(),
+        "--if-newer",
+        missing.to_str().unwrap(),
+    ]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("missing.txt"));
+}
+
+#[test]
+fn search() {
+    let dir = temp_dir("search");
+    let dict = generate(&dir);
+
+    let output = run(&[
+        "search",
+        "-d",
+        &dict,
+        "satin",
+        "--stats",
+        "--report-unusable",
+    ]);
+    assert_eq!(stdout(&output), "ant\nants\nsatin\nstain\ntan\n");
+    assert!(!output.stderr.is_empty());
+
+    let output = run(&["search", "-d", &dict, "satin", "--group-by-length"]);
+    assert_eq!(
+        stdout(&output),
+        "=== 3 letters ===\nant\ntan\n=== 4 letters ===\nants\n=== 5 letters ===\nsatin\nstain\n"
+    );
+
+    let output = run(&["search", "-d", &dict, "satin", "--anagram-summary"]);
+    assert_eq!(
+        stdout(&output),
+        "ant (2): ant, tan\nanst (1): ants\nainst (2): satin, stain\n"
+    );
+}
+
+#[test]
+fn leet_spelling() {
+    let dir = temp_dir("leet-spelling");
+    let words = dir.join("words.txt");
+    fs::write(&words, "h3llo\ncat\n").unwrap();
+    let dict = dir.join("leet.dict");
+    let dict = dict.to_str().unwrap();
+    run(&[
+        "generate",
+        "--leet",
+        "--keep-leet-spelling",
+        "-o",
+        dict,
+        words.to_str().unwrap(),
+    ]);
+
+    // `h3llo` is searched, scored and grouped as `hello`
+    let search = |extra: &[&str]| {
+        let output = run(&[&["search", "-q", "-d", dict, "hellocat"][..], extra].concat());
+        stdout(&output).to_owned()
+    };
+    assert_eq!(search(&["--show-remaining"]), "cat [ehllo]\nh3llo [act]\n");
+    assert_eq!(search(&["--show-multiplicity"]), "cat [x1]\nh3llo [x1]\n");
+    assert_eq!(search(&["--prioritize", "l"]), "h3llo\ncat\n");
+    assert_eq!(search(&["--sort", "score"]), "h3llo\ncat\n");
+    assert_eq!(
+        search(&["--anagram-summary"]),
+        "act (1): cat\nehllo (1): h3llo\n"
+    );
+    assert_eq!(
+        search(&["--template", "<word> <remaining> <score>"]),
+        "cat ehllo 5\nh3llo act 8\n"
+    );
+
+    // but filtered by text as spelled
+    assert_eq!(search(&["--starts-with", "he"]), "");
+    assert_eq!(search(&["--starts-with", "h"]), "h3llo\n");
+    assert_eq!(search(&["--pattern", "he*"]), "");
+    assert_eq!(search(&["--pattern", "h_llo"]), "h3llo\n");
+}
+
+#[test]
+fn invalid_letters() {
+    let dir = temp_dir("invalid-letters");
+    let dict = generate(&dir);
+
+    // a bad character gives the same error wherever it appears
+    let errors = [
+        run_failing(&["search", "-d", &dict, "sa1n"]),
+        run_failing(&["search", "-d", &dict, "satin", "-r", "a, 1"]),
+        run_failing(&["search", "-d", &dict, "satin", "--starts-with", "s1"]),
+    ]
+    .map(|output| {
+        assert_eq!(stdout(&output), "");
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        stderr.lines().last().unwrap().to_owned()
+    });
+    assert!(errors[0].contains("NotAlphabetic"), "{}", errors[0]);
+    assert_eq!(errors[1], errors[0]);
+    assert_eq!(errors[2], errors[0]);
+}
+
+#[test]
+fn zero_lengths() {
+    let dir = temp_dir("zero-lengths");
+    let dict = generate(&dir);
+
+    for (flag, args) in [
+        ("--min-length", ["search", "-d", &dict, "satin", "-m", "0"]),
+        ("--max-length", ["search", "-d", &dict, "satin", "-M", "0"]),
+        (
+            "--default-min-length",
+            ["generate", "-o", &dict, "--default-min-length", "0", "-"],
+        ),
+        ("--min-length", ["compare", &dict, &dict, "-c1", "-m", "0"]),
+    ] {
+        let stderr = String::from_utf8(run_failing(&args).stderr).unwrap();
+        assert!(
+            stderr.contains(&format!("{} must be at least 1", flag)),
+            "{}",
+            stderr
+        );
+    }
+    // the dictionary is left as it was
+    assert_eq!(
+        stdout(&run(&["search", "-d", &dict, "satin", "-m", "5"])),
+        "satin\nstain\n"
+    );
+}
+
+// 1 and 2 letter words, allowed in Scrabble, are found end to end, and an
+// empty word kept in the dictionary never is
+#[test]
+fn short_words() {
+    let dir = temp_dir("short-words");
+    let words = dir.join("words.txt");
+    fs::write(&words, "a\nat\n\nta\ntab\nx\n").unwrap();
+    let dict = dir.join("short.dict");
+    let dict = dict.to_str().unwrap();
+    run(&[
+        "generate",
+        "-o",
+        dict,
+        "--keep-blank",
+        words.to_str().unwrap(),
+    ]);
+    let search = |extra: &[&str]| {
+        let args = [&["search", "-q", "-d", dict, "tab"][..], extra].concat();
+        stdout(&run(&args)).to_owned()
+    };
+
+    assert_eq!(search(&[]), "tab\n");
+    assert_eq!(search(&["--allow-short"]), "a\nat\nta\ntab\n");
+    assert_eq!(search(&["--min-length", "1"]), "a\nat\nta\ntab\n");
+    assert_eq!(search(&["--min-length", "2"]), "at\nta\ntab\n");
+    assert_eq!(search(&["--allow-short", "--max-length", "1"]), "a\n");
+    // the empty word doesn't match the inverted search either
+    assert_eq!(
+        search(&["--allow-short", "--invert", "--max-length", "1"]),
+        "x\n"
+    );
+
+    let output = run_failing(&["search", "-d", dict, "tab", "--allow-short", "-m", "2"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("cannot be used with"));
+}
+
+// folding and order don't depend on the locale, even a Turkish one with its
+// own rules for `i`
+#[test]
+fn locale_independent() {
+    let dir = temp_dir("locale-independent");
+    let words = dir.join("words.txt");
+    fs::write(&words, "ant\nZulu\nilk\nIlk\nink\n").unwrap();
+    let dict = dir.join("words.dict");
+    let dict = dict.to_str().unwrap();
+    let turkish = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_word-puzzle-searcher"))
+            .args(args)
+            .env("LC_ALL", "tr_TR.UTF-8")
+            .env("LANG", "tr_TR.UTF-8")
+            .output()
+            .unwrap()
+    };
+
+    assert!(
+        turkish(&["generate", "-q", "-o", dict, words.to_str().unwrap()])
+            .status
+            .success()
+    );
+    let output = turkish(&["search", "-q", "-d", dict, "antzuluIIkn"]);
+    assert_eq!(stdout(&output), "Ilk\nZulu\nant\nilk\nink\n");
+
+    // the dotted capital I isn't a letter
+    fs::write(&words, "\u{130}lk\n").unwrap();
+    assert!(!turkish(&["generate", "-o", dict, words.to_str().unwrap()])
+        .status
+        .success());
+    assert!(!turkish(&["search", "-d", dict, "\u{130}lk"])
+        .status
+        .success());
+}
+
+#[test]
+fn info() {
+    let dir = temp_dir("info");
+    let dict = generate(&dir);
+
+    let output = run(&["info", "-d", &dict]);
+    assert!(stdout(&output).contains("entries: 7\n"));
+    assert!(output.stderr.is_empty());
+
+    let read = format::read_dict(&mut fs::File::open(&dict).unwrap()).unwrap();
+    let file_hash = format::file_hash(&mut fs::File::open(&dict).unwrap()).unwrap();
+    assert!(stdout(&output).contains(&format!(
+        "content hash: sha256:{}\nfile hash: sha256:{}\n",
+        hash::to_hex(&read.content_hash()),
+        hash::to_hex(&file_hash)
+    )));
+}
+
+#[test]
+fn peek_header() {
+    let dir = temp_dir("peek-header");
+    let dict = generate(&dir);
+
+    let mut file = fs::File::open(&dict).unwrap();
+    let header = format::peek_header(&mut file).unwrap();
+    assert_eq!(header.version, format::FORMAT_VERSION);
+    assert_eq!(header.word_count, 7);
+    assert_eq!(header.str_length, WORDS.len() - 7);
+}
+
+#[test]
+fn export() {
+    let dir = temp_dir("export");
+    let dict = generate(&dir);
+
+    let output = run(&["export", "-d", &dict]);
+    assert_eq!(stdout(&output), "ant\nants\nquit\nsatin\nstain\ntan\nzoo\n");
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn anagram_keys_round_trip() {
+    let dir = temp_dir("anagram-keys");
+    let dict = generate(&dir);
+
+    let output = run(&["export", "-d", &dict, "-f", "anagram-keys"]);
+    assert_eq!(
+        stdout(&output),
+        "ainst satin stain\nanst ants\nant ant tan\niqtu quit\nooz zoo\n"
+    );
+    let classes = dir.join("classes.txt");
+    fs::write(&classes, &output.stdout).unwrap();
+
+    let other = dir.join("other.dict");
+    let other = other.to_str().unwrap();
+    run(&[
+        "generate",
+        "-q",
+        "-o",
+        other,
+        "--format",
+        "anagram-keys",
+        classes.to_str().unwrap(),
+    ]);
+    assert_eq!(
+        run(&["export", "-d", other]).stdout,
+        run(&["export", "-d", &dict]).stdout
+    );
+
+    run_failing(&[
+        "generate",
+        "-o",
+        other,
+        "--format",
+        "anagram-keys",
+        "--tier-column",
+        classes.to_str().unwrap(),
+    ]);
+}
+
+#[test]
+fn lint() {
+    let dir = temp_dir("lint");
+    let words = dir.join("words.txt");
+    fs::write(&words, "cat\ndog\ncat\nc4t\n").unwrap();
+
+    let output = run(&["lint", words.to_str().unwrap()]);
+    assert_eq!(stdout(&output), "");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("unique words: 2\n"));
+    assert!(stderr.contains("duplicates: 1\n"));
+    assert!(stderr.contains("non-letters: 1\n"));
+}
+
+#[test]
+fn compare() {
+    let dir = temp_dir("compare");
+    let dict = generate(&dir);
+
+    let output = run(&["compare", &dict, &dict, "--count", "5"]);
+    assert!(stdout(&output).starts_with("racks: 5\n"));
+    assert!(output.stderr.is_empty());
+
+    // the CSV starts with an echo of what produced it
+    let csv = dir.join("compare.csv");
+    let csv_arg = csv.to_str().unwrap();
+    run(&[
+        "compare", &dict, &dict, "--count", "5", "--seed", "7", "--csv", csv_arg,
+    ]);
+    let csv = fs::read_to_string(&csv).unwrap();
+    let (echo, table) = csv.split_at(csv.find("rack,").unwrap());
+    let echo = echo.parse::<QueryEcho>().unwrap();
+    assert_eq!(echo.seed, Some(7));
+    assert_eq!(echo.dictionaries.len(), 2);
+    assert_eq!(echo.dictionaries[0].path, dict);
+    assert_eq!(
+        echo.dictionaries[0].hash,
+        format::file_fnv_hash(&mut fs::File::open(&dict).unwrap()).unwrap()
+    );
+    let read = format::read_dict(&mut fs::File::open(&dict).unwrap()).unwrap();
+    assert_eq!(echo.dictionaries[0].words, Some(read.content_hash()));
+    assert_eq!(echo.query.min_length, 3);
+    assert_eq!(table.lines().count(), 6);
+
+    // racks with separators are quoted
+    let racks = dir.join("racks.txt");
+    fs::write(&racks, "s,a,t\nant\n").unwrap();
+    let csv = dir.join("racks.csv");
+    run(&[
+        "compare",
+        &dict,
+        &dict,
+        "--racks",
+        racks.to_str().unwrap(),
+        "--csv",
+        csv.to_str().unwrap(),
+    ]);
+    let csv = fs::read_to_string(&csv).unwrap();
+    let table = &csv[csv.find("rack,").unwrap()..];
+    assert_eq!(
+        table.lines().skip(1).collect::<Vec<_>>(),
+        ["\"s,a,t\",0,0,0", "ant,2,2,0"]
+    );
+}
+
+// generates the sample dictionary with the command line, returning its path
+fn generate_sample(dir: &Path) -> String {
+    let words = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/words.txt");
+    let dict = dir.join("sample.dict");
+    run(&[
+        "generate",
+        "-o",
+        dict.to_str().unwrap(),
+        words.to_str().unwrap(),
+    ]);
+
+    dict.to_str().unwrap().to_owned()
+}
+
+#[test]
+fn sample_search() {
+    let dir = temp_dir("sample-search");
+    let dict = generate_sample(&dir);
+
+    for rack in ["retains", "quiz?", "committee"] {
+        let output = run(&["search", "-d", &dict, rack]);
+        let query = SearchQuery {
+            min_length: 3,
+            ..SearchQuery::new(Rack::parse(rack, &RackOptions::default()).unwrap())
+        };
+        let expected = query
+            .search(&sample::dictionary(), &Indexes::default())
+            .words
+            .iter()
+            .map(|word| format!("{}\n", word))
+            .collect::<String>();
+        assert_eq!(stdout(&output), expected, "{}", rack);
+    }
+}
+
+#[test]
+fn sample_round_trip() {
+    let dir = temp_dir("sample-round-trip");
+    let generated = generate_sample(&dir);
+    let written = dir.join("written.dict");
+    sample::write_dictionary(&written).unwrap();
+
+    let read = |path: &Path| {
+        let dict = format::read_dict(&mut fs::File::open(path).unwrap()).unwrap();
+        let mut words = dict
+            .word_count()
+            .keys()
+            .map(|key| dict.get(key).unwrap().word.to_owned())
+            .collect::<Vec<_>>();
+        words.sort_unstable();
+        words
+    };
+    let mut words = sample::WORDS.lines().collect::<Vec<_>>();
+    words.sort_unstable();
+    assert_eq!(read(Path::new(&generated)), words);
+    assert_eq!(read(&written), words);
+}
+
+#[test]
+fn sample_top() {
+    let dir = temp_dir("sample-top");
+    let dict = generate_sample(&dir);
+    // an empty list of used words needs every match, as without a limit
+    let used = dir.join("none.txt");
+    let used = used.to_str().unwrap();
+
+    for sort in ["alpha", "consumed"] {
+        for (offset, limit) in [("0", "20"), ("15", "7"), ("0", "100000"), ("100000", "5")] {
+            let args = [
+                "search",
+                "-q",
+                "-d",
+                &dict,
+                "aeinrst??",
+                "--sort",
+                sort,
+                "--offset",
+                offset,
+                "--limit",
+                limit,
+            ];
+            let top = run(&args);
+            let all = run(&[&args[..], &["--used-file", used]].concat());
+            assert_eq!(stdout(&top), stdout(&all), "{} {} {}", sort, offset, limit);
+        }
+    }
+}
+
+#[test]
+fn sample_smoke() {
+    let dir = temp_dir("sample-smoke");
+    let dict = generate_sample(&dir);
+    let words = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/words.txt");
+
+    for args in [
+        vec!["info", "-d", &dict],
+        vec!["export", "-d", &dict, "-f", "csv-counts"],
+        vec!["shortlist", "-d", &dict, "--containing", "q"],
+        vec!["compare", &dict, &dict, "--count", "3"],
+        vec!["search", "-d", &dict, "aeinrst", "--histogram"],
+    ] {
+        let output = run(&args);
+        assert!(!output.stdout.is_empty(), "{:?}", args);
+    }
+
+    run(&["lint", words.to_str().unwrap()]);
+}
+
+#[test]
+fn expand_inflections() {
+    let dir = temp_dir("expand-inflections");
+    let words = dir.join("words.txt");
+    fs::write(&words, "run\nbake\n").unwrap();
+    let dict = dir.join("inflected.dict");
+    let dict = dict.to_str().unwrap();
+
+    let output = run(&[
+        "generate",
+        "-o",
+        dict,
+        "--expand-inflections",
+        words.to_str().unwrap(),
+    ]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Derived 6 inflected words"));
+
+    let output = run(&["search", "-d", dict, "bakesrun", "-s", " "]);
+    assert_eq!(stdout(&output), "bake bakes run runs ");
+
+    let output = run(&[
+        "search",
+        "-d",
+        dict,
+        "bakesrun",
+        "-s",
+        " ",
+        "--exclude-derived",
+    ]);
+    assert_eq!(stdout(&output), "bake run ");
+}
+
+#[test]
+fn quiet() {
+    let dir = temp_dir("quiet");
+    let words = dir.join("words.txt");
+    fs::write(&words, WORDS).unwrap();
+    let dict = dir.join("quiet.dict");
+    let dict = dict.to_str().unwrap();
+
+    let output = run(&["generate", "-q", "-o", dict, words.to_str().unwrap()]);
+    assert!(output.stderr.is_empty());
+
+    let output = run(&["search", "--quiet", "-d", dict, "satin", "-s", " "]);
+    assert_eq!(stdout(&output), "ant ants satin stain tan ");
+    assert!(output.stderr.is_empty());
+
+    // warnings and requested reports are kept
+    let output = run(&["search", "-q", "-d", dict, "satinx", "--report-unusable"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("letter x is unusable"));
+}
+
+#[test]
+fn did_you_mean() {
+    let stderr = |output: Output| String::from_utf8(output.stderr).unwrap();
+
+    let output = stderr(run_failing(&["serach", "retains"]));
+    assert!(output.starts_with("error: unknown subcommand 'serach', did you mean 'search'?\n"));
+    assert!(output.contains("USAGE:"));
+
+    let output = stderr(run_failing(&["search", "--min-lenght", "3", "retains"]));
+    assert!(
+        output.starts_with("error: unknown flag '--min-lenght', did you mean '--min-length'?\n")
+    );
+
+    // global flags too
+    let output = stderr(run_failing(&["info", "--strict-fromat"]));
+    assert!(output.contains("did you mean '--strict-format'?"));
+
+    let output = stderr(run_failing(&["xyzzy"]));
+    assert!(!output.contains("did you mean"));
+    let output = stderr(run_failing(&["search", "--zzzzzz", "retains"]));
+    assert!(!output.contains("did you mean"));
+    assert!(output.contains("USAGE:"));
+}
+
+#[test]
+fn tiers() {
+    let dir = temp_dir("tiers");
+    let words = dir.join("words.txt");
+    fs::write(&words, "ant 0.01\ntan,2e-6\nants\t1e-9\n").unwrap();
+    let dict = dir.join("tiers.dict");
+    let dict = dict.to_str().unwrap();
+
+    run(&[
+        "generate",
+        "-o",
+        dict,
+        "--tier-thresholds",
+        "1e-5,1e-7",
+        words.to_str().unwrap(),
+    ]);
+    let output = run(&["info", "-d", dict]);
+    assert!(stdout(&output).contains("words per tier: [1, 1, 1, 0]\n"));
+
+    let search = |max_tier: &str| {
+        let output = run(&[
+            "search",
+            "-d",
+            dict,
+            "ants",
+            "-s",
+            " ",
+            "--max-tier",
+            max_tier,
+        ]);
+        stdout(&output).to_owned()
+    };
+    assert_eq!(search("0"), "ant ");
+    assert_eq!(search("1"), "ant tan ");
+    assert_eq!(search("3"), "ant ants tan ");
+    assert_eq!(search("intermediate"), "ant tan ");
+
+    // a single tier, by number or by name
+    let output = run(&["search", "-d", dict, "ants", "-s", " ", "--tier", "rare"]);
+    assert_eq!(stdout(&output), "ants ");
+
+    let words = dir.join("named.txt");
+    fs::write(&words, "ant\tcommon\ntan\t1\nants\tRARE\n").unwrap();
+    let named = dir.join("named.dict");
+    let named = named.to_str().unwrap();
+    run(&[
+        "generate",
+        "-o",
+        named,
+        "--tier-column",
+        words.to_str().unwrap(),
+    ]);
+    let output = run(&["search", "-d", named, "ants", "-s", " ", "--tier", "1"]);
+    assert_eq!(stdout(&output), "tan ");
+
+    // unavailable rather than matching nothing
+    let untiered = generate(&dir);
+    let output = run_failing(&["search", "-d", &untiered, "ants", "--max-tier", "1"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--max-tier is unavailable"));
+    let output = run_failing(&["search", "-d", &untiered, "ants", "--tier", "common"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--tier is unavailable"));
+}
+
+#[test]
+fn profile_memory() {
+    let dir = temp_dir("profile-memory");
+    let dict = generate(&dir);
+    let args = ["--profile-memory", "search", "-q", "-d", &dict, "satin"];
+
+    if cfg!(feature = "profile-memory") {
+        let output = run(&args);
+        assert_eq!(stdout(&output), "ant\nants\nsatin\nstain\ntan\n");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.starts_with("Peak heap memory: "), "{}", stderr);
+    } else {
+        let output = run_failing(&args);
+        assert!(String::from_utf8_lossy(&output.stderr).contains("no `profile-memory` feature"));
+    }
+}
+
+#[cfg(feature = "archives")]
+#[test]
+fn archive() {
+    use std::io::Write;
+
+    let dir = temp_dir("archive");
+    let dict = generate(&dir);
+    let bundle = dir.join("bundle.zip");
+    let mut writer = zip::ZipWriter::new(fs::File::create(&bundle).unwrap());
+    writer
+        .start_file("en.dict", zip::write::FileOptions::default())
+        .unwrap();
+    writer.write_all(&fs::read(&dict).unwrap()).unwrap();
+    writer.finish().unwrap();
+
+    let entry = format!("{}:en.dict", bundle.display());
+    let output = run(&["search", "-d", &entry, "satin"]);
+    let expected = run(&["search", "-d", &dict, "satin"]);
+    assert_eq!(output.stdout, expected.stdout);
+    assert!(stdout(&run(&["info", "-d", &entry])).contains("entries: 7"));
+
+    let missing = format!("{}:fr.dict", bundle.display());
+    let output = run_failing(&["search", "-d", &missing, "satin"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("MissingEntry"));
+    // missing entries aren't retried, as missing files
+    assert!(!stderr.contains("retrying"));
+}
+
+#[test]
+fn info_corrupted() {
+    let dir = temp_dir("info-corrupted");
+    let mut word_count = HashMap::new();
+    for (key, word) in [((0, 5), "stank"), ((1, 4), "tank"), ((5, 4), "tank")] {
+        word_count.insert(key, CountSet::from_word(word).unwrap());
+    }
+    let dict = unsafe { Dictionary::from_raw_parts(String::from("stanktank"), word_count) };
+    let path = dir.join("corrupted.dict");
+    format::write_dict(&dict, &mut fs::File::create(&path).unwrap()).unwrap();
+
+    let output = run(&["info", "-d", path.to_str().unwrap()]);
+    assert!(stdout(&output).contains("overlapping entry pairs: 1\n"));
+    assert!(stdout(&output).contains("duplicate entries: 1 words spelled by 2 entries\n"));
+
+    let output = run(&["info", "-d", &generate(&dir)]);
+    assert!(!stdout(&output).contains("overlapping"));
+    assert!(!stdout(&output).contains("duplicate"));
+}
+
+#[test]
+fn tui_fallback() {
+    let dir = temp_dir("tui-fallback");
+    let dict = generate(&dir);
+
+    // without a terminal, the results are printed as usual
+    let output = run(&["search", "--tui", "-d", &dict, "satin"]);
+    let expected = run(&["search", "-d", &dict, "satin"]);
+    assert_eq!(output.stdout, expected.stdout);
+}
+
+#[test]
+fn longest() {
+    let dir = temp_dir("longest");
+    let dict = generate(&dir);
+
+    let output = run(&["search", "--longest", "-d", &dict, "satin"]);
+    assert_eq!(stdout(&output), "satin\n");
+    let output = run(&[
+        "search",
+        "--longest",
+        "-d",
+        &dict,
+        "satin",
+        "-r",
+        "q",
+        "--force",
+    ]);
+    assert_eq!(stdout(&output), "");
+    // a single word, so no limit is needed
+    let output = run(&["search", "--longest", "--invert", "-d", &dict, "satin"]);
+    assert_eq!(stdout(&output), "quit\n");
+}
+
+#[test]
+fn conflicts() {
+    let dir = temp_dir("conflicts");
+    let dict = generate(&dir);
+
+    let output = run_failing(&["search", "-d", &dict, "satin", "-r", "q", "-M", "2"]);
+    assert_eq!(stdout(&output), "");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(
+            "no word can match: the minimum length 3 is above the maximum length 2; \
+             the required letters need q more than the rack has (search anyway with --force)"
+        ),
+        "{}",
+        stderr
+    );
+
+    let output = run(&["search", "-d", &dict, "satin", "--pattern", "__", "--force"]);
+    assert_eq!(stdout(&output), "");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("No word can match: the pattern matches words of 2 letters"));
+}
+
+#[test]
+fn pick() {
+    let dir = temp_dir("pick");
+    let dict = generate(&dir);
+
+    let output = run(&["pick", "-d", &dict, "--length", "5", "--seed", "3"]);
+    assert!(["stain\n", "satin\n"].contains(&stdout(&output)));
+    let again = run(&["pick", "-d", &dict, "--length", "5", "--seed", "3"]);
+    assert_eq!(again.stdout, output.stdout);
+    let output = run(&["pick", "-d", &dict, "-l", "4"]);
+    assert!(["ants\n", "quit\n"].contains(&stdout(&output)));
+
+    let output = run_failing(&["pick", "-d", &dict, "--length", "9"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no words of length 9"));
+}
+
+#[test]
+fn diff() {
+    let dir = temp_dir("diff");
+    let old = generate(&dir);
+    let words = dir.join("new.txt");
+    fs::write(&words, "stain\nsaint\nant\ntan\nquit\nzoo\nzoos\n").unwrap();
+    let new = dir.join("new.dict");
+    let new = new.to_str().unwrap();
+    run(&["generate", "-q", "-o", new, words.to_str().unwrap()]);
+
+    let output = run(&["diff", &old, new]);
+    assert_eq!(
+        stdout(&output),
+        "=== added (2) ===\nsaint\nzoos\n=== removed (2) ===\nants\nsatin\n"
+    );
+    let output = run(&["diff", &old, &old]);
+    assert_eq!(stdout(&output), "=== added (0) ===\n=== removed (0) ===\n");
+}
+
+#[test]
+fn fallback() {
+    let dir = temp_dir("fallback");
+    let fallback = generate(&dir);
+    let words = dir.join("primary.txt");
+    fs::write(&words, "STAIN\nant\n").unwrap();
+    let primary = dir.join("primary.dict");
+    let primary = primary.to_str().unwrap();
+    run(&["generate", "-q", "-o", primary, words.to_str().unwrap()]);
+
+    let search = [
+        "search",
+        "-q",
+        "-d",
+        primary,
+        "--fallback",
+        &fallback,
+        "satin",
+    ];
+    let output = run(&search);
+    assert_eq!(
+        stdout(&output),
+        "STAIN\nant\n=== fallback ===\nants\nsatin\ntan\n"
+    );
+
+    // pages run on from the main results to the fallback ones
+    let output = run(&[&search[..], &["--offset", "1", "--limit", "2"]].concat());
+    assert_eq!(stdout(&output), "ant\n=== fallback ===\nants\n");
+
+    let output = run(&[&search[..], &["--no-fallback-results"]].concat());
+    assert_eq!(stdout(&output), "STAIN\nant\n");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "3 more words in the fallback dictionary\n"
+    );
+}
+
+#[test]
+fn print0() {
+    let dir = temp_dir("print0");
+    let dict = generate(&dir);
+
+    let output = run(&["search", "-q", "-d", &dict, "--print0", "satin"]);
+    let words = output
+        .stdout
+        .split(|&b| b == 0)
+        .map(|word| std::str::from_utf8(word).unwrap())
+        .collect::<Vec<_>>();
+    // every word is terminated, so the last field is empty
+    assert_eq!(words, ["ant", "ants", "satin", "stain", "tan", ""]);
+
+    run_failing(&["search", "-d", &dict, "--print0", "-s", ",", "satin"]);
+}
+
+#[test]
+fn used_words() {
+    let dir = temp_dir("used");
+    let dict = generate(&dir);
+    let used = dir.join("used.txt");
+    let used = used.to_str().unwrap();
+
+    run(&["used", used, "Stain", "ant"]);
+    // edited by hand between two invocations
+    let mut list = fs::read_to_string(used).unwrap();
+    list.push_str("tan\n");
+    fs::write(used, list).unwrap();
+    run(&["used", used, "ant"]);
+    assert_eq!(stdout(&run(&["used", used])), "ant\nstain\ntan\n");
+    run_failing(&["used", used, "st4in"]);
+
+    let output = run(&["search", "-d", &dict, "--used-file", used, "satin"]);
+    assert_eq!(stdout(&output), "ants\nsatin\n");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Left out 3 used words"));
+
+    run(&["used", used, "--clear"]);
+    assert_eq!(stdout(&run(&["used", used])), "");
+    let output = run(&["search", "-q", "-d", &dict, "--used-file", used, "satin"]);
+    assert_eq!(stdout(&output), "ant\nants\nsatin\nstain\ntan\n");
+}
+
+#[test]
+fn exclude_dictionary() {
+    let dir = temp_dir("exclude-dictionary");
+    let dict = generate(&dir);
+    // ignoring case, as with the used words
+    let known = ["Stain", "TAN", "zoo"].iter().collect::<Dictionary>();
+    let mut bytes = Vec::new();
+    format::write_dict(&known, &mut bytes).unwrap();
+    let known = dir.join("known.dict");
+    fs::write(&known, bytes).unwrap();
+    let known = known.to_str().unwrap();
+
+    let output = run(&[
+        "search",
+        "-d",
+        &dict,
+        "--exclude-dictionary",
+        known,
+        "satin",
+    ]);
+    assert_eq!(stdout(&output), "ant\nants\nsatin\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Left out 2 words of the exclude dictionary"));
+
+    // along with the used words, and past a limit
+    let used = dir.join("used.txt");
+    fs::write(&used, "ant\n").unwrap();
+    let output = run(&[
+        "search",
+        "-q",
+        "-d",
+        &dict,
+        "--exclude-dictionary",
+        known,
+        "--used-file",
+        used.to_str().unwrap(),
+        "--limit",
+        "1",
+        "satin",
+    ]);
+    assert_eq!(stdout(&output), "ants\n");
+
+    // and whichever of the two dictionaries has the capitals
+    let main = ["Stain", "ant"].iter().collect::<Dictionary>();
+    let mut bytes = Vec::new();
+    format::write_dict(&main, &mut bytes).unwrap();
+    let main = dir.join("main.dict");
+    fs::write(&main, bytes).unwrap();
+    let output = run(&[
+        "search",
+        "-q",
+        "-d",
+        main.to_str().unwrap(),
+        "--exclude-dictionary",
+        &dict,
+        "satin",
+    ]);
+    assert_eq!(stdout(&output), "");
+}
+
+#[test]
+fn prioritize() {
+    let dir = temp_dir("prioritize");
+    let dict = generate(&dir);
+
+    let output = run(&["search", "-q", "-d", &dict, "--prioritize", "S", "satin"]);
+    assert_eq!(stdout(&output), "ants\nsatin\nstain\nant\ntan\n");
+    run_failing(&[
+        "search",
+        "-d",
+        &dict,
+        "--prioritize",
+        "s",
+        "--sort",
+        "consumed",
+        "satin",
+    ]);
+}
+
+#[test]
+fn sort_score() {
+    let dir = temp_dir("sort-score");
+    let dict = generate(&dir);
+    let search = |extra: &[&str]| {
+        let args = [
+            &["search", "-q", "-d", &dict, "satin?u?", "--sort", "score"][..],
+            extra,
+        ]
+        .concat();
+        stdout(&run(&args)).to_owned()
+    };
+
+    // quit is made with blanks, which score nothing
+    assert_eq!(search(&[]), "satin\nstain\nants\nant\nquit\ntan\n");
+    assert_eq!(search(&["--limit", "2", "--offset", "1"]), "stain\nants\n");
+    assert_eq!(
+        search(&["--scorer", "rarity"]),
+        "quit\nsatin\nstain\nants\nant\ntan\n"
+    );
+    assert_eq!(
+        search(&["--score-expr", "blanks*10 + len"]),
+        "quit\nsatin\nstain\nants\nant\ntan\n"
+    );
+
+    let stderr = |extra: &[&str]| {
+        let args = [&["search", "-d", &dict, "satin"][..], extra].concat();
+        String::from_utf8(run_failing(&args).stderr).unwrap()
+    };
+    assert!(stderr(&["--sort", "score", "--scorer", "scrabbel"])
+        .contains("unknown scorer scrabbel (expected scrabble or rarity)"));
+    assert!(stderr(&["--sort", "score", "--score-expr", "len * lenght"])
+        .contains("unknown identifier lenght"));
+    assert!(stderr(&["--scorer", "rarity"]).contains("only apply to --sort score"));
+    stderr(&[
+        "--sort",
+        "score",
+        "--scorer",
+        "rarity",
+        "--score-expr",
+        "len",
+    ]);
+}
+
+#[test]
+fn verbose() {
+    let dir = temp_dir("verbose");
+    let dict = generate(&dir);
+    let words = dir.join("words.txt");
+    let words = words.to_str().unwrap();
+
+    let output = run(&["generate", "-v", "-o", &dict, words]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("Reserved room for 7 words of 27 bytes, used by 7 words of 27 bytes"),
+        "{}",
+        stderr
+    );
+    run_failing(&["generate", "-v", "-q", "-o", &dict, words]);
+}
+
+#[test]
+fn validate() {
+    let dir = temp_dir("validate");
+    let dict = generate(&dir);
+    let validate = |extra: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_word-puzzle-searcher"))
+            .args([&["validate", "-d", &dict][..], extra].concat())
+            .output()
+            .unwrap()
+    };
+
+    let output = validate(&[]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "errors: 0, warnings: 0\n");
+
+    // data left after the dictionary is a warning, failing only on warnings
+    let mut bytes = fs::read(&dict).unwrap();
+    bytes.extend_from_slice(b"junk");
+    fs::write(&dict, &bytes).unwrap();
+    let output = validate(&[]);
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        "warning[trailing_bytes]: 4 bytes follow the end of the dictionary\n\
+         errors: 0, warnings: 1\n"
+    );
+    let output = validate(&["--fail-on", "warning", "--format", "json"]);
+    assert_eq!(output.status.code(), Some(2));
+    let json = stdout(&output);
+    assert!(json.starts_with(
+        "{\"version\":1,\"format_version\":2,\"findings\":[{\"rule_id\":\"trailing_bytes\",\
+         \"severity\":\"warning\",\"entry\":null,\"offset\":null,"
+    ));
+    assert!(json.ends_with("\"trailing_bytes\":1}}}\n"), "{}", json);
+
+    // cut short
+    fs::write(&dict, &bytes[..20]).unwrap();
+    let output = validate(&[]);
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        stdout(&output),
+        "error[unreadable]: the file ends early\nerrors: 1, warnings: 0\n"
+    );
+}
+
+#[test]
+fn generate_diff() {
+    let dir = temp_dir("generate-diff");
+    let dict = generate(&dir);
+    let words = dir.join("new.txt");
+    let mut list = (0..12)
+        .map(|i| format!("x{}\n", "i".repeat(i + 1)))
+        .collect::<String>();
+    list.push_str("stain\nsatin\nant\n");
+    fs::write(&words, list).unwrap();
+    let words = words.to_str().unwrap();
+
+    // regenerating in place diffs against the file being replaced
+    let output = run(&[
+        "generate",
+        "-q",
+        "-o",
+        &dict,
+        words,
+        "--diff-against",
+        &dict,
+    ]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.starts_with("=== added (12) ===\nxi\nxii\nxiii\n"));
+    assert!(stderr.ends_with("... and 2 more\n=== removed (4) ===\nants\nquit\ntan\nzoo\n"));
+
+    let diff_file = dir.join("diff.txt");
+    let output = run(&[
+        "generate",
+        "-q",
+        "-o",
+        &dir.join("other.dict").to_string_lossy(),
+        words,
+        "--diff-against",
+        &dict,
+        "--diff-full",
+        "--diff-output",
+        diff_file.to_str().unwrap(),
+    ]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        fs::read_to_string(&diff_file).unwrap()
+    );
+    assert_eq!(
+        fs::read_to_string(&diff_file).unwrap(),
+        "=== added (0) ===\n=== removed (0) ===\n"
+    );
+}
+
+#[test]
+fn hooks() {
+    let dir = temp_dir("hooks");
+    let dict = generate(&dir);
+
+    let output = run(&["hooks", "-d", &dict, "ant"]);
+    assert_eq!(
+        stdout(&output),
+        "=== front (0) ===\n=== back (1) ===\nants\n"
+    );
+    let output = run(&["hooks", "-d", &dict, "an"]);
+    assert_eq!(
+        stdout(&output),
+        "=== front (1) ===\ntan\n=== back (1) ===\nant\n"
+    );
+    run_failing(&["hooks", "-d", &dict, "an't"]);
+
+    // the same through the sorted index
+    let words = dir.join("words.txt");
+    run(&[
+        "generate",
+        "--sorted-index",
+        "-o",
+        &dict,
+        words.to_str().unwrap(),
+    ]);
+    assert!(Path::new(&format!("{}.sidx", dict)).exists());
+    let output = run(&["hooks", "-d", &dict, "an"]);
+    assert_eq!(
+        stdout(&output),
+        "=== front (1) ===\ntan\n=== back (1) ===\nant\n"
+    );
+}
+
+#[test]
+fn counts() {
+    let dir = temp_dir("counts");
+    let dict = generate(&dir);
+
+    for (letters, counts) in [("satin", "s1,a1,t1,i1,n1"), ("ZOOTAN", "o2,z1,t1,a1,n1")] {
+        let from_letters = run(&["search", "-q", "-d", &dict, letters]);
+        let from_counts = run(&["search", "-q", "-d", &dict, "--counts", counts]);
+        assert!(!stdout(&from_letters).is_empty());
+        assert_eq!(stdout(&from_counts), stdout(&from_letters));
+    }
+
+    run_failing(&["search", "-d", &dict, "--counts", "s1", "satin"]);
+    run_failing(&["search", "-d", &dict, "--counts", "s1,a"]);
+    run_failing(&["search", "-d", &dict]);
+}
+
+#[test]
+fn unlimited_repeats() {
+    let dir = temp_dir("unlimited-repeats");
+    let dict = generate(&dir);
+
+    assert_eq!(stdout(&run(&["search", "-q", "-d", &dict, "zot"])), "");
+    let output = run(&["search", "-q", "-d", &dict, "zot", "--unlimited-repeats"]);
+    assert_eq!(stdout(&output), "zoo\n");
+}
+
+#[test]
+fn multiplicity() {
+    let dir = temp_dir("multiplicity");
+    let dict = generate(&dir);
+    let search = |extra: &[&str]| {
+        let args = [&["search", "-q", "-d", &dict, "aattnnsss?"][..], extra].concat();
+        stdout(&run(&args)).to_owned()
+    };
+
+    assert_eq!(search(&["--multiplicity", "2"]), "ant\nants\ntan\n");
+    assert_eq!(search(&["--multiplicity", "3"]), "");
+    assert_eq!(
+        search(&["--show-multiplicity", "--min-use", "4"]),
+        "ants [x2]\nsatin [x0]\nstain [x0]\n"
+    );
+}
+
+#[test]
+fn default_dictionary() {
+    let dir = temp_dir("default-dictionary");
+    let in_dir = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_word-puzzle-searcher"))
+            .args(args)
+            .current_dir(&dir)
+            .output()
+            .unwrap()
+    };
+
+    let output = in_dir(&["search", "ants"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("there's no default.dict"));
+
+    fs::write(dir.join("words.txt"), WORDS).unwrap();
+    assert!(in_dir(&["generate", "words.txt"]).status.success());
+    let output = in_dir(&["search", "-q", "ants", "-s", " "]);
+    assert_eq!(stdout(&output), "ant ants tan ");
+
+    let output = in_dir(&["search", "--no-default-dictionary", "ants"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no dictionary file given"));
+    let output = in_dir(&["info", "--no-default-dictionary", "-d", "default.dict"]);
+    assert!(output.status.success());
+}
+
+#[test]
+fn batch() {
+    let dir = temp_dir("batch");
+    let dict = generate(&dir);
+    let racks = dir.join("racks.txt");
+    fs::write(&racks, "ants\n\nzz1\n  \r\nsatin\nt@n\n").unwrap();
+    let racks = racks.to_str().unwrap();
+
+    // some racks failed: the others are still listed, in order
+    let output = Command::new(env!("CARGO_BIN_EXE_word-puzzle-searcher"))
+        .args(["search", "-q", "-d", &dict, "--batch", racks, "-s", " "])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        stdout(&output),
+        "=== ants ===\nant ants tan \n=== satin ===\nant ants satin stain tan \n"
+    );
+    let errors = String::from_utf8_lossy(&output.stderr);
+    let errors = errors.lines().collect::<Vec<_>>();
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].starts_with("{\"line\":3,\"rack\":\"zz1\",\"error\":"));
+    assert!(errors[1].starts_with("{\"line\":6,\"rack\":\"t@n\",\"error\":"));
+
+    // the same query for every rack
+    let valid = dir.join("valid.txt");
+    fs::write(&valid, "ants\nsatin\n").unwrap();
+    let output = run(&[
+        "search",
+        "-q",
+        "-d",
+        &dict,
+        "--batch",
+        valid.to_str().unwrap(),
+        "--limit",
+        "1",
+        "--sort",
+        "consumed",
+    ]);
+    assert_eq!(
+        stdout(&output),
+        "=== ants ===\nants\n=== satin ===\nsatin\n"
+    );
+
+    let failing = dir.join("failing.txt");
+    fs::write(&failing, "1\n").unwrap();
+    let output = run_failing(&["search", "-d", &dict, "--batch", failing.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("every rack of the batch failed"));
+}
+
+#[test]
+fn collapse_variants() {
+    let dir = temp_dir("collapse-variants");
+    let words = dir.join("words.txt");
+    fs::write(&words, "walk\nwalks\nwalked\nwalking\nlawn\n").unwrap();
+    let dict = dir.join("variants.dict");
+    let dict = dict.to_str().unwrap();
+    run(&["generate", "-o", dict, words.to_str().unwrap()]);
+    let search = |extra: &[&str]| {
+        let args = [&["search", "-q", "-d", dict, "walkedings"][..], extra].concat();
+        stdout(&run(&args)).to_owned()
+    };
+
+    assert_eq!(search(&[]), "lawn\nwalk\nwalked\nwalking\nwalks\n");
+    assert_eq!(search(&["--collapse-variants"]), "lawn\nwalk [+3]\n");
+    assert_eq!(
+        search(&["--collapse-variants", "--show-variants"]),
+        "lawn\nwalk [walked walking walks]\n"
+    );
+    // the pages are of the collapsed list
+    assert_eq!(
+        search(&["--collapse-variants", "--offset", "1", "--limit", "1"]),
+        "walk [+3]\n"
+    );
+}
+
+#[test]
+fn template() {
+    let dir = temp_dir("template");
+    let (red, blue) = (dir.join("red.txt"), dir.join("blue.txt"));
+    fs::write(&red, "ant\ntan\n").unwrap();
+    fs::write(&blue, "ants\n").unwrap();
+    let dict = dir.join("sources.dict");
+    let dict = dict.to_str().unwrap();
+    run(&[
+        "generate",
+        "-o",
+        dict,
+        "--source",
+        &format!("red:{}", red.to_str().unwrap()),
+        "--source",
+        &format!("blue:{}", blue.to_str().unwrap()),
+    ]);
+    let search = |extra: &[&str]| {
+        let args = [&["search", "-q", "-d", dict, "ants"][..], extra].concat();
+        stdout(&run(&args)).to_owned()
+    };
+
+    assert_eq!(
+        search(&[
+            "--template",
+            r"<word>\t<length>\t<score>\t[<remaining>]\t<sources>"
+        ]),
+        "ant\t3\t3\t[s]\tred\nants\t4\t4\t[]\tblue\ntan\t3\t3\t[s]\tred\n"
+    );
+    // the separator follows each word, and headers stay on their own lines
+    // whether the template or the separator ends them
+    assert_eq!(
+        search(&[
+            "--group-by-length",
+            "-s",
+            "",
+            "--template",
+            r"<word>:<score>\n"
+        ]),
+        "=== 3 letters ===\nant:3\ntan:3\n=== 4 letters ===\nants:4\n"
+    );
+    assert_eq!(
+        search(&["--group-by-length", "-s", " ", "--template", r"\<<word>\>"]),
+        "=== 3 letters ===\n<ant> <tan> \n=== 4 letters ===\n<ants> \n"
+    );
+
+    let output = run_failing(&["search", "-d", dict, "ants", "--template", "<len>"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains(
+        "unknown placeholder <len> (expected one of <word>, <length>, <score>, \
+         <remaining>, <sources>, <tier>)"
+    ));
+    let output = run_failing(&["search", "-d", dict, "ants", "--template", "<tier>"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("<tier> is unavailable"));
+}
+
+#[test]
+fn history() {
+    let dir = temp_dir("history");
+    let dict = generate(&dir);
+    let log = dir.join("history.jsonl");
+    let log = log.to_str().unwrap();
+    let search = |letters: &str| {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_word-puzzle-searcher"));
+        command
+            .args(["search", "-q", "-d", &dict, "--history", log, letters])
+            .env_remove("WPS_NO_HISTORY")
+            .stdout(Stdio::null());
+        command
+    };
+
+    // searches running at the same time append whole lines
+    let children = (0..16)
+        .map(|i| {
+            search(if i % 2 == 0 { "satin" } else { "zoo?" })
+                .spawn()
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    for mut child in children {
+        assert!(child.wait().unwrap().success());
+    }
+    let status = search("quit").env("WPS_NO_HISTORY", "1").status().unwrap();
+    assert!(status.success());
+
+    let lines = fs::read_to_string(log).unwrap();
+    assert_eq!(lines.lines().count(), 16);
+    for line in lines.lines() {
+        assert!(line.starts_with("{\"timestamp\":"), "{}", line);
+        assert!(line.ends_with(",\"results\":5}") || line.ends_with(",\"results\":1}"));
+    }
+
+    let output = run(&["history", log, "--rack", "TINAS"]);
+    let listed = stdout(&output).lines().collect::<Vec<_>>();
+    assert_eq!(listed.len(), 8);
+    assert!(listed
+        .iter()
+        .all(|line| line.contains("     5  rack=ainst;")));
+    let output = run(&["history", log, "--raw", "--rack", "?ozo"]);
+    assert_eq!(stdout(&output).lines().count(), 8);
+    let output = run(&["history", log, "--until", "1970-01-31"]);
+    assert_eq!(stdout(&output), "");
+    let output = run(&["history", log, "--since", "1970-01-31"]);
+    assert_eq!(stdout(&output).lines().count(), 16);
+    run_failing(&["history", log, "--since", "2026-02-30"]);
+}
+
+#[test]
+fn external_sort() {
+    let dir = temp_dir("external-sort");
+    let dict = generate(&dir);
+    let search = |extra: &[&str]| {
+        let args = [&["search", "-d", &dict, "satin"][..], extra].concat();
+        let output = run(&args);
+        (
+            stdout(&output).to_owned(),
+            String::from_utf8_lossy(&output.stderr).contains("temporary files"),
+        )
+    };
+
+    let (expected, _) = search(&[]);
+    assert_eq!(search(&["--external-sort=0"]), (expected.clone(), true));
+    // too few matches to bother
+    assert_eq!(search(&["--external-sort"]), (expected.clone(), false));
+    assert_eq!(
+        search(&["--external-sort=0", "--offset", "1", "--limit", "2"]),
+        (String::from("ants\nsatin\n"), true)
+    );
+
+    // other orders fail even with too few matches to sort externally
+    for (threshold, sort) in [("=0", "consumed"), ("", "consumed"), ("", "score")] {
+        let output = run_failing(&[
+            "search",
+            "-d",
+            &dict,
+            &format!("--external-sort{}", threshold),
+            "--sort",
+            sort,
+            "satin",
+        ]);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stderr.contains("only lists words alphabetically"),
+            "{}",
+            stderr
+        );
+    }
+    // the rack after the flag isn't taken for its number
+    let output = run(&["search", "-d", &dict, "--external-sort", "satin"]);
+    assert_eq!(stdout(&output), expected);
+}
+
+#[test]
+fn dictionary_glob() {
+    let dir = temp_dir("dictionary-glob");
+    let dicts = dir.join("dicts");
+    fs::create_dir(&dicts).unwrap();
+    fs::rename(generate(&dir), dicts.join("en.dict")).unwrap();
+    let fr = ["nait", "tas", "ant"].iter().collect::<Dictionary>();
+    let mut bytes = Vec::new();
+    format::write_dict(&fr, &mut bytes).unwrap();
+    fs::write(dicts.join("fr.dict"), &bytes).unwrap();
+    fs::write(dicts.join("broken.dict"), b"DICT\x01\0\0\0garbage").unwrap();
+    fs::write(dicts.join("notes.txt"), "not a dictionary").unwrap();
+    let pattern = dicts.join("*.dict");
+    let pattern = pattern.to_str().unwrap();
+
+    // the broken file is only skipped, and the words are tagged with the
+    // stem of their file, the first one having them
+    let output = run(&[
+        "search",
+        "-d",
+        pattern,
+        "satin",
+        "--template",
+        "<word> <sources>",
+    ]);
+    assert_eq!(
+        stdout(&output),
+        "ant en\nants en\nnait fr\nsatin en\nstain en\ntan en\ntas fr\n"
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("skipping") && stderr.contains("broken.dict"));
+    let output = run(&["search", "-d", pattern, "satin", "--from-source", "fr"]);
+    assert_eq!(stdout(&output), "nait\ntas\n");
+
+    let output = run_failing(&["search", "-d", pattern, "satin", "--strict"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("broken.dict"));
+
+    let missing = dir.join("missing/*.dict");
+    let output = run_failing(&["search", "-d", missing.to_str().unwrap(), "satin"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no dictionary file matches"));
+
+    // file names needn't be UTF-8
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        let name = std::ffi::OsStr::from_bytes(b"\xffde.dict");
+        fs::write(dicts.join(name), &bytes).unwrap();
+        let pattern = dicts.join("?de.dict");
+        let output = run(&[
+            "search",
+            "-d",
+            pattern.to_str().unwrap(),
+            "satin",
+            "--template",
+            "<word> <sources>",
+        ]);
+        assert_eq!(
+            stdout(&output),
+            "ant \u{fffd}de\nnait \u{fffd}de\ntas \u{fffd}de\n"
+        );
+    }
+}
+
+#[test]
+fn dictionary_glob_options() {
+    let dir = temp_dir("dictionary-glob-options");
+    let generate = |name: &str, words: &str, extra: &[&str]| {
+        let list = dir.join(format!("{}.txt", name));
+        fs::write(&list, words).unwrap();
+        let dict = dir.join(format!("{}.dict", name));
+        let args = [
+            &["generate", "-q", "-o", dict.to_str().unwrap()][..],
+            extra,
+            &[list.to_str().unwrap()],
+        ];
+        run(&args.concat());
+    };
+    generate(
+        "en",
+        "stain 0\nant 2\n",
+        &["--tier-column", "--default-min-length", "4"],
+    );
+    generate(
+        "fr",
+        "satin 1\ntan 0\n",
+        &["--tier-column", "--default-min-length", "4"],
+    );
+    let pattern = dir.join("*.dict");
+    let pattern = pattern.to_str().unwrap();
+
+    // the tiers and the defaults the files share carry over
+    let output = run(&["search", "-q", "-d", pattern, "satin", "--max-tier", "1"]);
+    assert_eq!(stdout(&output), "satin\nstain\n");
+    let output = run(&["search", "-q", "-d", pattern, "satin", "--tier", "0"]);
+    assert_eq!(stdout(&output), "stain\n");
+
+    // differing defaults need the option from the command line
+    generate("de", "ants\n", &[]);
+    let output = run_failing(&["search", "-d", pattern, "satin"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("pass --min-length"));
+    let output = run(&["search", "-q", "-d", pattern, "satin", "--min-length", "3"]);
+    assert_eq!(stdout(&output), "ant\nants\nsatin\nstain\ntan\n");
+
+    let fallback = dir.join("en.dict");
+    run_failing(&[
+        "search",
+        "-d",
+        pattern,
+        "satin",
+        "--fallback",
+        fallback.to_str().unwrap(),
+    ]);
+}
+
+#[test]
+fn retry_reading() {
+    let dir = temp_dir("retry-reading");
+    let dict = generate(&dir);
+    let retried = |dict: &str| {
+        let output = run_failing(&["search", "-d", dict, "satin"]);
+        String::from_utf8_lossy(&output.stderr).contains("retrying")
+    };
+
+    // a truncated file may be one being replaced
+    let bytes = fs::read(&dict).unwrap();
+    fs::write(&dict, &bytes[..(bytes.len() / 2)]).unwrap();
+    assert!(retried(&dict));
+
+    // and so may the header of one file on the body of another: its
+    // lengths don't fit the rest of the file
+    let other = ["stain", "ant"].iter().collect::<Dictionary>();
+    let mut other_bytes = Vec::new();
+    format::write_dict(&other, &mut other_bytes).unwrap();
+    // the magic and version, then the word count and word string length
+    let header = 8 + 2 * std::mem::size_of::<usize>();
+    let mixed = [&bytes[..header], &other_bytes[header..]].concat();
+    fs::write(&dict, mixed).unwrap();
+    assert!(retried(&dict));
+
+    // a missing one stays missing
+    assert!(!retried(dir.join("missing.dict").to_str().unwrap()));
+
+    // generating over a file replaces it whole, leaving no temporary file
+    let words = dir.join("words.txt");
+    run(&[
+        "generate",
+        "--sorted-index",
+        "-o",
+        &dict,
+        words.to_str().unwrap(),
+    ]);
+    run(&["search", "-d", &dict, "satin"]);
+    let mut files = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect::<Vec<_>>();
+    files.sort();
+    assert_eq!(files, ["test.dict", "test.dict.sidx", "words.txt"]);
+}